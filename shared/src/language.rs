@@ -43,6 +43,15 @@ pub struct Language {
     pub(crate) tree_sitter_grammar_config: Option<GrammarConfig>,
     pub(crate) highlight_query: Option<&'static str>,
     pub(crate) formatter_command: Option<Command>,
+    /// The number of spaces of one indentation level.
+    pub(crate) indent_width: usize,
+    /// Characters that are part of a word besides letters, digits and `_`,
+    /// for example `$` in JavaScript identifiers.
+    pub(crate) word_chars: &'static str,
+    /// The kinds of tree-sitter nodes that are import statements, for example `use_declaration` in Rust.
+    pub(crate) import_node_kinds: &'static [&'static str],
+    /// The token that starts a line comment, for example `//` in Rust.
+    pub(crate) line_comment: Option<&'static str>,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -69,6 +78,10 @@ impl Language {
             lsp_command: None,
             tree_sitter_grammar_config: None,
             formatter_command: None,
+            indent_width: 4,
+            word_chars: "-",
+            import_node_kinds: &[],
+            line_comment: None,
         }
     }
 
@@ -92,7 +105,7 @@ pub struct GrammarConfig {
 }
 
 impl Language {
-    fn extensions(&self) -> &'static [&'static str] {
+    pub fn extensions(&self) -> &'static [&'static str] {
         self.extensions
     }
 
@@ -166,6 +179,22 @@ impl Language {
             .map(|command| ProcessCommand::new(command.0, command.1))
     }
 
+    pub fn indent_width(&self) -> usize {
+        self.indent_width
+    }
+
+    pub fn word_chars(&self) -> &'static str {
+        self.word_chars
+    }
+
+    pub fn import_node_kinds(&self) -> &'static [&'static str] {
+        self.import_node_kinds
+    }
+
+    pub fn line_comment(&self) -> Option<&'static str> {
+        self.line_comment
+    }
+
     pub fn formatter(&self) -> Option<Formatter> {
         self.formatter_command().map(Formatter::from)
     }
@@ -6,6 +6,7 @@ pub const LANGUAGES: &[&Language] = &[
     &csv(),
     &dockerfile(),
     &graphql(),
+    &html(),
     &javascript(true),
     &javascript(false),
     &just(),
@@ -18,6 +19,7 @@ pub const LANGUAGES: &[&Language] = &[
     &tree_sitter_query(),
     &typescript(false),
     &typescript(true),
+    &xml(),
     &yaml(),
 ];
 
@@ -35,6 +37,10 @@ const fn common_lisp() -> Language {
         }),
         highlight_query: None,
         formatter_command: None,
+        indent_width: 2,
+        word_chars: "-*+!?<>=/",
+        import_node_kinds: &[],
+        line_comment: Some(";"),
     }
 }
 const fn csv() -> Language {
@@ -45,12 +51,16 @@ const fn csv() -> Language {
         lsp_command: None,
         highlight_query: None,
         formatter_command: None,
+        indent_width: 4,
+        word_chars: "-",
+        import_node_kinds: &[],
         tree_sitter_grammar_config: Some(GrammarConfig {
             id: "csv",
             url: "https://github.com/arnau/tree-sitter-csv",
             commit: "main",
             subpath: None,
         }),
+        line_comment: None,
     }
 }
 
@@ -62,12 +72,16 @@ const fn css() -> Language {
         lsp_command: None,
         highlight_query: None,
         formatter_command: Some(Command("prettierd", &[".css"])),
+        indent_width: 2,
+        word_chars: "-",
+        import_node_kinds: &["import_statement"],
         tree_sitter_grammar_config: Some(GrammarConfig {
             id: "css",
             url: "https://github.com/tree-sitter/tree-sitter-css",
             commit: "master",
             subpath: None,
         }),
+        line_comment: None,
     }
 }
 
@@ -79,12 +93,16 @@ const fn dockerfile() -> Language {
         lsp_command: None,
         highlight_query: None,
         formatter_command: None,
+        indent_width: 4,
+        word_chars: "-",
+        import_node_kinds: &[],
         tree_sitter_grammar_config: Some(GrammarConfig {
             id: "dockerfile",
             url: "https://github.com/camdencheek/tree-sitter-dockerfile",
             commit: "main",
             subpath: None,
         }),
+        line_comment: Some("#"),
     }
 }
 
@@ -99,14 +117,36 @@ const fn graphql() -> Language {
             subpath: None,
         }),
         formatter_command: Some(Command("prettierd", &[".graphql"])),
+        indent_width: 2,
         lsp_command: Some(LspCommand {
             command: Command("graphql-lsp", &["server", "-m", "stream"]),
             initialization_options: Some(r#"{ "graphql-config.load.legacy": true }"#),
         }),
+        line_comment: Some("#"),
         ..Language::new()
     }
 }
 
+const fn html() -> Language {
+    Language {
+        lsp_language_id: Some(LanguageId::new("html")),
+        extensions: &["html", "htm"],
+        lsp_command: Some(LspCommand {
+            command: Command("vscode-html-language-server", &["--stdio"]),
+            ..LspCommand::default()
+        }),
+        tree_sitter_grammar_config: Some(GrammarConfig {
+            id: "html",
+            url: "https://github.com/tree-sitter/tree-sitter-html",
+            commit: "master",
+            subpath: None,
+        }),
+        formatter_command: Some(Command("prettierd", &[".html"])),
+        indent_width: 2,
+        word_chars: "-",
+        ..Language::new()
+    }
+}
 const fn javascript(jsx: bool) -> Language {
     Language {
         lsp_language_id: Some(LanguageId::new(if jsx {
@@ -126,6 +166,10 @@ const fn javascript(jsx: bool) -> Language {
             subpath: None,
         }),
         formatter_command: Some(Command("prettierd", if jsx { &[".jsx"] } else { &[".js"] })),
+        indent_width: 2,
+        word_chars: "-$",
+        import_node_kinds: &["import_statement"],
+        line_comment: Some("//"),
         ..Language::new()
     }
 }
@@ -144,6 +188,10 @@ const fn json() -> Language {
         }),
         highlight_query: None,
         formatter_command: Some(Command("prettierd", &[".json"])),
+        indent_width: 2,
+        word_chars: "-",
+        import_node_kinds: &[],
+        line_comment: None,
     }
 }
 
@@ -161,6 +209,10 @@ const fn just() -> Language {
         }),
         highlight_query: None,
         formatter_command: None,
+        indent_width: 4,
+        word_chars: "-",
+        import_node_kinds: &[],
+        line_comment: Some("#"),
     }
 }
 
@@ -179,6 +231,7 @@ const fn markdown() -> Language {
             subpath: Some("tree-sitter-markdown"),
         }),
         formatter_command: Some(Command("prettierd", &[".md"])),
+        indent_width: 2,
         ..Language::new()
     }
 }
@@ -198,6 +251,12 @@ const fn python() -> Language {
             subpath: None,
         }),
         formatter_command: Some(Command("ruff", &["format", "--stdin-filename", ".py"])),
+        import_node_kinds: &[
+            "future_import_statement",
+            "import_statement",
+            "import_from_statement",
+        ],
+        line_comment: Some("#"),
         ..Language::new()
     }
 }
@@ -219,6 +278,10 @@ const fn rust() -> Language {
             subpath: None,
         }),
         formatter_command: Some(Command("rustfmt", &["--edition=2021"])),
+        indent_width: 4,
+        word_chars: "-",
+        import_node_kinds: &["use_declaration", "extern_crate_declaration"],
+        line_comment: Some("//"),
     }
 }
 
@@ -234,6 +297,7 @@ const fn sql() -> Language {
             subpath: None,
         }),
         formatter_command: Some(Command("sql-formatter", &["--language", "postgresql"])),
+        line_comment: Some("--"),
         ..Language::new()
     }
 }
@@ -252,6 +316,10 @@ const fn toml() -> Language {
         }),
         highlight_query: None,
         formatter_command: None,
+        indent_width: 4,
+        word_chars: "-",
+        import_node_kinds: &[],
+        line_comment: Some("#"),
     }
 }
 
@@ -269,6 +337,10 @@ const fn tree_sitter_query() -> Language {
         }),
         highlight_query: None,
         formatter_command: None,
+        indent_width: 4,
+        word_chars: "-",
+        import_node_kinds: &[],
+        line_comment: Some(";"),
     }
 }
 
@@ -299,10 +371,28 @@ const fn typescript(tsx: bool) -> Language {
             subpath: Some(choice(tsx, "tsx", "typescript")),
         }),
         formatter_command: Some(Command("prettierd", choice(tsx, &[".tsx"], &[".ts"]))),
+        indent_width: 2,
+        word_chars: "-$",
+        import_node_kinds: &["import_statement"],
+        line_comment: Some("//"),
         ..Language::new()
     }
 }
 
+const fn xml() -> Language {
+    Language {
+        extensions: &["xml", "svg"],
+        tree_sitter_grammar_config: Some(GrammarConfig {
+            id: "xml",
+            url: "https://github.com/tree-sitter-grammars/tree-sitter-xml",
+            commit: "master",
+            subpath: Some("xml"),
+        }),
+        indent_width: 2,
+        word_chars: "-:.",
+        ..Language::new()
+    }
+}
 const fn yaml() -> Language {
     Language {
         file_names: &[],
@@ -316,6 +406,10 @@ const fn yaml() -> Language {
             commit: "master",
         }),
         formatter_command: None,
+        indent_width: 2,
+        word_chars: "-",
+        import_node_kinds: &[],
         highlight_query: None,
+        line_comment: Some("#"),
     }
 }
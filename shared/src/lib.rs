@@ -4,6 +4,6 @@ pub(crate) mod formatter;
 pub mod grammar;
 pub mod icons;
 pub mod language;
-pub(crate) mod languages;
+pub mod languages;
 pub(crate) mod process_command;
 pub mod ts_highlight_query;
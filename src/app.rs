@@ -14,6 +14,7 @@ use crate::{
         },
     },
     context::{Context, GlobalMode, LocalSearchConfigMode, QuickfixListSource, Search},
+    encoding::Encoding,
     frontend::Frontend,
     git,
     grid::{Grid, LineUpdate},
@@ -31,8 +32,10 @@ use crate::{
     position::Position,
     quickfix_list::{Location, QuickfixList, QuickfixListItem, QuickfixListType},
     screen::{Screen, Window},
+    search_preset::{SearchPreset, SearchPresets},
     selection::{Filter, FilterKind, FilterMechanism, FilterTarget, SelectionMode},
-    syntax_highlight::{HighlighedSpans, SyntaxHighlightRequest},
+    syntax_highlight::{HighlighedSpan, HighlighedSpans, SyntaxHighlightRequest},
+    task::Task,
     ui_tree::{ComponentKind, KindedComponent},
 };
 use event::event::Event;
@@ -42,6 +45,8 @@ use shared::{canonicalized_path::CanonicalizedPath, language::Language};
 use std::{
     any::TypeId,
     cell::RefCell,
+    collections::HashMap,
+    ops::Range,
     path::{Path, PathBuf},
     rc::Rc,
     sync::{
@@ -72,14 +77,23 @@ pub(crate) struct App<T: Frontend> {
     frontend: Arc<Mutex<T>>,
 
     syntax_highlight_request_sender: Option<Sender<SyntaxHighlightRequest>>,
+    /// The latest syntax highlight request of each component,
+    /// responses of other requests are outdated and thus discarded.
+    latest_syntax_highlight_request_ids: HashMap<ComponentId, usize>,
+    syntax_highlight_request_counter: usize,
+    /// The number of scratch editors opened so far, used for numbering their titles.
+    scratch_counter: usize,
+    /// The scratch editor showing the output of each task, by the name of the task,
+    /// so that running a task again replaces its previous output.
+    task_output_editors: HashMap<String, ComponentId>,
 
     /// Used for navigating between opened files
     file_path_history: History<CanonicalizedPath>,
 }
 
 const GLOBAL_TITLE_BAR_HEIGHT: u16 = 1;
+const SCROLL_ANIMATION_FRAME_DURATION: std::time::Duration = std::time::Duration::from_millis(16);
 impl<T: Frontend> App<T> {
-    #[cfg(test)]
     pub(crate) fn new(
         frontend: Arc<Mutex<T>>,
         working_directory: CanonicalizedPath,
@@ -88,7 +102,6 @@ impl<T: Frontend> App<T> {
         Self::from_channel(frontend, working_directory, sender, receiver)
     }
 
-    #[cfg(test)]
     pub(crate) fn disable_lsp(&mut self) {
         self.enable_lsp = false
     }
@@ -113,6 +126,10 @@ impl<T: Frontend> App<T> {
             working_directory,
             frontend,
             syntax_highlight_request_sender: None,
+            latest_syntax_highlight_request_ids: Default::default(),
+            syntax_highlight_request_counter: 0,
+            scratch_counter: 0,
+            task_output_editors: HashMap::new(),
             global_title: None,
 
             file_path_history: History::new(),
@@ -122,10 +139,15 @@ impl<T: Frontend> App<T> {
     fn update_highlighted_spans(
         &self,
         component_id: ComponentId,
+        request_id: usize,
+        byte_range: Range<usize>,
         highlighted_spans: HighlighedSpans,
     ) -> Result<(), anyhow::Error> {
+        if self.latest_syntax_highlight_request_ids.get(&component_id) != Some(&request_id) {
+            return Ok(());
+        }
         self.layout
-            .update_highlighted_spans(component_id, highlighted_spans)
+            .update_highlighted_spans(component_id, byte_range, highlighted_spans)
     }
 
     pub(crate) fn run(
@@ -146,23 +168,7 @@ impl<T: Frontend> App<T> {
         self.render()?;
 
         while let Ok(message) = self.receiver.recv() {
-            match message {
-                AppMessage::Event(event) => self.handle_event(event),
-                AppMessage::LspNotification(notification) => {
-                    self.handle_lsp_notification(notification).map(|_| false)
-                }
-                AppMessage::QuitAll => {
-                    self.quit()?;
-                    Ok(true)
-                }
-                AppMessage::SyntaxHighlightResponse {
-                    component_id,
-                    highlighted_spans,
-                } => self
-                    .update_highlighted_spans(component_id, highlighted_spans)
-                    .map(|_| false),
-            }
-            .unwrap_or_else(|e| {
+            self.handle_app_message(message).unwrap_or_else(|e| {
                 self.show_global_info(Info::new("ERROR".to_string(), e.to_string()));
                 false
             });
@@ -177,6 +183,55 @@ impl<T: Frontend> App<T> {
         self.quit()
     }
 
+    /// Returns true if the app should quit.
+    fn handle_app_message(&mut self, message: AppMessage) -> anyhow::Result<bool> {
+        match message {
+            AppMessage::Event(event) => self.handle_event(event),
+            AppMessage::LspNotification(notification) => {
+                self.handle_lsp_notification(notification).map(|_| false)
+            }
+            AppMessage::QuitAll => {
+                self.quit()?;
+                Ok(true)
+            }
+            AppMessage::SyntaxHighlightResponse {
+                component_id,
+                request_id,
+                byte_range,
+                highlighted_spans,
+            } => self
+                .update_highlighted_spans(component_id, request_id, byte_range, highlighted_spans)
+                .map(|_| false),
+            AppMessage::ScrollAnimationTick(component_id) => {
+                self.advance_scroll_animation(component_id);
+                Ok(false)
+            }
+            AppMessage::RenderTick => Ok(false),
+            AppMessage::ShellOutput {
+                component_id,
+                command,
+                output,
+            } => self
+                .handle_shell_output(component_id, command, output)
+                .map(|_| false),
+            AppMessage::TaskOutput {
+                task,
+                success,
+                output,
+            } => self
+                .handle_task_output(task, success, output)
+                .map(|_| false),
+            AppMessage::FormattedLines {
+                component_id,
+                version,
+                lines,
+                formatted,
+            } => self
+                .handle_formatted_lines(component_id, version, lines, formatted)
+                .map(|_| false),
+        }
+    }
+
     pub(crate) fn quit(&mut self) -> anyhow::Result<()> {
         let mut frontend = self.frontend.lock().unwrap();
         frontend.leave_alternate_screen()?;
@@ -192,6 +247,15 @@ impl<T: Frontend> App<T> {
 
     /// Returns true if the app should quit.
     fn handle_event(&mut self, event: Event) -> anyhow::Result<bool> {
+        self.handle_event_strictly(event).unwrap_or_else(|e| {
+            self.show_global_info(Info::new("ERROR".to_string(), e.to_string()))
+        });
+
+        Ok(false)
+    }
+
+    /// Same as `handle_event`, except that errors are returned instead of being shown.
+    pub(crate) fn handle_event_strictly(&mut self, event: Event) -> anyhow::Result<()> {
         // Pass event to focused window
         let component = self.current_component();
         self.context
@@ -202,21 +266,18 @@ impl<T: Frontend> App<T> {
                     height: rows,
                     width: columns,
                 });
+                Ok(())
             }
             event => {
                 let dispatches = component.borrow_mut().handle_event(&self.context, event);
-                self.handle_dispatches_result(dispatches)
-                    .unwrap_or_else(|e| {
-                        self.show_global_info(Info::new("ERROR".to_string(), e.to_string()))
-                    });
+                self.handle_dispatches_result(dispatches)?;
+                self.handle_dispatch(Dispatch::RequestDocumentHighlight)
             }
         }
-
-        Ok(false)
     }
 
     /// Return true if there's no more windows
-    fn should_quit(&mut self) -> bool {
+    pub(crate) fn should_quit(&mut self) -> bool {
         self.layout.components().is_empty()
     }
 
@@ -280,7 +341,17 @@ impl<T: Frontend> App<T> {
                 .map(|mode| mode.display())
                 .unwrap_or_else(|| self.current_component().borrow().editor().display_mode());
 
-            let mode = format!("[{}]", mode);
+            let encoding = self
+                .current_component()
+                .borrow()
+                .editor()
+                .buffer()
+                .encoding();
+            let mode = if encoding == Encoding::Utf8 {
+                format!("[{}]", mode)
+            } else {
+                format!("[{}] [{}]", mode, encoding.display())
+            };
 
             let title = if let Some(title) = self.global_title.as_ref() {
                 title.clone()
@@ -409,6 +480,17 @@ impl<T: Frontend> App<T> {
                     )?;
                 }
             }
+            Dispatch::ResolveCodeAction(code_action) => {
+                if let Some(params) = self.get_request_params() {
+                    self.lsp_manager.send_message(
+                        params.path.clone(),
+                        FromEditor::CodeActionResolve {
+                            code_action,
+                            params,
+                        },
+                    )?
+                }
+            }
             Dispatch::ResolveCompletionItem(completion_item) => {
                 if let Some(params) = self.get_request_params() {
                     self.lsp_manager.send_message(
@@ -566,6 +648,7 @@ impl<T: Frontend> App<T> {
                 self.set_quickfix_list_type(Default::default(), r#type)?;
             }
             Dispatch::GotoQuickfixListItem(movement) => self.goto_quickfix_list_item(movement)?,
+            Dispatch::QuickfixDo(dispatch_editor) => self.quickfix_do(dispatch_editor)?,
             Dispatch::ApplyWorkspaceEdit(workspace_edit) => {
                 self.apply_workspace_edit(workspace_edit)?;
             }
@@ -579,6 +662,12 @@ impl<T: Frontend> App<T> {
             Dispatch::ToEditor(dispatch_editor) => self.handle_dispatch_editor(dispatch_editor)?,
             Dispatch::GotoLocation(location) => self.go_to_location(&location)?,
             Dispatch::OpenMoveToIndexPrompt => self.open_move_to_index_prompt()?,
+            Dispatch::OpenMoveToColumnPrompt => self.open_move_to_column_prompt()?,
+            Dispatch::OpenJumpToPercentOfLinePrompt => {
+                self.open_jump_to_percent_of_line_prompt()?
+            }
+            Dispatch::OpenGoToByteOffsetPrompt => self.open_go_to_byte_offset_prompt()?,
+            Dispatch::OpenInsertDateTimePrompt => self.open_insert_date_time_prompt()?,
             Dispatch::RunCommand(command) => self.run_command(command)?,
             Dispatch::QuitAll => self.quit_all()?,
             Dispatch::OpenCommandPrompt => self.open_command_prompt()?,
@@ -644,6 +733,17 @@ impl<T: Frontend> App<T> {
                 self.open_update_replacement_prompt(scope)?
             }
             Dispatch::OpenUpdateSearchPrompt { scope } => self.open_update_search_prompt(scope)?,
+            Dispatch::OpenSaveSearchPresetPrompt { scope } => {
+                self.open_save_search_preset_prompt(scope)?
+            }
+            Dispatch::SaveSearchPreset { scope, name } => {
+                self.context.save_search_preset(scope, name)?;
+                self.show_search_config(scope)
+            }
+            Dispatch::OpenSearchPresetPrompt { scope } => self.open_search_preset_prompt(scope)?,
+            Dispatch::ApplySearchPreset { scope, preset } => {
+                self.apply_search_preset(scope, preset)?
+            }
             Dispatch::Replace { scope } => match scope {
                 Scope::Local => self.handle_dispatch_editor(ReplacePattern {
                     config: self.context.local_search_config().clone(),
@@ -667,7 +767,6 @@ impl<T: Frontend> App<T> {
                     self.render_dropdown(dropdown, render)?;
                 }
             }
-            #[cfg(test)]
             Dispatch::OpenPrompt {
                 config,
                 key,
@@ -682,10 +781,231 @@ impl<T: Frontend> App<T> {
             Dispatch::GoToNextFile => self.go_to_next_file()?,
             Dispatch::PushPromptHistory { key, line } => self.push_history_prompt(key, line),
             Dispatch::OpenThemePrompt => self.open_theme_prompt()?,
+            Dispatch::OpenWrapInTagPrompt => self.open_wrap_in_tag_prompt()?,
+            Dispatch::OpenPipeSelectionPrompt => self.open_pipe_selection_prompt()?,
+            Dispatch::OpenInsertShellOutputPrompt => self.open_insert_shell_output_prompt()?,
+            Dispatch::OpenSelectLinesMatchingPrompt { matching } => {
+                self.open_select_lines_matching_prompt(matching)?
+            }
+            Dispatch::OpenSelectByQueryPrompt => self.open_select_by_query_prompt()?,
+            Dispatch::InsertShellOutput {
+                component_id,
+                command,
+            } => self.insert_shell_output(component_id, command),
+            #[cfg(test)]
+            Dispatch::SetSnippets(snippets) => self.set_snippets(snippets),
+            #[cfg(test)]
+            Dispatch::HandleNextAppMessage => {
+                let message = self
+                    .receiver
+                    .recv_timeout(std::time::Duration::from_secs(10))?;
+                self.handle_app_message(message)?;
+            }
+            Dispatch::ToggleSmoothScroll => self.context.toggle_smooth_scroll(),
+            Dispatch::ToggleFileExplorerSidebar => {
+                self.context.toggle_file_explorer_sidebar();
+                self.layout
+                    .set_file_explorer_sidebar(self.context.file_explorer_sidebar())
+            }
+            Dispatch::StartScrollAnimation(component_id) => {
+                self.schedule_scroll_animation_tick(component_id)
+            }
+            Dispatch::ToggleIndentGuides => self.context.toggle_indent_guides(),
+            Dispatch::ToggleGroupImports => self.context.toggle_group_imports(),
+            Dispatch::ToggleValidateStructuralEdits => {
+                self.context.toggle_validate_structural_edits()
+            }
+            Dispatch::ToggleReindentOnPaste => self.context.toggle_reindent_on_paste(),
+            Dispatch::ToggleYankFlash => self.context.toggle_yank_flash(),
+            Dispatch::OpenYankFlashDurationPrompt => self.open_yank_flash_duration_prompt()?,
+            Dispatch::OpenLargeFileThresholdPrompt => self.open_large_file_threshold_prompt()?,
+            Dispatch::OpenIncrementalReparseThresholdPrompt => {
+                self.open_incremental_reparse_threshold_prompt()?
+            }
+            Dispatch::SetDateTimeFormat(format) => self.context.set_date_time_format(format),
+            Dispatch::ToggleDateTimeUtc => self.context.toggle_date_time_utc(),
+            Dispatch::ToggleDocumentHighlight => self.context.toggle_document_highlight(),
+            Dispatch::RequestDocumentHighlight => self.request_document_highlight()?,
+            Dispatch::RequestSelectionRange => self.request_selection_range()?,
+            Dispatch::RequestRangeFormatting(range) => self.request_range_formatting(range)?,
+            Dispatch::FormatLines {
+                component_id,
+                lines,
+            } => self.format_lines(component_id, lines)?,
+            Dispatch::OrganizeImports => self.organize_imports()?,
+            Dispatch::SetYankFlashDuration(duration) => {
+                self.context.set_yank_flash_duration(duration)
+            }
+            Dispatch::SetLargeFileThreshold(bytes) => self.context.set_large_file_threshold(bytes),
+            Dispatch::SetIncrementalReparseThreshold(bytes) => {
+                self.set_incremental_reparse_threshold(bytes)
+            }
+            Dispatch::ScheduleRender(duration) => self.schedule_render(duration),
+            Dispatch::ToggleDefaultCursorDirection(selection_mode) => self
+                .context
+                .toggle_default_cursor_direction(&selection_mode),
+            Dispatch::CycleFinalNewline => self.context.cycle_final_newline(),
+            Dispatch::ListTodos => self.list_todos()?,
+            Dispatch::OpenScratchPrompt => self.open_scratch_prompt()?,
+            Dispatch::OpenScratch { extension } => self.open_scratch(&extension)?,
+            Dispatch::FocusScratch(component_id) => {
+                if let Some(editor) = self.layout.get_scratch_editor(component_id) {
+                    self.layout
+                        .replace_and_focus_current_suggestive_editor(editor)
+                }
+            }
+            Dispatch::OpenSaveScratchAsPrompt(component_id) => {
+                self.open_save_scratch_as_prompt(component_id)?
+            }
+            Dispatch::SaveScratchAs { component_id, path } => {
+                self.save_scratch_as(component_id, path)?
+            }
+            Dispatch::OpenRunTaskPrompt => self.open_run_task_prompt()?,
+            Dispatch::RunTaskCommand(task) => self.run_task_command(task),
+            Dispatch::OpenParseErrorsToQuickfixPrompt => {
+                self.open_parse_errors_to_quickfix_prompt()?
+            }
+            Dispatch::ParseErrorsToQuickfix { pattern } => {
+                self.parse_errors_to_quickfix(pattern)?
+            }
         }
         Ok(())
     }
 
+    fn schedule_scroll_animation_tick(&self, component_id: ComponentId) {
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(SCROLL_ANIMATION_FRAME_DURATION);
+            let _ = sender.send(AppMessage::ScrollAnimationTick(component_id));
+        });
+    }
+
+    fn schedule_render(&self, duration: std::time::Duration) {
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            std::thread::sleep(duration);
+            let _ = sender.send(AppMessage::RenderTick);
+        });
+    }
+
+    /// Run `command` in another thread, so that slow commands do not block the UI,
+    /// its output is inserted when it completes.
+    fn insert_shell_output(&self, component_id: ComponentId, command: String) {
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            let output = crate::utils::run_shell_command(&command, "")
+                .unwrap_or_else(|error| Err(error.to_string()));
+            let _ = sender.send(AppMessage::ShellOutput {
+                component_id,
+                command,
+                output,
+            });
+        });
+    }
+
+    fn handle_shell_output(
+        &mut self,
+        component_id: ComponentId,
+        command: String,
+        output: Result<String, String>,
+    ) -> anyhow::Result<()> {
+        match output {
+            Ok(stdout) => {
+                let component = self.layout.get_suggestive_editor_by_id(component_id)?;
+                // Most commands end their output with a newline, which is rarely wanted
+                let stdout = stdout.strip_suffix('\n').unwrap_or(&stdout);
+                let dispatches = component
+                    .borrow_mut()
+                    .editor_mut()
+                    .insert_at_cursor(stdout)?;
+                self.handle_dispatches(dispatches)
+            }
+            Err(stderr) => {
+                self.show_global_info(Info::new(
+                    format!("`{}` failed", command),
+                    stderr.trim_end().to_string(),
+                ));
+                Ok(())
+            }
+        }
+    }
+
+    /// Run the formatter on a thread, so that pasting does not block on it.
+    fn format_lines(
+        &self,
+        component_id: ComponentId,
+        lines: std::ops::RangeInclusive<usize>,
+    ) -> anyhow::Result<()> {
+        let component = self.layout.get_suggestive_editor_by_id(component_id)?;
+        let (content, version, formatter) = {
+            let component = component.borrow();
+            let buffer = component.editor().buffer();
+            (
+                buffer.content(),
+                buffer.version(),
+                buffer.language().and_then(|language| language.formatter()),
+            )
+        };
+        let Some(formatter) = formatter else {
+            return Ok(());
+        };
+        let sender = self.sender.clone();
+        std::thread::spawn(move || {
+            log::info!("[FORMAT]: {}", formatter.command_string());
+            let formatted = formatter
+                .format(&content)
+                .map_err(|error| error.to_string());
+            let _ = sender.send(AppMessage::FormattedLines {
+                component_id,
+                version,
+                lines,
+                formatted,
+            });
+        });
+        Ok(())
+    }
+
+    fn handle_formatted_lines(
+        &mut self,
+        component_id: ComponentId,
+        version: usize,
+        lines: std::ops::RangeInclusive<usize>,
+        formatted: Result<String, String>,
+    ) -> anyhow::Result<()> {
+        let formatted = match formatted {
+            Ok(formatted) => formatted,
+            Err(error) => {
+                // Keep the reindented paste
+                log::info!("Error formatting: {}", error);
+                return Ok(());
+            }
+        };
+        // The editor might have been closed in the meantime
+        let Ok(component) = self.layout.get_suggestive_editor_by_id(component_id) else {
+            return Ok(());
+        };
+        let dispatches = component
+            .borrow_mut()
+            .editor_mut()
+            .apply_formatted_lines(version, lines, &formatted)?;
+        self.handle_dispatches(dispatches)
+    }
+
+    /// The scrolled editor might no longer be the current component,
+    /// for example when the focus moves to another window during the animation.
+    fn advance_scroll_animation(&mut self, component_id: ComponentId) {
+        let Some(component) = self.layout.get_component_by_id(component_id) else {
+            return;
+        };
+        if component
+            .borrow_mut()
+            .editor_mut()
+            .advance_scroll_animation()
+        {
+            self.schedule_scroll_animation_tick(component_id)
+        }
+    }
+
     pub(crate) fn current_component(&self) -> Rc<RefCell<dyn Component>> {
         self.layout.get_current_component()
     }
@@ -732,6 +1052,250 @@ impl<T: Frontend> App<T> {
         )
     }
 
+    fn open_move_to_column_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Move to column".to_string(),
+                on_enter: DispatchPrompt::MoveSelectionToColumn,
+                items: vec![],
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::MoveToColumn,
+            None,
+        )
+    }
+
+    fn open_jump_to_percent_of_line_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Jump to percent of line".to_string(),
+                on_enter: DispatchPrompt::JumpToPercentOfLine,
+                items: vec![],
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::JumpToPercentOfLine,
+            None,
+        )
+    }
+
+    fn open_go_to_byte_offset_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Go to byte offset (decimal or 0x-prefixed hexadecimal)".to_string(),
+                on_enter: DispatchPrompt::GoToByteOffset,
+                items: vec![],
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::GoToByteOffset,
+            None,
+        )
+    }
+
+    /// Request the occurrences of the symbol under the cursor, after clearing the current ones
+    /// if the cursor has moved off them.
+    ///
+    /// This is dispatched after every key event, and the request is debounced by the LSP server process.
+    fn request_document_highlight(&mut self) -> anyhow::Result<()> {
+        {
+            let component = self.current_component();
+            let mut component = component.borrow_mut();
+            let editor = component.editor_mut();
+            if !self.context.document_highlight() {
+                editor.clear_document_highlights();
+                return Ok(());
+            }
+            editor.clear_document_highlights_unless_under_cursor();
+        }
+        if let Some(params) = self.get_request_params() {
+            self.lsp_manager.send_message(
+                params.path.clone(),
+                FromEditor::TextDocumentDocumentHighlight(params),
+            )?;
+        }
+        Ok(())
+    }
+
+    /// The ranges of the tree-sitter nodes are used instead
+    /// if the language server is not running or not initialized yet.
+    fn request_selection_range(&mut self) -> anyhow::Result<()> {
+        match self.get_request_params() {
+            Some(params) if self.lsp_manager.is_initialized(&params.path) => {
+                self.lsp_manager.send_message(
+                    params.path.clone(),
+                    FromEditor::TextDocumentSelectionRange(params),
+                )
+            }
+            _ => self.apply_selection_ranges(Vec::new()),
+        }
+    }
+
+    fn request_range_formatting(
+        &mut self,
+        range: Range<crate::position::Position>,
+    ) -> anyhow::Result<()> {
+        match self.get_request_params() {
+            Some(params) if self.lsp_manager.is_initialized(&params.path) => {
+                self.lsp_manager.send_message(
+                    params.path.clone(),
+                    FromEditor::TextDocumentRangeFormatting { params, range },
+                )
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn set_selection_ranges(
+        &mut self,
+        context: ResponseContext,
+        ranges: Vec<Range<crate::position::Position>>,
+    ) -> anyhow::Result<()> {
+        // The response is stale if another file is focused since the request
+        if context.path.is_none() || context.path != self.current_component().borrow().path() {
+            return Ok(());
+        }
+        self.apply_selection_ranges(ranges)
+    }
+
+    fn apply_selection_ranges(
+        &mut self,
+        ranges: Vec<Range<crate::position::Position>>,
+    ) -> anyhow::Result<()> {
+        let dispatches = self
+            .current_component()
+            .borrow_mut()
+            .editor_mut()
+            .set_selection_ranges(ranges)?;
+        self.handle_dispatches(dispatches)
+    }
+
+    fn organize_imports(&mut self) -> anyhow::Result<()> {
+        match self.get_request_params() {
+            Some(params) if self.lsp_manager.is_initialized(&params.path) => {
+                self.lsp_manager.send_message(
+                    params.path.clone(),
+                    FromEditor::TextDocumentOrganizeImports(params),
+                )
+            }
+            _ => self.apply_organize_imports(Vec::new()),
+        }
+    }
+
+    fn apply_organize_imports(
+        &mut self,
+        code_actions: Vec<crate::lsp::code_action::CodeAction>,
+    ) -> anyhow::Result<()> {
+        match code_actions.into_iter().find(|code_action| {
+            code_action.kind.as_ref().is_some_and(|kind| {
+                kind.starts_with(lsp_types::CodeActionKind::SOURCE_ORGANIZE_IMPORTS.as_str())
+            })
+        }) {
+            Some(code_action) => self.handle_dispatches(code_action.dispatches()),
+            None => self.handle_dispatch_editor(DispatchEditor::SortImports),
+        }
+    }
+
+    fn open_insert_date_time_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: format!(
+                    "Insert date/time with format (currently {})",
+                    self.context.date_time_format()
+                ),
+                on_enter: DispatchPrompt::InsertDateTime,
+                items: vec![],
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::DateTimeFormat,
+            None,
+        )
+    }
+
+    fn open_wrap_in_tag_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Wrap in tag".to_string(),
+                on_enter: DispatchPrompt::WrapSelectionInTag,
+                items: vec![],
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::WrapInTag,
+            None,
+        )
+    }
+
+    fn open_pipe_selection_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Pipe selections through command".to_string(),
+                on_enter: DispatchPrompt::PipeSelection,
+                items: vec![],
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::PipeSelection,
+            None,
+        )
+    }
+
+    fn open_insert_shell_output_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Insert output of command".to_string(),
+                on_enter: DispatchPrompt::InsertShellOutput,
+                items: vec![],
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::InsertShellOutput,
+            None,
+        )
+    }
+
+    fn open_select_lines_matching_prompt(&mut self, matching: bool) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: format!(
+                    "Select lines {} (regex)",
+                    if matching { "matching" } else { "not matching" }
+                ),
+                on_enter: DispatchPrompt::SelectLinesMatching { matching },
+                items: vec![],
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::SelectLinesMatching,
+            None,
+        )
+    }
+
+    fn open_select_by_query_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Select by tree-sitter query".to_string(),
+                on_enter: DispatchPrompt::SelectByQuery,
+                items: vec![],
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::SelectByQuery,
+            None,
+        )
+    }
+
     fn open_rename_prompt(&mut self, current_name: Option<String>) -> anyhow::Result<()> {
         self.open_prompt(
             PromptConfig {
@@ -837,6 +1401,14 @@ impl<T: Frontend> App<T> {
 
     fn open_file_picker(&mut self, kind: FilePickerKind) -> anyhow::Result<()> {
         let working_directory = self.working_directory.clone();
+        // Recent files are listed in the order of recency instead of being grouped by folder
+        let group_by_folder = kind != FilePickerKind::Recent;
+        // Scratch buffers have no path, but they are opened nonetheless
+        let scratch_editors = if kind == FilePickerKind::Opened {
+            self.layout.scratch_editors(&self.context)
+        } else {
+            Vec::new()
+        };
         self.open_prompt(
             PromptConfig {
                 title: format!("Open file: {}", kind.display()),
@@ -862,40 +1434,348 @@ impl<T: Frontend> App<T> {
                             .into_iter()
                             .map(|path| path.into_path_buf())
                             .collect_vec(),
+                        FilePickerKind::Recent => self
+                            .context
+                            .recent_files()
+                            .iter()
+                            .filter(|path| path.exists())
+                            .cloned()
+                            .collect_vec(),
                     }
                     .into_iter()
-                    .map(|path| {
+                    .enumerate()
+                    .map(|(index, path)| {
                         DropdownItem::new({
-                            let name = path
-                                .file_name()
-                                .unwrap_or_default()
-                                .to_string_lossy()
-                                .to_string();
+                            let name = if group_by_folder {
+                                path.file_name()
+                                    .unwrap_or_default()
+                                    .to_string_lossy()
+                                    .to_string()
+                            } else {
+                                path.strip_prefix(&self.working_directory)
+                                    .unwrap_or(path.as_path())
+                                    .display()
+                                    .to_string()
+                            };
                             let icon = shared::canonicalized_path::get_path_icon(&path);
                             format!("{icon} {name}")
                         })
-                        .set_group(path.parent().map(|parent| {
+                        .set_group(path.parent().filter(|_| group_by_folder).map(|parent| {
                             let relative = parent
                                 .strip_prefix(&self.working_directory)
                                 .map(|path| path.display().to_string())
                                 .unwrap_or_else(|_| parent.display().to_string());
                             format!("{} {}", shared::icons::get_icon_config().folder, relative,)
                         }))
+                        .set_rank((!group_by_folder).then(|| Box::new([index]) as Box<[usize]>))
                         .set_dispatches(Dispatches::one(
                             crate::app::Dispatch::OpenFileFromPathBuf(path),
                         ))
                     })
+                    .chain(scratch_editors.into_iter().map(|(component_id, title)| {
+                        DropdownItem::new(title)
+                            .set_dispatches(Dispatches::one(Dispatch::FocusScratch(component_id)))
+                    }))
                     .collect_vec()
                 },
                 enter_selects_first_matching_item: true,
                 leaves_current_line_empty: true,
                 fire_dispatches_on_change: None,
             },
-            PromptHistoryKey::OpenFile,
+            PromptHistoryKey::OpenFile,
+            None,
+        )
+    }
+
+    fn open_scratch_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Open scratch (file extension)".to_string(),
+                on_enter: DispatchPrompt::OpenScratch,
+                items: Some("txt")
+                    .into_iter()
+                    .chain(
+                        shared::languages::LANGUAGES
+                            .iter()
+                            .filter_map(|language| language.extensions().first().copied()),
+                    )
+                    .map(|extension| {
+                        DropdownItem::new(extension.to_string()).set_dispatches(Dispatches::one(
+                            Dispatch::OpenScratch {
+                                extension: extension.to_string(),
+                            },
+                        ))
+                    })
+                    .collect_vec(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::OpenScratch,
+            None,
+        )
+    }
+
+    /// Open and focus an unnamed in-memory buffer, for drafting notes or snippets.
+    fn open_scratch(&mut self, extension: &str) -> anyhow::Result<()> {
+        // Counting the current scratch editors instead would reuse the number
+        // of a scratch editor that has been closed or saved to a path
+        self.scratch_counter += 1;
+        let title = format!("[Scratch {}]", self.scratch_counter);
+        self.open_scratch_with_content(shared::language::from_extension(extension), title, "")
+    }
+
+    fn open_scratch_with_content(
+        &mut self,
+        language: Option<Language>,
+        title: String,
+        content: &str,
+    ) -> anyhow::Result<()> {
+        let mut buffer = Buffer::new(
+            language
+                .as_ref()
+                .and_then(|language| language.tree_sitter_language()),
+            content,
+        );
+        if let Some(language) = language {
+            buffer.set_language(language)?;
+        }
+        let mut editor = SuggestiveEditor::from_buffer(
+            Rc::new(RefCell::new(buffer)),
+            SuggestiveEditorFilter::CurrentWord,
+        );
+        editor.set_title(title);
+        self.layout
+            .replace_and_focus_current_suggestive_editor(Rc::new(RefCell::new(editor)));
+        Ok(())
+    }
+
+    fn open_run_task_prompt(&mut self) -> anyhow::Result<()> {
+        let tasks = Task::load(&self.working_directory)?;
+        if tasks.is_empty() {
+            self.show_global_info(Info::new(
+                "Run task".to_string(),
+                "No tasks are defined in .ki/tasks.json".to_string(),
+            ));
+            return Ok(());
+        }
+        self.open_prompt(
+            PromptConfig {
+                title: "Run task".to_string(),
+                on_enter: DispatchPrompt::Null,
+                items: tasks
+                    .into_iter()
+                    .map(|task| {
+                        DropdownItem::new(format!("{}: {}", task.name, task.command))
+                            .set_dispatches(Dispatches::one(Dispatch::RunTaskCommand(task)))
+                    })
+                    .collect_vec(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::RunTask,
+            None,
+        )
+    }
+
+    /// Run the task in another thread, so that slow tasks do not block the UI.
+    fn run_task_command(&self, task: Task) {
+        let sender = self.sender.clone();
+        let working_directory = self.working_directory.clone();
+        std::thread::spawn(move || {
+            let (success, output) = task
+                .run(&working_directory)
+                .unwrap_or_else(|error| (false, error.to_string()));
+            let _ = sender.send(AppMessage::TaskOutput {
+                task,
+                success,
+                output,
+            });
+        });
+    }
+
+    fn open_yank_flash_duration_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: format!(
+                    "Yank flash duration in milliseconds (currently {})",
+                    self.context.yank_flash_duration().as_millis()
+                ),
+                on_enter: DispatchPrompt::SetYankFlashDuration,
+                items: vec![],
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::YankFlashDuration,
+            None,
+        )
+    }
+
+    fn open_large_file_threshold_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: format!(
+                    "Large file threshold in bytes, for files opened afterwards (currently {})",
+                    self.context.large_file_threshold()
+                ),
+                on_enter: DispatchPrompt::SetLargeFileThreshold,
+                items: vec![],
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::LargeFileThreshold,
+            None,
+        )
+    }
+
+    fn open_incremental_reparse_threshold_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: format!(
+                    "Incremental reparse threshold in bytes (currently {})",
+                    self.context.incremental_reparse_threshold()
+                ),
+                on_enter: DispatchPrompt::SetIncrementalReparseThreshold,
+                items: vec![],
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::IncrementalReparseThreshold,
+            None,
+        )
+    }
+
+    /// Unlike the large file threshold, this also applies to the buffers that are already open.
+    fn set_incremental_reparse_threshold(&mut self, bytes: usize) {
+        self.context.set_incremental_reparse_threshold(bytes);
+        for buffer in self.layout.buffers() {
+            buffer.borrow_mut().set_incremental_reparse_threshold(bytes)
+        }
+    }
+
+    fn open_parse_errors_to_quickfix_prompt(&mut self) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: "Parse errors to quickfix (regex, empty for default)".to_string(),
+                on_enter: DispatchPrompt::ParseErrorsToQuickfix,
+                items: vec![],
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::ParseErrorsToQuickfix,
+            None,
+        )
+    }
+
+    fn parse_errors_to_quickfix(&mut self, pattern: Option<String>) -> anyhow::Result<()> {
+        let items = crate::task::parse_error_locations(
+            &self.get_current_component_content(),
+            pattern
+                .as_deref()
+                .unwrap_or(crate::task::DEFAULT_ERROR_PATTERN),
+            &self.working_directory,
+        )?;
+        if items.is_empty() {
+            self.show_global_info(Info::new(
+                "Parse errors to quickfix".to_string(),
+                "No locations found".to_string(),
+            ));
+            return Ok(());
+        }
+        self.set_quickfix_list_type(
+            ResponseContext::default().set_description("Parsed errors"),
+            QuickfixListType::Items(items),
+        )
+    }
+
+    fn handle_task_output(
+        &mut self,
+        task: Task,
+        success: bool,
+        output: String,
+    ) -> anyhow::Result<()> {
+        if let Some(editor) = self
+            .task_output_editors
+            .get(&task.name)
+            .and_then(|id| self.layout.get_scratch_editor(*id))
+        {
+            editor.borrow_mut().set_content(&output)?;
+            self.layout
+                .replace_and_focus_current_suggestive_editor(editor);
+        } else {
+            self.open_scratch_with_content(None, format!("[Task: {}]", task.name), &output)?;
+            let id = self.current_component().borrow().id();
+            self.task_output_editors.insert(task.name.clone(), id);
+        }
+        let items = crate::task::parse_error_locations(
+            &output,
+            task.error_pattern(),
+            &self.working_directory,
+        )?;
+        if items.is_empty() {
+            self.show_global_info(Info::new(
+                format!("Task `{}`", task.name),
+                if success { "Succeeded" } else { "Failed" }.to_string(),
+            ));
+            return Ok(());
+        }
+        self.set_quickfix_list_type(
+            ResponseContext::default().set_description(&format!("Task: {}", task.name)),
+            QuickfixListType::Items(items),
+        )
+    }
+
+    fn open_save_scratch_as_prompt(&mut self, component_id: ComponentId) -> anyhow::Result<()> {
+        if self.layout.get_scratch_editor(component_id).is_none() {
+            return Ok(());
+        }
+        self.open_prompt(
+            PromptConfig {
+                title: "Save scratch as".to_string(),
+                on_enter: DispatchPrompt::SaveScratchAs { component_id },
+                items: vec![],
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::AddPath,
             None,
         )
     }
 
+    /// Write the content of the given scratch editor to a new file at `path`,
+    /// after which the editor is treated like that of any other opened file.
+    fn save_scratch_as(&mut self, component_id: ComponentId, path: String) -> anyhow::Result<()> {
+        let Some(editor) = self.layout.get_scratch_editor(component_id) else {
+            return Ok(());
+        };
+        let path_buf = PathBuf::from(path.clone());
+        if path_buf.exists() {
+            return Err(anyhow::anyhow!("The path \"{}\" already exists", path));
+        };
+        self.add_path_parent(&path_buf)?;
+        std::fs::File::create(&path_buf)?;
+        let path: CanonicalizedPath = path_buf.try_into()?;
+        {
+            let mut editor = editor.borrow_mut();
+            editor.editor_mut().buffer_mut().set_path(path.clone());
+            editor.editor_mut().clear_title();
+        }
+        self.layout.add_suggestive_editor(editor.clone());
+        let dispatches = editor.borrow_mut().editor_mut().save(&self.context)?;
+        self.handle_dispatches(dispatches)?;
+        self.layout.refresh_file_explorer(&self.working_directory)?;
+        if self.enable_lsp {
+            self.lsp_manager.open_file(path)?;
+        }
+        Ok(())
+    }
+
     /// This only opens the file in the background but does not focus it.
     /// If you need to focus it, use `Self::go_to_file` instead.
     fn open_file(
@@ -906,15 +1786,41 @@ impl<T: Frontend> App<T> {
         if option.store_history() {
             self.file_path_history.push(path.clone())
         }
+        if let Err(error) = self.context.push_recent_file(path.to_path_buf()) {
+            log::error!("Failed to save recent files: {:?}", error)
+        }
         // Check if the file is opened before
         // so that we won't notify the LSP twice
         if let Some(matching_editor) = self.layout.open_file(path, option.is_focus()) {
             return Ok(matching_editor);
         }
 
-        let buffer = Buffer::from_path(path, true)?;
+        let mut buffer =
+            Buffer::from_path_with_large_file_threshold(path, self.context.large_file_threshold())?;
+        buffer.set_incremental_reparse_threshold(self.context.incremental_reparse_threshold());
+        if buffer.has_mixed_line_endings() {
+            self.show_global_info(Info::new(
+                "Mixed line endings".to_string(),
+                format!(
+                    "The line endings of {} will be normalized to {} on save.",
+                    path.display_absolute(),
+                    buffer.line_ending().display()
+                ),
+            ))
+        }
+        if buffer.encoding() == Encoding::Latin1 {
+            self.show_global_info(Info::new(
+                "Unknown encoding".to_string(),
+                format!(
+                    "{} is not valid UTF-8, so it is decoded as {}, and will be saved as such.",
+                    path.display_absolute(),
+                    Encoding::Latin1.display()
+                ),
+            ))
+        }
         let language = buffer.language();
         let content = buffer.content();
+        let large_file_mode = buffer.is_large_file_mode();
         let buffer = Rc::new(RefCell::new(buffer));
         let editor = SuggestiveEditor::from_buffer(buffer, SuggestiveEditorFilter::CurrentWord);
         let component_id = editor.id();
@@ -928,9 +1834,10 @@ impl<T: Frontend> App<T> {
         }
 
         if let Some(language) = language {
+            self.highlight_visible_lines(&component, language.clone(), &content)?;
             self.request_syntax_highlight(component_id, language, content)?;
         }
-        if self.enable_lsp {
+        if self.enable_lsp && !large_file_mode {
             self.lsp_manager.open_file(path.clone())?;
         }
         Ok(component)
@@ -1037,6 +1944,19 @@ impl<T: Frontend> App<T> {
                 self.handle_dispatch(Dispatch::ReceiveCodeActions(code_actions))?;
                 Ok(())
             }
+            LspNotification::OrganizeImports(code_actions) => {
+                self.apply_organize_imports(code_actions)
+            }
+            LspNotification::CodeActionResolve(code_action) => {
+                // Prevent resolving again if the resolved code action is still without edit
+                self.handle_dispatches(
+                    crate::lsp::code_action::CodeAction {
+                        unresolved: None,
+                        ..code_action
+                    }
+                    .dispatches(),
+                )
+            }
             LspNotification::SignatureHelp(signature_help) => {
                 self.handle_signature_help(signature_help)?;
                 Ok(())
@@ -1048,6 +1968,23 @@ impl<T: Frontend> App<T> {
             LspNotification::CompletionItemResolve(completion_item) => {
                 self.update_current_completion_item(completion_item.into())
             }
+            LspNotification::SelectionRange(context, ranges) => {
+                self.set_selection_ranges(context, ranges)
+            }
+            LspNotification::DocumentHighlight(context, ranges) => {
+                let component = self.current_component();
+                // The response is stale if another file is focused since the request
+                if self.context.document_highlight()
+                    && context.path.is_some()
+                    && context.path == component.borrow().path()
+                {
+                    component
+                        .borrow_mut()
+                        .editor_mut()
+                        .set_document_highlights(ranges);
+                }
+                Ok(())
+            }
         }
     }
 
@@ -1091,6 +2028,35 @@ impl<T: Frontend> App<T> {
         Ok(())
     }
 
+    /// The items of each file are processed from the bottom up,
+    /// so that the edits do not shift the locations of the remaining items.
+    fn quickfix_do(&mut self, dispatch_editor: DispatchEditor) -> anyhow::Result<()> {
+        let Some(quickfix_list) = self.get_quickfix_list() else {
+            return Ok(());
+        };
+        let locations = quickfix_list
+            .items()
+            .into_iter()
+            .map(|item| item.location().clone())
+            .sorted()
+            .rev()
+            .group_by(|location| location.path.clone());
+        for (path, locations) in &locations {
+            let component = self.open_file(&path, OpenFileOption::Background)?;
+            for location in locations {
+                let dispatches = component
+                    .borrow_mut()
+                    .editor_mut()
+                    .set_position_range(location.range)?;
+                self.handle_dispatches(dispatches)?;
+                self.handle_dispatch_editor_custom(dispatch_editor.clone(), component.clone())?;
+            }
+            let dispatches = component.borrow_mut().editor_mut().save(&self.context)?;
+            self.handle_dispatches(dispatches)?;
+        }
+        Ok(())
+    }
+
     fn show_global_info(&mut self, info: Info) {
         self.layout.show_global_info(info).unwrap_or_else(|err| {
             log::error!("Error showing info: {:?}", err);
@@ -1165,7 +2131,7 @@ impl<T: Frontend> App<T> {
 
             self.handle_dispatches(dispatches)?;
 
-            let dispatches = component.borrow_mut().editor_mut().save()?;
+            let dispatches = component.borrow_mut().editor_mut().save(&self.context)?;
 
             self.handle_dispatches(dispatches)?;
         }
@@ -1185,6 +2151,9 @@ impl<T: Frontend> App<T> {
     }
 
     fn global_replace(&mut self) -> anyhow::Result<()> {
+        if self.context.global_search_config().open_buffers_only() {
+            return self.replace_in_open_buffers();
+        }
         let working_directory = self.working_directory.clone();
         let global_search_config = self.context.global_search_config();
         let walk_builder_config = WalkBuilderConfig {
@@ -1197,6 +2166,20 @@ impl<T: Frontend> App<T> {
         self.layout.reload_buffers(affected_paths)
     }
 
+    /// Like the search of open buffers, this replaces the content of the buffers,
+    /// including their unsaved modifications, instead of the files on disk.
+    /// The replacements can be undone, and are saved with the buffers.
+    fn replace_in_open_buffers(&mut self) -> anyhow::Result<()> {
+        let config = self.context.global_search_config().local_config().clone();
+        for buffer in self.layout.buffers() {
+            let mut buffer = buffer.borrow_mut();
+            if buffer.path().is_some() {
+                buffer.replace(config.clone(), Default::default())?;
+            }
+        }
+        Ok(())
+    }
+
     fn global_search(&mut self) -> anyhow::Result<()> {
         let working_directory = self.working_directory.clone();
 
@@ -1210,19 +2193,29 @@ impl<T: Frontend> App<T> {
         if config.search().is_empty() {
             return Ok(());
         }
-        let locations = match config.mode {
-            LocalSearchConfigMode::Regex(regex) => {
-                list::grep::run(&config.search(), walk_builder_config, regex)
-            }
-            LocalSearchConfigMode::AstGrep => {
-                list::ast_grep::run(config.search().clone(), walk_builder_config)
-            }
-            LocalSearchConfigMode::CaseAgnostic => {
-                list::case_agnostic::run(config.search().clone(), walk_builder_config)
-            }
-        }?;
+        let (description, locations) = if global_search_config.open_buffers_only() {
+            (
+                "Open buffers search",
+                list::open_buffers::run(self.layout.buffers(), config)?,
+            )
+        } else {
+            (
+                "Global search",
+                match config.mode {
+                    LocalSearchConfigMode::Regex(regex) => {
+                        list::grep::run(&config.search(), walk_builder_config, regex)
+                    }
+                    LocalSearchConfigMode::AstGrep => {
+                        list::ast_grep::run(config.search().clone(), walk_builder_config)
+                    }
+                    LocalSearchConfigMode::CaseAgnostic => {
+                        list::case_agnostic::run(config.search().clone(), walk_builder_config)
+                    }
+                }?,
+            )
+        };
         self.set_quickfix_list_type(
-            ResponseContext::default().set_description("Global search"),
+            ResponseContext::default().set_description(description),
             QuickfixListType::Items(
                 locations
                     .into_iter()
@@ -1233,6 +2226,19 @@ impl<T: Frontend> App<T> {
         Ok(())
     }
 
+    fn list_todos(&mut self) -> anyhow::Result<()> {
+        let walk_builder_config = WalkBuilderConfig {
+            root: self.working_directory.clone().into(),
+            include: None,
+            exclude: None,
+        };
+        let items = list::todo::run(self.context.todo_tags().to_vec(), walk_builder_config)?;
+        self.set_quickfix_list_type(
+            ResponseContext::default().set_description("TODOs"),
+            QuickfixListType::Items(items),
+        )
+    }
+
     pub(crate) fn quit_all(&self) -> Result<(), anyhow::Error> {
         Ok(self.sender.send(AppMessage::QuitAll)?)
     }
@@ -1255,7 +2261,7 @@ impl<T: Frontend> App<T> {
     }
 
     fn save_all(&self) -> anyhow::Result<()> {
-        self.layout.save_all()
+        self.layout.save_all(&self.context)
     }
 
     fn open_yes_no_prompt(&mut self, prompt: YesNoPrompt) -> anyhow::Result<()> {
@@ -1327,14 +2333,19 @@ impl<T: Frontend> App<T> {
     }
 
     fn request_syntax_highlight(
-        &self,
+        &mut self,
         component_id: ComponentId,
         language: Language,
         content: String,
     ) -> anyhow::Result<()> {
         if let Some(sender) = &self.syntax_highlight_request_sender {
+            self.syntax_highlight_request_counter += 1;
+            let request_id = self.syntax_highlight_request_counter;
+            self.latest_syntax_highlight_request_ids
+                .insert(component_id, request_id);
             sender.send(SyntaxHighlightRequest {
                 component_id,
+                request_id,
                 language,
                 source_code: content,
             })?;
@@ -1342,6 +2353,67 @@ impl<T: Frontend> App<T> {
         Ok(())
     }
 
+    /// Highlight the lines within the scroll window of the editor synchronously,
+    /// so that a newly opened file is highlighted on its first render,
+    /// while the highlighting of the whole file streams in from the background.
+    ///
+    /// Only the visible lines are parsed, so this does not block on large files.
+    fn highlight_visible_lines(
+        &mut self,
+        component: &Rc<RefCell<SuggestiveEditor>>,
+        language: Language,
+        content: &str,
+    ) -> anyhow::Result<()> {
+        if self.syntax_highlight_request_sender.is_none() {
+            return Ok(());
+        }
+        let (scroll_offset, height) = {
+            let component = component.borrow();
+            let editor = component.editor();
+            (
+                editor.scroll_offset() as usize,
+                editor.rectangle().height as usize,
+            )
+        };
+        // The editor is not laid out yet if it is opened in the background
+        let height = if height == 0 {
+            self.layout.terminal_dimension().height as usize
+        } else {
+            height
+        };
+        let buffer = component.borrow().editor().buffer_rc();
+        let byte_range = {
+            let buffer = buffer.borrow();
+            let len_lines = buffer.len_lines();
+            let byte_start = buffer
+                .line_to_byte(scroll_offset.min(len_lines))
+                .unwrap_or(content.len());
+            let byte_end = buffer
+                .line_to_byte((scroll_offset + height).min(len_lines))
+                .unwrap_or(content.len());
+            byte_start..byte_end.max(byte_start)
+        };
+        let Some(visible_content) = content.get(byte_range.clone()) else {
+            return Ok(());
+        };
+        let highlighted_spans = self.context.highlight(language, visible_content)?;
+        let highlighted_spans = HighlighedSpans(
+            highlighted_spans
+                .0
+                .into_iter()
+                .map(|span| HighlighedSpan {
+                    byte_range: span.byte_range.start + byte_range.start
+                        ..span.byte_range.end + byte_range.start,
+                    ..span
+                })
+                .collect(),
+        );
+        buffer
+            .borrow_mut()
+            .update_highlighted_spans(byte_range, highlighted_spans);
+        Ok(())
+    }
+
     #[cfg(test)]
     pub(crate) fn get_current_selected_texts(&self) -> Vec<String> {
         let _content = self.current_component().borrow().content();
@@ -1353,11 +2425,12 @@ impl<T: Frontend> App<T> {
 
     #[cfg(test)]
     pub(crate) fn get_file_content(&self, path: &CanonicalizedPath) -> String {
-        self.layout
-            .get_existing_editor(path)
-            .unwrap()
-            .borrow()
-            .content()
+        self.get_buffer_content(path).unwrap()
+    }
+
+    /// Returns the content of the opened buffer of the given `path`, if any.
+    pub(crate) fn get_buffer_content(&self, path: &CanonicalizedPath) -> Option<String> {
+        Some(self.layout.get_existing_editor(path)?.borrow().content())
     }
 
     pub(crate) fn handle_dispatch_editor(
@@ -1426,6 +2499,30 @@ impl<T: Frontend> App<T> {
         self.syntax_highlight_request_sender = Some(sender);
     }
 
+    pub(crate) fn set_snippets(&mut self, snippets: crate::snippet::Snippets) {
+        self.context.set_snippets(snippets)
+    }
+
+    pub(crate) fn set_search_presets(&mut self, search_presets: SearchPresets) {
+        self.context.set_search_presets(search_presets)
+    }
+
+    pub(crate) fn set_recent_files(&mut self, recent_files: crate::recent_files::RecentFiles) {
+        self.context.set_recent_files(recent_files)
+    }
+
+    pub(crate) fn set_todo_tags(&mut self, todo_tags: Vec<list::todo::TodoTag>) {
+        self.context.set_todo_tags(todo_tags)
+    }
+
+    pub(crate) fn set_character_replacements(
+        &mut self,
+        character_replacements: Vec<crate::char_info::CharacterReplacement>,
+    ) {
+        self.context
+            .set_character_replacements(character_replacements)
+    }
+
     #[cfg(test)]
     pub(crate) fn get_current_file_path(&self) -> Option<CanonicalizedPath> {
         self.current_component().borrow().path()
@@ -1601,6 +2698,19 @@ impl<T: Frontend> App<T> {
                                                     filter_glob: GlobalSearchFilterGlob::Exclude,
                                                 },
                                             ),
+                                            Keymap::new(
+                                                "o",
+                                                show_checkbox(
+                                                    "Open buffers only",
+                                                    config.open_buffers_only(),
+                                                ),
+                                                Dispatch::UpdateGlobalSearchConfig {
+                                                    update:
+                                                        GlobalSearchConfigUpdate::SetOpenBuffersOnly(
+                                                            !config.open_buffers_only(),
+                                                        ),
+                                                },
+                                            ),
                                         ]
                                         .to_vec()
                                     })
@@ -1646,11 +2756,40 @@ impl<T: Frontend> App<T> {
                     },
                     KeymapLegendSection {
                         title: "Actions".to_string(),
-                        keymaps: Keymaps::new(&[Keymap::new(
-                            "R",
-                            "Replace all".to_string(),
-                            Dispatch::Replace { scope },
-                        )]),
+                        keymaps: Keymaps::new(
+                            &[
+                                Keymap::new(
+                                    "R",
+                                    "Replace all".to_string(),
+                                    Dispatch::Replace { scope },
+                                ),
+                                Keymap::new(
+                                    "p",
+                                    "Load preset".to_string(),
+                                    Dispatch::OpenSearchPresetPrompt { scope },
+                                ),
+                                Keymap::new(
+                                    "P",
+                                    "Save as preset".to_string(),
+                                    Dispatch::OpenSaveSearchPresetPrompt { scope },
+                                ),
+                            ]
+                            .into_iter()
+                            // Only local replace can produce selections
+                            .chain((scope == Scope::Local).then(|| {
+                                update_keymap(
+                                    "k",
+                                    show_checkbox(
+                                        "Keep replacements selected",
+                                        local_search_config.select_replacements(),
+                                    ),
+                                    LocalSearchConfigUpdate::SelectReplacements(
+                                        !local_search_config.select_replacements(),
+                                    ),
+                                )
+                            }))
+                            .collect_vec(),
+                        ),
                     },
                 ]
                 .into_iter()
@@ -1677,6 +2816,15 @@ impl<T: Frontend> App<T> {
                                     }),
                                     regex.match_whole_word,
                                 ),
+                                update_mode_keymap(
+                                    "m",
+                                    "Multi-line".to_string(),
+                                    LocalSearchConfigMode::Regex(RegexConfig {
+                                        multiline: !regex.multiline,
+                                        ..regex
+                                    }),
+                                    regex.multiline,
+                                ),
                             ]
                             .into_iter()
                             .collect_vec(),
@@ -1703,6 +2851,65 @@ impl<T: Frontend> App<T> {
         )
     }
 
+    fn open_save_search_preset_prompt(&mut self, scope: Scope) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: format!("Save search preset ({:?}) as", scope),
+                on_enter: DispatchPrompt::SaveSearchPreset { scope },
+                items: self
+                    .context
+                    .search_presets()
+                    .iter()
+                    .map(|preset| DropdownItem::new(preset.name.clone()))
+                    .collect_vec(),
+                enter_selects_first_matching_item: false,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::SearchPreset,
+            None,
+        )
+    }
+
+    fn open_search_preset_prompt(&mut self, scope: Scope) -> anyhow::Result<()> {
+        self.open_prompt(
+            PromptConfig {
+                title: format!("Load search preset ({:?})", scope),
+                on_enter: DispatchPrompt::Null,
+                items: self
+                    .context
+                    .search_presets()
+                    .iter()
+                    .map(|preset| {
+                        DropdownItem::new(preset.display(scope)).set_dispatches(Dispatches::one(
+                            Dispatch::ApplySearchPreset {
+                                scope,
+                                preset: preset.clone(),
+                            },
+                        ))
+                    })
+                    .collect_vec(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            PromptHistoryKey::SearchPreset,
+            None,
+        )
+    }
+
+    fn apply_search_preset(&mut self, scope: Scope, preset: SearchPreset) -> anyhow::Result<()> {
+        self.context.apply_search_preset(scope, preset)?;
+        match scope {
+            Scope::Local => self.local_search()?,
+            Scope::Global => {
+                self.global_search()?;
+            }
+        }
+        self.show_search_config(scope);
+        Ok(())
+    }
+
     fn open_update_search_prompt(&mut self, scope: Scope) -> Result<(), anyhow::Error> {
         self.open_prompt(
             PromptConfig {
@@ -1897,6 +3104,11 @@ impl<T: Frontend> App<T> {
         self.layout.get_opened_files().len()
     }
 
+    #[cfg(test)]
+    pub(crate) fn scratch_editors_count(&self) -> usize {
+        self.layout.scratch_editors(&self.context).len()
+    }
+
     #[cfg(test)]
     pub(crate) fn quickfix_list_info(&self) -> Option<String> {
         self.layout.quickfix_list_info()
@@ -2088,6 +3300,9 @@ pub(crate) enum Dispatch {
     },
     SetQuickfixList(QuickfixListType),
     GotoQuickfixListItem(Movement),
+    /// Apply the `DispatchEditor` at the location of every quickfix item,
+    /// then save the affected files, like Vim's `:cdo`.
+    QuickfixDo(DispatchEditor),
     ApplyWorkspaceEdit(WorkspaceEdit),
     ShowKeymapLegend(KeymapLegendConfig),
     RemainOnlyCurrentComponent,
@@ -2099,11 +3314,37 @@ pub(crate) enum Dispatch {
     RequestDocumentSymbols,
     GotoLocation(Location),
     OpenMoveToIndexPrompt,
+    OpenMoveToColumnPrompt,
+    OpenJumpToPercentOfLinePrompt,
+    OpenGoToByteOffsetPrompt,
+    OpenInsertDateTimePrompt,
+    /// See [`Context::date_time_format`].
+    SetDateTimeFormat(String),
+    ToggleDateTimeUtc,
+    /// See [`Context::document_highlight`].
+    ToggleDocumentHighlight,
+    /// Dispatched after every key event, see [`App::request_document_highlight`].
+    RequestDocumentHighlight,
+    /// See [`Editor::set_selection_ranges`].
+    RequestSelectionRange,
+    /// Format the given range with the language server, see [`Context::reindent_on_paste`].
+    RequestRangeFormatting(Range<crate::position::Position>),
+    /// Format the given lines with the formatter of the language in the background,
+    /// see [`Context::reindent_on_paste`].
+    FormatLines {
+        component_id: ComponentId,
+        lines: std::ops::RangeInclusive<usize>,
+    },
+    /// Apply the first `source.organizeImports` code action of the language server,
+    /// or sort the imports if there is none.
+    OrganizeImports,
     RunCommand(String),
     QuitAll,
     OpenCommandPrompt,
     SaveQuitAll,
     RevealInExplorer(CanonicalizedPath),
+    /// See [`Context::file_explorer_sidebar`].
+    ToggleFileExplorerSidebar,
     OpenYesNoPrompt(YesNoPrompt),
     OpenMoveFilePrompt(CanonicalizedPath),
     OpenAddPathPrompt(CanonicalizedPath),
@@ -2158,6 +3399,22 @@ pub(crate) enum Dispatch {
     OpenUpdateSearchPrompt {
         scope: Scope,
     },
+    OpenSaveSearchPresetPrompt {
+        scope: Scope,
+    },
+    /// Save the current search config of `scope` as a named preset.
+    SaveSearchPreset {
+        scope: Scope,
+        name: String,
+    },
+    OpenSearchPresetPrompt {
+        scope: Scope,
+    },
+    /// Populate the search config of `scope` with `preset`, then run the search.
+    ApplySearchPreset {
+        scope: Scope,
+        preset: SearchPreset,
+    },
     Replace {
         scope: Scope,
     },
@@ -2167,7 +3424,6 @@ pub(crate) enum Dispatch {
     RenderDropdown {
         render: DropdownRender,
     },
-    #[cfg(test)]
     OpenPrompt {
         config: PromptConfig,
         key: PromptHistoryKey,
@@ -2175,6 +3431,8 @@ pub(crate) enum Dispatch {
     },
     ShowEditorInfo(Info),
     ReceiveCodeActions(Vec<crate::lsp::code_action::CodeAction>),
+    /// Resolve the edit of the chosen code action, which will be applied once it is received.
+    ResolveCodeAction(crate::lsp::code_action::UnresolvedCodeAction),
     OtherWindow,
     CloseCurrentWindowAndFocusParent,
     CloseEditorInfo,
@@ -2185,12 +3443,83 @@ pub(crate) enum Dispatch {
         line: String,
     },
     OpenThemePrompt,
+    OpenWrapInTagPrompt,
+    OpenPipeSelectionPrompt,
+    OpenInsertShellOutputPrompt,
+    OpenSelectLinesMatchingPrompt {
+        matching: bool,
+    },
+    OpenSelectByQueryPrompt,
+    /// Run the shell command in the background,
+    /// then insert its stdout at the cursor of the given component.
+    InsertShellOutput {
+        component_id: ComponentId,
+        command: String,
+    },
+    /// Block until the next `AppMessage` arrives, then handle it.
+    #[cfg(test)]
+    HandleNextAppMessage,
+    #[cfg(test)]
+    SetSnippets(crate::snippet::Snippets),
     ResolveCompletionItem(lsp_types::CompletionItem),
+    ToggleSmoothScroll,
+    /// Start ticking the smooth scrolling of the editor of the given id.
+    StartScrollAnimation(ComponentId),
+    ToggleIndentGuides,
+    ToggleGroupImports,
+    ToggleValidateStructuralEdits,
+    ToggleReindentOnPaste,
+    ToggleYankFlash,
+    OpenYankFlashDurationPrompt,
+    SetYankFlashDuration(std::time::Duration),
+    OpenLargeFileThresholdPrompt,
+    OpenIncrementalReparseThresholdPrompt,
+    /// See [`Context::incremental_reparse_threshold`].
+    SetIncrementalReparseThreshold(usize),
+    /// See [`Context::large_file_threshold`].
+    SetLargeFileThreshold(usize),
+    /// Render again after the given duration, for transient decorations such as the yank flash.
+    ScheduleRender(std::time::Duration),
+    /// See [`Context::default_cursor_direction`].
+    ToggleDefaultCursorDirection(SelectionMode),
+    /// See [`crate::context::FinalNewline`].
+    CycleFinalNewline,
+    /// Show the comments tagged with the configured TODO tags throughout the project
+    /// in the quickfix list.
+    ListTodos,
+    /// Prompt for the language of a new scratch buffer.
+    OpenScratchPrompt,
+    /// Open an unnamed in-memory buffer, highlighted as the language of the given file extension.
+    OpenScratch {
+        extension: String,
+    },
+    FocusScratch(ComponentId),
+    /// Prompt for the path to save the given scratch editor to,
+    /// this is ignored if the component is not a scratch editor.
+    OpenSaveScratchAsPrompt(ComponentId),
+    SaveScratchAs {
+        component_id: ComponentId,
+        path: String,
+    },
+    /// Prompt for one of the tasks defined in `.ki/tasks.json`, see [`crate::task`].
+    OpenRunTaskPrompt,
+    /// Run the task in the background, then show its output in a scratch buffer,
+    /// and the errors found in the output in the quickfix list.
+    RunTaskCommand(Task),
+    OpenParseErrorsToQuickfixPrompt,
+    /// Build the quickfix list from the `path:line:column` locations found in the current buffer,
+    /// such as pasted compiler output, see [`crate::task::parse_error_locations`].
+    ///
+    /// [`crate::task::DEFAULT_ERROR_PATTERN`] is used if `pattern` is `None`.
+    ParseErrorsToQuickfix {
+        pattern: Option<String>,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum GlobalSearchConfigUpdate {
     SetGlob(GlobalSearchFilterGlob, String),
+    SetOpenBuffersOnly(bool),
 }
 
 #[derive(Clone, Hash, Debug, PartialEq, Eq, Copy)]
@@ -2204,6 +3533,7 @@ pub(crate) enum LocalSearchConfigUpdate {
     Mode(LocalSearchConfigMode),
     Replacement(String),
     Search(String),
+    SelectReplacements(bool),
 }
 
 #[derive(Clone, Debug, PartialEq)]
@@ -2217,6 +3547,8 @@ pub(crate) enum FilePickerKind {
     NonGitIgnored,
     GitStatus(git::DiffMode),
     Opened,
+    /// Recently opened files, including those of previous sessions.
+    Recent,
 }
 impl FilePickerKind {
     pub(crate) fn display(&self) -> String {
@@ -2224,6 +3556,7 @@ impl FilePickerKind {
             FilePickerKind::NonGitIgnored => "Not Git Ignored".to_string(),
             FilePickerKind::GitStatus(diff_mode) => format!("Git Status ({})", diff_mode.display()),
             FilePickerKind::Opened => "Opened".to_string(),
+            FilePickerKind::Recent => "Recent".to_string(),
         }
     }
 }
@@ -2267,10 +3600,37 @@ pub(crate) enum AppMessage {
     LspNotification(LspNotification),
     Event(Event),
     QuitAll,
+    /// One chunk of a syntax highlighting pass, the `highlighted_spans`
+    /// replace the existing spans within `byte_range`.
     SyntaxHighlightResponse {
         component_id: ComponentId,
+        request_id: usize,
+        byte_range: Range<usize>,
         highlighted_spans: HighlighedSpans,
     },
+    ScrollAnimationTick(ComponentId),
+    /// Sent by [`Dispatch::ScheduleRender`], nothing needs to be done other than rendering.
+    RenderTick,
+    ShellOutput {
+        component_id: ComponentId,
+        command: String,
+        /// `Ok(stdout)` or `Err(stderr)`
+        output: Result<String, String>,
+    },
+    TaskOutput {
+        task: Task,
+        success: bool,
+        output: String,
+    },
+    /// Sent by [`Dispatch::FormatLines`].
+    FormattedLines {
+        component_id: ComponentId,
+        /// The version of the buffer that was formatted.
+        version: usize,
+        lines: std::ops::RangeInclusive<usize>,
+        /// `Ok(formatted content)` or `Err(error)`
+        formatted: Result<String, String>,
+    },
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -2290,7 +3650,27 @@ pub(crate) enum DispatchPrompt {
         filter_glob: GlobalSearchFilterGlob,
     },
     MoveSelectionByIndex,
+    MoveSelectionToColumn,
+    JumpToPercentOfLine,
+    GoToByteOffset,
+    /// Set the date/time format, which is remembered for later insertions, then insert the date/time.
+    InsertDateTime,
+    ParseErrorsToQuickfix,
+    SetYankFlashDuration,
+    SetLargeFileThreshold,
+    SetIncrementalReparseThreshold,
+    OpenScratch,
+    SaveScratchAs {
+        component_id: ComponentId,
+    },
     RenameSymbol,
+    WrapSelectionInTag,
+    PipeSelection,
+    InsertShellOutput,
+    SelectLinesMatching {
+        matching: bool,
+    },
+    SelectByQuery,
     UpdateLocalSearchConfigSearch {
         scope: Scope,
         show_config_after_enter: bool,
@@ -2312,6 +3692,9 @@ pub(crate) enum DispatchPrompt {
     UpdateLocalSearchConfigReplacement {
         scope: Scope,
     },
+    SaveSearchPreset {
+        scope: Scope,
+    },
     #[cfg(test)]
     SetContent,
 }
@@ -2346,9 +3729,89 @@ impl DispatchPrompt {
                     [Dispatch::ToEditor(MoveSelection(Movement::Index(index)))].to_vec(),
                 ))
             }
+            DispatchPrompt::MoveSelectionToColumn => {
+                let column = text.parse::<usize>()?.saturating_sub(1);
+                Ok(Dispatches::one(Dispatch::ToEditor(MoveSelectionToColumn(
+                    column,
+                ))))
+            }
+            DispatchPrompt::JumpToPercentOfLine => {
+                let percent = text.trim().trim_end_matches('%').parse::<usize>()?;
+                Ok(Dispatches::one(Dispatch::ToEditor(JumpToPercentOfLine(
+                    percent,
+                ))))
+            }
+            DispatchPrompt::SetYankFlashDuration => {
+                let milliseconds = text.trim().trim_end_matches("ms").parse::<u64>()?;
+                Ok(Dispatches::one(Dispatch::SetYankFlashDuration(
+                    std::time::Duration::from_millis(milliseconds),
+                )))
+            }
+            DispatchPrompt::SetLargeFileThreshold => {
+                let bytes = text.trim().trim_end_matches('B').parse::<usize>()?;
+                Ok(Dispatches::one(Dispatch::SetLargeFileThreshold(bytes)))
+            }
+            DispatchPrompt::SetIncrementalReparseThreshold => {
+                let bytes = text.trim().trim_end_matches('B').parse::<usize>()?;
+                Ok(Dispatches::one(Dispatch::SetIncrementalReparseThreshold(
+                    bytes,
+                )))
+            }
+            DispatchPrompt::InsertDateTime => Ok(Dispatches::new(
+                [
+                    Dispatch::SetDateTimeFormat(text.to_string()),
+                    Dispatch::ToEditor(InsertDateTime),
+                ]
+                .to_vec(),
+            )),
+            DispatchPrompt::GoToByteOffset => {
+                let text = text.trim();
+                let byte_offset = match text.strip_prefix("0x") {
+                    Some(hexadecimal) => usize::from_str_radix(hexadecimal, 16)?,
+                    None => text.parse::<usize>()?,
+                };
+                Ok(Dispatches::one(Dispatch::ToEditor(GoToByteOffset(
+                    byte_offset,
+                ))))
+            }
+            DispatchPrompt::ParseErrorsToQuickfix => {
+                Ok(Dispatches::one(Dispatch::ParseErrorsToQuickfix {
+                    pattern: Some(text.trim())
+                        .filter(|pattern| !pattern.is_empty())
+                        .map(|pattern| pattern.to_string()),
+                }))
+            }
+            DispatchPrompt::OpenScratch => Ok(Dispatches::one(Dispatch::OpenScratch {
+                extension: text.trim().to_string(),
+            })),
+            DispatchPrompt::SaveScratchAs { component_id } => {
+                Ok(Dispatches::one(Dispatch::SaveScratchAs {
+                    component_id,
+                    path: text.trim().to_string(),
+                }))
+            }
             DispatchPrompt::RenameSymbol => Ok(Dispatches::new(vec![Dispatch::RenameSymbol {
                 new_name: text.to_string(),
             }])),
+            DispatchPrompt::WrapSelectionInTag => Ok(Dispatches::one(Dispatch::ToEditor(
+                WrapSelectionInTag(text.to_string()),
+            ))),
+            DispatchPrompt::PipeSelection => Ok(Dispatches::one(Dispatch::ToEditor(
+                PipeSelection(text.to_string()),
+            ))),
+            DispatchPrompt::InsertShellOutput => Ok(Dispatches::one(Dispatch::ToEditor(
+                InsertShellOutput(text.to_string()),
+            ))),
+            DispatchPrompt::SelectLinesMatching { matching } => {
+                Ok(Dispatches::one(Dispatch::ToEditor(if matching {
+                    SelectLinesMatching(text.to_string())
+                } else {
+                    SelectLinesNotMatching(text.to_string())
+                })))
+            }
+            DispatchPrompt::SelectByQuery => Ok(Dispatches::one(Dispatch::ToEditor(
+                SelectByQuery(text.to_string()),
+            ))),
             DispatchPrompt::UpdateLocalSearchConfigSearch {
                 scope,
                 show_config_after_enter,
@@ -2404,6 +3867,12 @@ impl DispatchPrompt {
                 }]
                 .to_vec(),
             )),
+            DispatchPrompt::SaveSearchPreset { scope } => {
+                Ok(Dispatches::one(Dispatch::SaveSearchPreset {
+                    scope,
+                    name: text.to_string(),
+                }))
+            }
             #[cfg(test)]
             DispatchPrompt::SetContent => Ok(Dispatches::new(
                 [Dispatch::ToEditor(SetContent(text.to_string()))].to_vec(),
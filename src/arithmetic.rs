@@ -0,0 +1,229 @@
+//! A tiny evaluator of arithmetic expressions, for example `(1 + 2) * 3 % 4` or `200 * 15%`.
+//!
+//! `%` is the remainder operator when an operand follows it, otherwise it means percent.
+//! A sign only belongs to the following operand when it is attached to it,
+//! so `10 % -3` is a remainder while `50% - 1` is a percent.
+
+use itertools::Itertools;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Operator(char),
+}
+
+pub(crate) fn evaluate(expression: &str) -> anyhow::Result<f64> {
+    let (tokens, spaced) = tokenize(expression)?;
+    let mut parser = Parser {
+        tokens,
+        spaced,
+        index: 0,
+    };
+    let value = parser.expression()?;
+    if let Some(token) = parser.peek() {
+        anyhow::bail!("Unexpected {}", display_token(token))
+    }
+    Ok(value)
+}
+
+/// Integers are displayed without the fractional part,
+/// and the floating point error is rounded off, so that `0.1 + 0.2` is `0.3`.
+pub(crate) fn display(value: f64) -> String {
+    let value = (value * 1e10).round() / 1e10;
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        format!("{}", value as i64)
+    } else {
+        format!("{}", value)
+    }
+}
+
+fn display_token(token: Token) -> String {
+    match token {
+        Token::Number(number) => format!("number {}", display(number)),
+        Token::Operator(operator) => format!("'{}'", operator),
+    }
+}
+
+/// Returns the tokens, and whether each of them is followed by whitespace.
+fn tokenize(expression: &str) -> anyhow::Result<(Vec<Token>, Vec<bool>)> {
+    let mut tokens = Vec::new();
+    let mut spaced = Vec::new();
+    let mut chars = expression.chars().peekable();
+    while let Some(&char) = chars.peek() {
+        if char.is_whitespace() {
+            chars.next();
+            if let Some(spaced) = spaced.last_mut() {
+                *spaced = true
+            }
+            continue;
+        }
+        spaced.push(false);
+        match char {
+            '0'..='9' | '.' => {
+                let number: String = chars
+                    .peeking_take_while(|char| char.is_ascii_digit() || ['.', '_'].contains(char))
+                    .filter(|char| *char != '_')
+                    .collect();
+                tokens.push(Token::Number(
+                    number
+                        .parse()
+                        .map_err(|_| anyhow::anyhow!("Invalid number {:?}", number))?,
+                ))
+            }
+            '+' | '-' | '*' | '/' | '%' | '(' | ')' => {
+                chars.next();
+                tokens.push(Token::Operator(char))
+            }
+            char => anyhow::bail!("Unexpected character {:?}", char),
+        }
+    }
+    Ok((tokens, spaced))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    /// Whether each token is followed by whitespace.
+    spaced: Vec<bool>,
+    index: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<Token> {
+        self.tokens.get(self.index).copied()
+    }
+
+    fn next_if_operator(&mut self, operators: &[char]) -> Option<char> {
+        match self.peek() {
+            Some(Token::Operator(operator)) if operators.contains(&operator) => {
+                self.index += 1;
+                Some(operator)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns true if the token after the next one starts an operand, that is a number
+    /// or a parenthesized expression, optionally preceded by signs attached to it,
+    /// which means the next `%` is the remainder operator instead of percent.
+    fn is_operand_after_next(&self) -> bool {
+        let mut index = self.index + 1;
+        while matches!(self.tokens.get(index), Some(Token::Operator('-' | '+')))
+            && !self.spaced[index]
+        {
+            index += 1
+        }
+        matches!(
+            self.tokens.get(index),
+            Some(Token::Number(_) | Token::Operator('('))
+        )
+    }
+
+    fn expression(&mut self) -> anyhow::Result<f64> {
+        let mut value = self.term()?;
+        while let Some(operator) = self.next_if_operator(&['+', '-']) {
+            let right = self.term()?;
+            value = if operator == '+' {
+                value + right
+            } else {
+                value - right
+            };
+        }
+        Ok(value)
+    }
+
+    fn term(&mut self) -> anyhow::Result<f64> {
+        let mut value = self.unary()?;
+        loop {
+            let operator = if self.peek() == Some(Token::Operator('%')) {
+                if !self.is_operand_after_next() {
+                    break;
+                }
+                self.next_if_operator(&['%'])
+            } else {
+                self.next_if_operator(&['*', '/'])
+            };
+            let Some(operator) = operator else { break };
+            let right = self.unary()?;
+            if operator != '*' && right == 0.0 {
+                anyhow::bail!("Division by zero")
+            }
+            value = match operator {
+                '*' => value * right,
+                '/' => value / right,
+                _ => value % right,
+            };
+        }
+        Ok(value)
+    }
+
+    fn unary(&mut self) -> anyhow::Result<f64> {
+        match self.next_if_operator(&['-', '+']) {
+            Some('-') => Ok(-self.unary()?),
+            Some(_) => self.unary(),
+            None => self.percent(),
+        }
+    }
+
+    fn percent(&mut self) -> anyhow::Result<f64> {
+        let mut value = self.primary()?;
+        while self.peek() == Some(Token::Operator('%')) && !self.is_operand_after_next() {
+            self.index += 1;
+            value /= 100.0;
+        }
+        Ok(value)
+    }
+
+    fn primary(&mut self) -> anyhow::Result<f64> {
+        let token = self.peek();
+        self.index += 1;
+        match token {
+            Some(Token::Number(number)) => Ok(number),
+            Some(Token::Operator('(')) => {
+                let value = self.expression()?;
+                if self.next_if_operator(&[')']).is_none() {
+                    anyhow::bail!("Missing ')'")
+                }
+                Ok(value)
+            }
+            Some(token) => anyhow::bail!("Unexpected {}", display_token(token)),
+            None => anyhow::bail!("Unexpected end of expression"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_arithmetic {
+    use super::{display, evaluate};
+
+    fn run(expression: &str) -> String {
+        display(evaluate(expression).unwrap())
+    }
+
+    #[test]
+    fn precedence_and_parentheses() {
+        assert_eq!(run("1 + 2 * 3"), "7");
+        assert_eq!(run("(1 + 2) * 3"), "9");
+        assert_eq!(run("-(4 - 6) / 4"), "0.5");
+        assert_eq!(run("1_000 * 2"), "2000");
+        assert_eq!(run("0.1 + 0.2"), "0.3");
+    }
+
+    #[test]
+    fn remainder_and_percent() {
+        assert_eq!(run("10 % 4"), "2");
+        assert_eq!(run("200 * 15%"), "30");
+        assert_eq!(run("50% + 1"), "1.5");
+        assert_eq!(run("10 % -3"), "1");
+        assert_eq!(run("10 % -(1 + 2)"), "1");
+        assert_eq!(run("50% - 1"), "-0.5");
+    }
+
+    #[test]
+    fn errors() {
+        assert!(evaluate("1 +").is_err());
+        assert!(evaluate("(1 + 2").is_err());
+        assert!(evaluate("1 / 0").is_err());
+        assert!(evaluate("x + 1").is_err());
+        assert!(evaluate("1 2").is_err());
+    }
+}
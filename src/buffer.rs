@@ -5,15 +5,18 @@ use crate::tree_sitter_traversal::{traverse, Order};
 use crate::{
     char_index_range::CharIndexRange,
     components::{editor::Movement, suggestive_editor::Decoration},
-    context::{LocalSearchConfig, LocalSearchConfigMode},
+    context::{FinalNewline, LocalSearchConfig, LocalSearchConfigMode},
     edit::{Action, ActionGroup, Edit, EditTransaction},
+    encoding::Encoding,
     position::Position,
     selection::{CharIndex, Selection, SelectionSet},
     selection_mode::{AstGrep, ByteRange},
+    snippet::Tabstop,
     syntax_highlight::{HighlighedSpan, HighlighedSpans},
     undo_tree::{Applicable, OldNew, UndoTree},
     utils::find_previous,
 };
+use anyhow::Context;
 use itertools::Itertools;
 use regex::Regex;
 use ropey::Rope;
@@ -21,13 +24,29 @@ use shared::{
     canonicalized_path::CanonicalizedPath,
     language::{self, Language},
 };
-use std::{collections::HashSet, ops::Range};
+use std::{
+    collections::HashSet,
+    ops::{Range, RangeInclusive},
+};
 use tree_sitter::{Node, Parser, Tree};
 
+/// Buffers larger than this (in bytes) are reparsed incrementally,
+/// and skip the structural validation of syntax-node edits.
+pub(crate) const DEFAULT_INCREMENTAL_REPARSE_THRESHOLD: usize = 200_000;
+
+/// Files larger than this (in bytes) are opened in large-file mode,
+/// where they are neither parsed by tree-sitter nor highlighted, see [`Buffer::is_large_file_mode`].
+pub(crate) const DEFAULT_LARGE_FILE_THRESHOLD: usize = 5_000_000;
+
 #[derive(Clone)]
 pub(crate) struct Buffer {
     rope: Rope,
     tree: Option<Tree>,
+    /// The edits made to the content since the tree was last parsed.
+    ///
+    /// They are only applied to the tree when it is reparsed, so that the tree
+    /// remains untouched until then (e.g. in insert mode), see [`Buffer::reparse_tree`].
+    pending_input_edits: Vec<tree_sitter::InputEdit>,
     treesitter_language: Option<tree_sitter::Language>,
     undo_tree: UndoTree<Patch>,
     language: Option<Language>,
@@ -37,6 +56,17 @@ pub(crate) struct Buffer {
     diagnostics: Vec<Diagnostic>,
     quickfix_list_items: Vec<QuickfixListItem>,
     decorations: Vec<Decoration>,
+    /// The pending tabstops of the last expanded snippet, in the order of navigation.
+    tabstops: Vec<Tabstop>,
+    incremental_reparse_threshold: usize,
+    /// The line ending detected when the file was opened, all line endings are normalized to it on save.
+    line_ending: LineEnding,
+    /// The encoding detected when the file was opened, which is also used to save the file.
+    encoding: Encoding,
+    large_file_mode: bool,
+    /// Incremented whenever the content, tree or any of the spans of this buffer changes,
+    /// so that derived data can be cached cheaply.
+    version: usize,
 }
 
 #[derive(Clone, PartialEq, Eq, Hash, Debug)]
@@ -47,6 +77,64 @@ pub(crate) struct Line {
     pub(crate) content: String,
 }
 
+/// A top-level import statement, see [`Buffer::import_statements`].
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub(crate) struct ImportStatement {
+    /// Includes the comments preceding the statement.
+    pub(crate) byte_range: Range<usize>,
+    /// Excludes the comments preceding the statement.
+    pub(crate) statement_byte_range: Range<usize>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub(crate) enum LineEnding {
+    #[default]
+    Lf,
+    Crlf,
+}
+
+impl LineEnding {
+    /// Returns the line ending used by the majority of the lines of `content`,
+    /// paired with whether `content` mixes both line endings.
+    ///
+    /// Content without any line break is deemed to use `\n`.
+    pub(crate) fn detect(content: &str) -> (LineEnding, bool) {
+        let crlf_count = content.matches("\r\n").count();
+        let lf_count = content.matches('\n').count() - crlf_count;
+        let line_ending = if crlf_count > lf_count {
+            LineEnding::Crlf
+        } else {
+            LineEnding::Lf
+        };
+        (line_ending, crlf_count > 0 && lf_count > 0)
+    }
+
+    pub(crate) fn display(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::Crlf => "CRLF",
+        }
+    }
+
+    pub(crate) fn toggle(self) -> LineEnding {
+        match self {
+            LineEnding::Lf => LineEnding::Crlf,
+            LineEnding::Crlf => LineEnding::Lf,
+        }
+    }
+
+    /// Convert every line ending of `content` to this line ending.
+    pub(crate) fn normalize(self, content: String) -> String {
+        match self {
+            LineEnding::Lf if content.contains("\r\n") => content.replace("\r\n", "\n"),
+            LineEnding::Crlf if LineEnding::detect(&content) != (LineEnding::Crlf, false) => {
+                content.replace("\r\n", "\n").replace('\n', "\r\n")
+            }
+            _ => content,
+        }
+    }
+}
+
 impl Buffer {
     pub(crate) fn new(language: Option<tree_sitter::Language>, text: &str) -> Self {
         Self {
@@ -62,6 +150,7 @@ impl Buffer {
                         .and_then(|_| parser.parse(text, None))
                 })
             },
+            pending_input_edits: Vec::new(),
             path: None,
             highlighted_spans: HighlighedSpans::default(),
             bookmarks: Vec::new(),
@@ -69,21 +158,52 @@ impl Buffer {
             undo_tree: UndoTree::new(),
             diagnostics: Vec::new(),
             quickfix_list_items: Vec::new(),
+            tabstops: Vec::new(),
+            incremental_reparse_threshold: DEFAULT_INCREMENTAL_REPARSE_THRESHOLD,
+            line_ending: LineEnding::default(),
+            encoding: Encoding::default(),
+            large_file_mode: false,
+            version: 0,
         }
     }
+
+    pub(crate) fn version(&self) -> usize {
+        self.version
+    }
+
+    pub(crate) fn set_incremental_reparse_threshold(&mut self, threshold: usize) {
+        self.incremental_reparse_threshold = threshold
+    }
+
+    /// Returns true if this buffer was opened from a file above the large file threshold,
+    /// in which case it has no language, so structural selection modes, syntax highlighting
+    /// and the language server are unavailable.
+    pub(crate) fn is_large_file_mode(&self) -> bool {
+        self.large_file_mode
+    }
+
+    /// Returns true if this buffer is above the incremental reparse threshold.
+    pub(crate) fn is_large(&self) -> bool {
+        self.rope.len_bytes() > self.incremental_reparse_threshold
+    }
     pub(crate) fn clear_quickfix_list_items(&mut self) {
+        self.version += 1;
         self.quickfix_list_items.clear()
     }
     pub(crate) fn update_quickfix_list_items(
         &mut self,
         quickfix_list_items: Vec<QuickfixListItem>,
     ) {
+        self.version += 1;
         self.quickfix_list_items = quickfix_list_items
     }
     pub(crate) fn reload(&mut self) -> anyhow::Result<()> {
         if let Some(path) = self.path() {
-            let updated_content = path.read()?;
+            // Decoded like in `Buffer::from_path`, so that the byte order mark is not read as content
+            let (encoding, updated_content) = Encoding::decode(&std::fs::read(&path)?)
+                .with_context(|| format!("Failed to reload {}", path.display_absolute()))?;
 
+            self.encoding = encoding;
             self.update_content(&updated_content, SelectionSet::default())?;
         }
         Ok(())
@@ -99,7 +219,32 @@ impl Buffer {
         decorations.clone_into(&mut self.decorations)
     }
 
+    pub(crate) fn set_tabstops(&mut self, tabstops: Vec<Tabstop>) {
+        self.tabstops = tabstops
+    }
+
+    /// Remove and return all the pending tabstops.
+    pub(crate) fn take_tabstops(&mut self) -> Vec<Tabstop> {
+        std::mem::take(&mut self.tabstops)
+    }
+
+    /// Remove and return the ranges of the next tabstop.
+    /// There can be more than one range, for example when a snippet is expanded by multiple cursors.
+    pub(crate) fn take_next_tabstop(&mut self) -> Vec<CharIndexRange> {
+        let Some(number) = self.tabstops.first().map(|tabstop| tabstop.number) else {
+            return Vec::new();
+        };
+        let (next, rest) = std::mem::take(&mut self.tabstops)
+            .into_iter()
+            .partition(|tabstop| tabstop.number == number);
+        self.tabstops = rest;
+        next.into_iter()
+            .map(|tabstop: Tabstop| tabstop.range)
+            .collect()
+    }
+
     pub(crate) fn save_bookmarks(&mut self, new_ranges: Vec<CharIndexRange>) {
+        self.version += 1;
         let old_ranges = std::mem::take(&mut self.bookmarks)
             .into_iter()
             .collect::<HashSet<_>>();
@@ -116,12 +261,12 @@ impl Buffer {
         self.path.clone()
     }
 
-    #[cfg(test)]
     pub(crate) fn set_path(&mut self, path: CanonicalizedPath) {
         self.path = Some(path);
     }
 
     pub(crate) fn set_diagnostics(&mut self, diagnostics: Vec<lsp_types::Diagnostic>) {
+        self.version += 1;
         self.diagnostics = diagnostics
             .into_iter()
             .filter_map(|diagnostic| Diagnostic::try_from(self, diagnostic).ok())
@@ -196,37 +341,6 @@ impl Buffer {
         let tree = language
             .map(|language| parser.set_language(&language))
             .and_then(|_| parser.parse(text, None));
-        // let start_char_index = edit.start;
-        // let old_end_char_index = edit.end();
-        // let new_end_char_index = edit.start + edit.new.len_chars();
-
-        // let start_byte = self.char_to_byte(start_char_index);
-        // let old_end_byte = self.char_to_byte(old_end_char_index);
-        // let start_position = self.char_to_point(start_char_index);
-        // let old_end_position = self.char_to_point(old_end_char_index);
-
-        // self.rope.remove(edit.start.0..edit.end().0);
-        // self.rope
-        //     .insert(edit.start.0, edit.new.to_string().as_str());
-
-        // let new_end_byte = self.char_to_byte(new_end_char_index);
-        // let new_end_position = self.char_to_point(new_end_char_index);
-
-        // let mut parser = tree_sitter::Parser::new();
-        // parser.set_language(self.tree.language()).unwrap();
-        // self.tree.edit(&InputEdit {
-        //     start_byte,
-        //     old_end_byte,
-        //     new_end_byte,
-        //     start_position,
-        //     old_end_position,
-        //     new_end_position,
-        // });
-
-        // self.tree = parser
-        //     .parse(&self.rope.to_string(), Some(&self.tree))
-        //     .unwrap();
-
         (Rope::from_str(text), tree)
     }
 
@@ -249,12 +363,30 @@ impl Buffer {
             .unwrap_or(false)
     }
 
-    pub(crate) fn update_highlighted_spans(&mut self, spans: HighlighedSpans) {
-        self.highlighted_spans = spans;
+    /// Replace the highlighted spans that intersect with `byte_range` by `spans`.
+    pub(crate) fn update_highlighted_spans(
+        &mut self,
+        byte_range: Range<usize>,
+        spans: HighlighedSpans,
+    ) {
+        self.highlighted_spans.0.retain(|span| {
+            span.byte_range.end <= byte_range.start || span.byte_range.start >= byte_range.end
+        });
+        self.highlighted_spans.0.extend(spans.0);
+        // Drop the spans beyond the end of the content, which might have shrunk since they were computed
+        let len_bytes = self.rope.len_bytes();
+        self.highlighted_spans
+            .0
+            .retain(|span| span.byte_range.end <= len_bytes);
+        self.highlighted_spans
+            .0
+            .sort_by_key(|span| span.byte_range.start);
     }
 
     pub(crate) fn update(&mut self, text: &str) {
+        self.version += 1;
         (self.rope, self.tree) = Self::get_rope_and_tree(self.treesitter_language.clone(), text);
+        self.pending_input_edits.clear();
     }
 
     pub(crate) fn get_line_by_char_index(&self, char_index: CharIndex) -> anyhow::Result<Rope> {
@@ -457,6 +589,7 @@ impl Buffer {
         };
 
         self.add_undo_patch(current_buffer_state, new_buffer_state.clone(), &before);
+        self.version += 1;
         if reparse_tree {
             self.reparse_tree()?;
         }
@@ -478,11 +611,28 @@ impl Buffer {
                 })
                 .collect_vec();
 
+        let start_byte = self.char_to_byte(edit.range.start)?;
+        let old_end_byte = self.char_to_byte(edit.end())?;
+        let start_position = self.byte_to_point(start_byte)?;
+        let old_end_position = self.byte_to_point(old_end_byte)?;
+
         // Update the content
         self.rope.try_remove(edit.range.start.0..edit.end().0)?;
         self.rope
             .try_insert(edit.range.start.0, edit.new.to_string().as_str())?;
 
+        // Record the edit, so that the tree can be reused for incremental reparsing
+        let new_end_byte = start_byte + edit.new.len_bytes();
+        let new_end_position = self.byte_to_point(new_end_byte)?;
+        self.pending_input_edits.push(tree_sitter::InputEdit {
+            start_byte,
+            old_end_byte,
+            new_end_byte,
+            start_position,
+            old_end_position,
+            new_end_position,
+        });
+
         // Update all the positional spans (by using the char index ranges computed before the content is updated
         self.quickfix_list_items = quickfix_list_items_with_char_index_range
             .into_iter()
@@ -499,6 +649,15 @@ impl Buffer {
             .into_iter()
             .filter_map(|bookmark| bookmark.apply_edit(edit))
             .collect();
+        self.tabstops = std::mem::take(&mut self.tabstops)
+            .into_iter()
+            .filter_map(|tabstop| {
+                Some(Tabstop {
+                    range: tabstop.range.apply_edit(edit)?,
+                    ..tabstop
+                })
+            })
+            .collect();
         self.diagnostics = std::mem::take(&mut self.diagnostics)
             .into_iter()
             .filter_map(|diagnostic| {
@@ -582,12 +741,83 @@ impl Buffer {
         }
     }
 
+    /// Returns the range of the tag name enclosing `range`, paired with the range of the
+    /// name of its matching opening/closing tag.
+    ///
+    /// HTML, JSX and XML elements are recognized, and `None` is returned if the element has syntax errors.
+    pub(crate) fn get_paired_tag_names(
+        &self,
+        range: CharIndexRange,
+    ) -> Option<(CharIndexRange, CharIndexRange)> {
+        const OPENING_TAG_KINDS: &[&str] = &["start_tag", "jsx_opening_element", "STag"];
+        const CLOSING_TAG_KINDS: &[&str] = &["end_tag", "jsx_closing_element", "ETag"];
+        let tag_name = |tag: Node<'_>| {
+            tag.child_by_field_name("name").or_else(|| {
+                (0..tag.named_child_count())
+                    .filter_map(|index| tag.named_child(index))
+                    .find(|child| matches!(child.kind(), "tag_name" | "Name"))
+            })
+        };
+        let start = self.char_to_byte(range.start).ok()?;
+        let end = self.char_to_byte(range.end).ok()?;
+        // The synced tree is used, because the tree is not reparsed in insert mode
+        let tree = self.synced_tree()?;
+        let node = tree.root_node().descendant_for_byte_range(start, end)?;
+        let tag = std::iter::successors(Some(node), |node| node.parent()).find(|node| {
+            OPENING_TAG_KINDS.contains(&node.kind()) || CLOSING_TAG_KINDS.contains(&node.kind())
+        })?;
+        let name = tag_name(tag)?;
+        if start < name.start_byte() || end > name.end_byte() {
+            return None;
+        }
+        let element = tag.parent()?;
+        if element.has_error() {
+            return None;
+        }
+        let partner = if OPENING_TAG_KINDS.contains(&tag.kind()) {
+            element
+                .child(element.child_count().saturating_sub(1))
+                .filter(|partner| CLOSING_TAG_KINDS.contains(&partner.kind()))?
+        } else {
+            element
+                .child(0)
+                .filter(|partner| OPENING_TAG_KINDS.contains(&partner.kind()))?
+        };
+        let partner_name = tag_name(partner)?;
+        Some((
+            self.byte_range_to_char_index_range(&name.byte_range())
+                .ok()?,
+            self.byte_range_to_char_index_range(&partner_name.byte_range())
+                .ok()?,
+        ))
+    }
+
     pub(crate) fn from_path(
         path: &CanonicalizedPath,
         enable_tree_sitter: bool,
     ) -> anyhow::Result<Buffer> {
-        let content = path.read()?;
-        let language = if enable_tree_sitter {
+        Self::from_path_(path, enable_tree_sitter, None)
+    }
+
+    /// Like [`Buffer::from_path`] with tree-sitter enabled,
+    /// unless the file is larger than `large_file_threshold` bytes.
+    pub(crate) fn from_path_with_large_file_threshold(
+        path: &CanonicalizedPath,
+        large_file_threshold: usize,
+    ) -> anyhow::Result<Buffer> {
+        Self::from_path_(path, true, Some(large_file_threshold))
+    }
+
+    fn from_path_(
+        path: &CanonicalizedPath,
+        enable_tree_sitter: bool,
+        large_file_threshold: Option<usize>,
+    ) -> anyhow::Result<Buffer> {
+        let bytes = std::fs::read(path)?;
+        let large_file_mode = large_file_threshold.is_some_and(|threshold| bytes.len() > threshold);
+        let (encoding, content) = Encoding::decode(&bytes)
+            .with_context(|| format!("Failed to open {}", path.display_absolute()))?;
+        let language = if enable_tree_sitter && !large_file_mode {
             language::from_path(path)
         } else {
             None
@@ -602,19 +832,90 @@ impl Buffer {
 
         buffer.path = Some(path.clone());
         buffer.language = language;
+        let (line_ending, is_mixed) = LineEnding::detect(&content);
+        if is_mixed {
+            log::warn!(
+                "{} has mixed line endings, they will be normalized to {} on save",
+                path.display_absolute(),
+                line_ending.display()
+            )
+        }
+        buffer.line_ending = line_ending;
+        buffer.encoding = encoding;
+        buffer.large_file_mode = large_file_mode;
 
         Ok(buffer)
     }
 
     pub(crate) fn reparse_tree(&mut self) -> anyhow::Result<()> {
+        // Only large buffers are reparsed incrementally,
+        // because a fresh parse is cheap enough for the rest
+        self.reparse_tree_(self.is_large())
+    }
+
+    fn reparse_tree_(&mut self, incremental: bool) -> anyhow::Result<()> {
+        self.version += 1;
         let mut parser = tree_sitter::Parser::new();
-        if let Some(tree) = self.tree.as_ref() {
+        if let Some(tree) = self.synced_tree() {
             parser.set_language(&tree.language())?;
-            self.tree = parser.parse(&self.rope.to_string(), None);
+            let old_tree = if incremental { Some(&tree) } else { None };
+            self.tree = parser.parse(&self.rope.to_string(), old_tree);
         }
+        self.pending_input_edits.clear();
         Ok(())
     }
 
+    /// Returns the last parsed tree, with the edits made since then applied to it,
+    /// so that the byte ranges of its nodes correspond to the current content.
+    ///
+    /// Note that the nodes are not reparsed, so their kinds and text might be stale.
+    pub(crate) fn synced_tree(&self) -> Option<Tree> {
+        let mut tree = self.tree.clone()?;
+        for input_edit in &self.pending_input_edits {
+            tree.edit(input_edit);
+        }
+        Some(tree)
+    }
+
+    /// Apply the edits of `edit_transaction` without recording undo history,
+    /// then reparse the tree incrementally.
+    ///
+    /// Returns the edits that revert this change, which should be passed to `revert_edits`.
+    pub(crate) fn apply_edits_transiently(
+        &mut self,
+        edit_transaction: &EditTransaction,
+    ) -> anyhow::Result<Vec<Edit>> {
+        let mut reverse_edits = Vec::new();
+        for edit in edit_transaction.edits() {
+            let old = self.slice(&edit.range)?;
+            self.apply_edit(edit)?;
+            reverse_edits.push(Edit {
+                range: (edit.range.start..edit.range.start + edit.new.len_chars()).into(),
+                new: old,
+            });
+        }
+        self.reparse_tree_(true)?;
+        Ok(reverse_edits)
+    }
+
+    /// Revert the change made by `apply_edits_transiently`.
+    ///
+    /// The tree is not reparsed, because it is assumed that this buffer will be edited again.
+    pub(crate) fn revert_edits(&mut self, reverse_edits: Vec<Edit>) -> anyhow::Result<()> {
+        reverse_edits
+            .iter()
+            .rev()
+            .try_for_each(|edit| self.apply_edit(edit))
+    }
+
+    fn byte_to_point(&self, byte: usize) -> anyhow::Result<tree_sitter::Point> {
+        let row = self.rope.try_byte_to_line(byte)?;
+        Ok(tree_sitter::Point {
+            row,
+            column: byte - self.rope.try_line_to_byte(row)?,
+        })
+    }
+
     pub(crate) fn get_formatted_content(&self) -> Option<String> {
         if let Some(content) = self.language.as_ref().and_then(|language| {
             language.formatter().map(|formatter| {
@@ -636,7 +937,9 @@ impl Buffer {
 
     pub(crate) fn save_without_formatting(&mut self) -> anyhow::Result<Option<CanonicalizedPath>> {
         if let Some(path) = &self.path.clone() {
-            path.write(&self.content())?;
+            std::fs::write(path, self.encoding.encode(&self.content())?)?;
+            // Selection modes like git hunks depend on the saved content
+            self.version += 1;
 
             Ok(Some(path.clone()))
         } else {
@@ -648,9 +951,16 @@ impl Buffer {
     pub(crate) fn save(
         &mut self,
         current_selection_set: SelectionSet,
+        final_newline: FinalNewline,
     ) -> anyhow::Result<Option<CanonicalizedPath>> {
-        if let Some(formatted_content) = self.get_formatted_content() {
-            self.update_content(&formatted_content, current_selection_set)?;
+        let content = self.line_ending.normalize(
+            final_newline.normalize(
+                self.get_formatted_content()
+                    .unwrap_or_else(|| self.content()),
+            ),
+        );
+        if content != self.content() {
+            self.update_content(&content, current_selection_set)?;
         }
 
         self.save_without_formatting()
@@ -673,10 +983,166 @@ impl Buffer {
         self.language.clone()
     }
 
-    #[cfg(test)]
+    /// Characters that are part of a word besides letters, digits and `_`.
+    pub(crate) fn word_chars(&self) -> &'static str {
+        self.language
+            .as_ref()
+            .unwrap_or(&Language::new())
+            .word_chars()
+    }
+
+    /// The first contiguous run of top-level import statements.
+    ///
+    /// Comments between import statements are attached to the statement that follows them.
+    /// For languages without import node kinds, the first run of lines that look like imports is used.
+    pub(crate) fn import_statements(&self) -> Vec<ImportStatement> {
+        let kinds = self
+            .language
+            .as_ref()
+            .map(|language| language.import_node_kinds())
+            .unwrap_or_default();
+        if let (Some(tree), false) = (&self.tree, kinds.is_empty()) {
+            let root = tree.root_node();
+            let mut cursor = root.walk();
+            let mut comment_start = None;
+            return root
+                .named_children(&mut cursor)
+                .skip_while(|node| !kinds.contains(&node.kind()))
+                .take_while(|node| kinds.contains(&node.kind()) || node.kind().contains("comment"))
+                .filter_map(|node| {
+                    if kinds.contains(&node.kind()) {
+                        Some(ImportStatement {
+                            byte_range: comment_start.take().unwrap_or(node.start_byte())
+                                ..node.end_byte(),
+                            statement_byte_range: node.byte_range(),
+                        })
+                    } else {
+                        comment_start.get_or_insert(node.start_byte());
+                        None
+                    }
+                })
+                .collect_vec();
+        }
+        let Ok(regex) = Regex::new(r"^\s*(import|use|from|require|using|#include)\b") else {
+            return Vec::new();
+        };
+        self.content()
+            .split_inclusive('\n')
+            .scan(0, |offset, line| {
+                let start = *offset;
+                *offset += line.len();
+                Some((start, line))
+            })
+            .skip_while(|(_, line)| !regex.is_match(line))
+            .take_while(|(_, line)| regex.is_match(line))
+            .map(|(start, line)| {
+                let byte_range = start..start + line.trim_end().len();
+                ImportStatement {
+                    statement_byte_range: byte_range.clone(),
+                    byte_range,
+                }
+            })
+            .collect_vec()
+    }
+
+    /// The byte range of [`Buffer::import_statements`].
+    pub(crate) fn import_block_byte_range(&self) -> Option<Range<usize>> {
+        let statements = self.import_statements();
+        Some(statements.first()?.byte_range.start..statements.last()?.byte_range.end)
+    }
+
+    pub(crate) fn line_ending(&self) -> LineEnding {
+        self.line_ending
+    }
+
+    pub(crate) fn encoding(&self) -> Encoding {
+        self.encoding
+    }
+
+    pub(crate) fn set_encoding(&mut self, encoding: Encoding) {
+        self.encoding = encoding
+    }
+
+    pub(crate) fn has_mixed_line_endings(&self) -> bool {
+        LineEnding::detect(&self.content()).1
+    }
+
+    /// Set the line ending of this buffer, and convert every line ending of its content to it,
+    /// returns `None` if the content is unchanged.
+    pub(crate) fn get_edit_transaction_of_line_ending(
+        &mut self,
+        line_ending: LineEnding,
+    ) -> anyhow::Result<Option<EditTransaction>> {
+        self.line_ending = line_ending;
+        let content = self.content();
+        let normalized = line_ending.normalize(content.clone());
+        if normalized == content {
+            return Ok(None);
+        }
+        self.get_edit_transaction(&normalized).map(Some)
+    }
+
+    /// Sort the statements of the import block alphabetically,
+    /// returns `None` if there is no import block or it is already sorted.
+    ///
+    /// If `group` is true, the statements are grouped by the convention of the language,
+    /// for example `std`, external and local crates in Rust,
+    /// and the groups are separated by an empty line.
+    pub(crate) fn get_edit_transaction_of_sort_imports(
+        &self,
+        group: bool,
+    ) -> anyhow::Result<Option<EditTransaction>> {
+        let statements = self.import_statements();
+        let (Some(first), Some(last)) = (statements.first(), statements.last()) else {
+            return Ok(None);
+        };
+        let block = first.byte_range.start..last.byte_range.end;
+        let content = self.content();
+        let is_rust = self.language.as_ref().and_then(|language| language.id())
+            == Some(language::LanguageId::new("rust"));
+        let sorted = statements
+            .iter()
+            .map(|statement| {
+                let text = &content[statement.statement_byte_range.clone()];
+                (
+                    if group {
+                        import_group(text, is_rust)
+                    } else {
+                        0
+                    },
+                    text,
+                    &content[statement.byte_range.clone()],
+                )
+            })
+            .sorted()
+            .group_by(|(group, _, _)| *group)
+            .into_iter()
+            .map(|(_, statements)| statements.map(|(_, _, text)| text).join("\n"))
+            .join("\n\n");
+        if sorted == content[block.clone()] {
+            return Ok(None);
+        }
+        Ok(Some(EditTransaction::from_action_groups(
+            [ActionGroup::new(
+                [Action::Edit(Edit {
+                    range: self.byte_range_to_char_index_range(&block)?,
+                    new: sorted.into(),
+                })]
+                .to_vec(),
+            )]
+            .to_vec(),
+        )))
+    }
+
     pub(crate) fn set_language(&mut self, language: Language) -> anyhow::Result<()> {
+        self.treesitter_language = language.tree_sitter_language();
         self.language = Some(language);
-        self.reparse_tree()
+        self.version += 1;
+        // The tree is parsed afresh, because the grammar might have changed
+        (_, self.tree) =
+            Self::get_rope_and_tree(self.treesitter_language.clone(), &self.rope.to_string());
+        self.pending_input_edits.clear();
+        Ok(())
     }
 
     pub(crate) fn treesitter_language(&self) -> Option<tree_sitter::Language> {
@@ -752,6 +1218,45 @@ impl Buffer {
 
     /// Get an `EditTransaction` by getting the line diffs between the content of this buffer and the given `new` string
     fn get_edit_transaction(&self, new: &str) -> anyhow::Result<EditTransaction> {
+        Ok(EditTransaction::from_action_groups(
+            self.get_line_diff_edits(new)?
+                .into_iter()
+                .map(|edit| ActionGroup {
+                    actions: [Action::Edit(edit)].to_vec(),
+                })
+                .collect_vec(),
+        ))
+    }
+
+    /// The changes from the content to its `formatted` version that touch the given `lines`,
+    /// so that formatting the whole content only affects those lines.
+    pub(crate) fn get_edit_transaction_of_formatted_lines(
+        &self,
+        formatted: &str,
+        lines: RangeInclusive<usize>,
+    ) -> anyhow::Result<EditTransaction> {
+        let edits = self
+            .get_line_diff_edits(formatted)?
+            .into_iter()
+            .map(|edit| {
+                let start = self.char_to_line(edit.range.start)?;
+                let end = self.char_to_line(edit.range.end)?;
+                Ok((start <= *lines.end() && end >= *lines.start()).then_some(edit))
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(EditTransaction::from_action_groups(
+            edits
+                .into_iter()
+                .flatten()
+                .map(|edit| ActionGroup {
+                    actions: [Action::Edit(edit)].to_vec(),
+                })
+                .collect_vec(),
+        ))
+    }
+
+    /// The line diffs between the content of this buffer and the given `new` string.
+    fn get_line_diff_edits(&self, new: &str) -> anyhow::Result<Vec<Edit>> {
         let old = self.rope.to_string();
         let new = new.to_string();
         let edits = {
@@ -819,14 +1324,7 @@ impl Buffer {
             edits
         };
 
-        Ok(EditTransaction::from_action_groups(
-            edits
-                .into_iter()
-                .map(|edit| ActionGroup {
-                    actions: [Action::Edit(edit)].to_vec(),
-                })
-                .collect_vec(),
-        ))
+        Ok(edits)
     }
 
     /// The boolean returned indicates whether the replacement causes any modification
@@ -836,7 +1334,28 @@ impl Buffer {
         current_selection_set: SelectionSet,
     ) -> anyhow::Result<(bool, SelectionSet)> {
         let before = self.rope.to_string();
-        let edit_transaction = match config.mode {
+        let edit_transaction = if config.select_replacements() {
+            self.get_edit_transaction_selecting_replacements(
+                &config,
+                &before,
+                current_selection_set.primary_selection(),
+            )?
+        } else {
+            self.get_edit_transaction_of_replace(&config, &before)?
+        };
+        let selection_set =
+            self.apply_edit_transaction(&edit_transaction, current_selection_set, true)?;
+        let after = self.content();
+        let modified = before != after;
+        Ok((modified, selection_set))
+    }
+
+    fn get_edit_transaction_of_replace(
+        &self,
+        config: &LocalSearchConfig,
+        before: &str,
+    ) -> anyhow::Result<EditTransaction> {
+        Ok(match config.mode {
             LocalSearchConfigMode::CaseAgnostic => {
                 let replaced =
                     CaseAgnostic::new(config.search()).replace_all(&before, config.replacement());
@@ -871,12 +1390,80 @@ impl Buffer {
                         .try_collect()?,
                 )
             }
+        })
+    }
+
+    /// Unlike `get_edit_transaction_of_replace`, which diffs the whole content,
+    /// every match is replaced individually, and then selected.
+    fn get_edit_transaction_selecting_replacements(
+        &self,
+        config: &LocalSearchConfig,
+        before: &str,
+        selection: &Selection,
+    ) -> anyhow::Result<EditTransaction> {
+        let replacement = config.replacement();
+        let replacements: Vec<(Range<usize>, String)> = match config.mode {
+            LocalSearchConfigMode::CaseAgnostic => {
+                let case_agnostic = CaseAgnostic::new(config.search());
+                case_agnostic
+                    .find_all(before)
+                    .into_iter()
+                    .sorted_by_key(|(range, _)| (range.range().start, range.range().end))
+                    // Different casings of the search might yield the same match
+                    .dedup_by(|(previous, _), (current, _)| {
+                        current.range().start < previous.range().end
+                    })
+                    .filter_map(|(range, old)| {
+                        let new =
+                            CaseAgnostic::replace(&old, &config.search(), &replacement).ok()?;
+                        Some((range.range().clone(), new))
+                    })
+                    .collect()
+            }
+            LocalSearchConfigMode::Regex(regex_config) => {
+                let regex = regex_config.to_regex(&config.search())?;
+                regex
+                    .captures_iter(before)
+                    .filter_map(|captures| {
+                        let range = captures.get(0)?.range();
+                        let mut new = String::new();
+                        captures.expand(&replacement, &mut new);
+                        Some((range, new))
+                    })
+                    .collect()
+            }
+            LocalSearchConfigMode::AstGrep => match self.treesitter_language() {
+                Some(language) => {
+                    AstGrep::replace(language, before, &config.search(), &replacement)?
+                        .into_iter()
+                        .map(|edit| -> anyhow::Result<_> {
+                            Ok((
+                                edit.position..edit.position + edit.deleted_length,
+                                String::from_utf8(edit.inserted_text)?,
+                            ))
+                        })
+                        .try_collect()?
+                }
+                None => Default::default(),
+            },
         };
-        let selection_set =
-            self.apply_edit_transaction(&edit_transaction, current_selection_set, true)?;
-        let after = self.content();
-        let modified = before != after;
-        Ok((modified, selection_set))
+        Ok(EditTransaction::from_action_groups(
+            replacements
+                .into_iter()
+                .map(|(range, new)| -> anyhow::Result<ActionGroup> {
+                    let range = self.byte_range_to_char_index_range(&range)?;
+                    let new: Rope = new.into();
+                    let selected = range.start..range.start + new.len_chars();
+                    Ok(ActionGroup::new(
+                        [
+                            Action::Edit(Edit { range, new }),
+                            Action::Select(selection.clone().set_range(selected.into())),
+                        ]
+                        .to_vec(),
+                    ))
+                })
+                .try_collect()?,
+        ))
     }
 
     pub(crate) fn char_index_range_to_byte_range(
@@ -902,7 +1489,10 @@ impl Buffer {
 mod test_buffer {
     use itertools::Itertools;
 
-    use crate::selection::SelectionSet;
+    use crate::{
+        edit::{Action, ActionGroup, Edit, EditTransaction},
+        selection::{CharIndex, SelectionSet},
+    };
 
     use super::Buffer;
 
@@ -999,6 +1589,7 @@ fn f(
                     escaped: true,
                     case_sensitive: false,
                     match_whole_word: false,
+                    multiline: false,
                 }))
                 .set_search("hel.".to_string())
                 .set_replacment("wow".to_string())
@@ -1015,6 +1606,7 @@ fn f(
                     escaped: false,
                     case_sensitive: false,
                     match_whole_word: false,
+                    multiline: false,
                 }))
                 .set_search(r"(\d+)".to_string())
                 .set_replacment(r"($1)".to_string())
@@ -1043,6 +1635,7 @@ fn f(
 
         use crate::{
             buffer::Buffer,
+            context::FinalNewline,
             selection::{CharIndex, SelectionSet},
         };
         use shared::canonicalized_path::CanonicalizedPath;
@@ -1068,7 +1661,9 @@ fn f(
                 buffer.update(" fn main\n() {}");
 
                 // Save the buffer
-                buffer.save(SelectionSet::default()).unwrap();
+                buffer
+                    .save(SelectionSet::default(), FinalNewline::Preserve)
+                    .unwrap();
 
                 // Expect the output is formatted
                 let saved_content = path.read().unwrap();
@@ -1096,7 +1691,9 @@ fn f(
                 let original = " fn main\n() {}";
                 buffer.update(original);
 
-                buffer.save(SelectionSet::default()).unwrap();
+                buffer
+                    .save(SelectionSet::default(), FinalNewline::Preserve)
+                    .unwrap();
 
                 // Expect the buffer is formatted
                 assert_ne!(buffer.rope.to_string(), original);
@@ -1120,7 +1717,9 @@ fn f(
                 buffer.update("fn main() {");
 
                 // Save the buffer
-                buffer.save(SelectionSet::default()).unwrap();
+                buffer
+                    .save(SelectionSet::default(), FinalNewline::Preserve)
+                    .unwrap();
 
                 // Expect the buffer remain unchanged,
                 // because the syntax node is invalid
@@ -1143,12 +1742,138 @@ fn f(
                 // but not to the formatter
                 assert!(!buffer.tree.as_ref().unwrap().root_node().has_error());
 
-                buffer.save(SelectionSet::default()).unwrap();
+                buffer
+                    .save(SelectionSet::default(), FinalNewline::Preserve)
+                    .unwrap();
 
                 // Expect the buffer remain unchanged
                 assert_eq!(buffer.rope.to_string(), code);
             })
         }
+
+        #[test]
+        fn should_normalize_final_newline() {
+            run_test(|path, mut buffer| {
+                // Invalid Rust code is not formatted
+                buffer.update("fn main() {");
+                buffer
+                    .save(SelectionSet::default(), FinalNewline::Ensure)
+                    .unwrap();
+                assert_eq!(path.read().unwrap(), "fn main() {\n");
+
+                buffer.update("fn main() {\n\n\n");
+                buffer
+                    .save(SelectionSet::default(), FinalNewline::Ensure)
+                    .unwrap();
+                assert_eq!(path.read().unwrap(), "fn main() {\n");
+
+                buffer
+                    .save(SelectionSet::default(), FinalNewline::Trim)
+                    .unwrap();
+                assert_eq!(path.read().unwrap(), "fn main() {");
+                assert_eq!(buffer.rope.to_string(), "fn main() {");
+            })
+        }
+    }
+
+    mod line_ending {
+        use tempfile::tempdir;
+
+        use crate::{
+            buffer::{Buffer, LineEnding},
+            context::FinalNewline,
+            selection::SelectionSet,
+        };
+        use shared::canonicalized_path::CanonicalizedPath;
+
+        #[test]
+        fn detect() {
+            assert_eq!(LineEnding::detect("a"), (LineEnding::Lf, false));
+            assert_eq!(LineEnding::detect("a\nb\n"), (LineEnding::Lf, false));
+            assert_eq!(LineEnding::detect("a\r\nb\r\n"), (LineEnding::Crlf, false));
+            assert_eq!(
+                LineEnding::detect("a\r\nb\r\nc\n"),
+                (LineEnding::Crlf, true)
+            );
+            assert_eq!(LineEnding::detect("a\r\nb\nc\n"), (LineEnding::Lf, true));
+        }
+
+        #[test]
+        fn should_preserve_line_ending_on_save() {
+            let dir = tempdir().unwrap();
+            let file_path = dir.path().join("main.txt");
+            std::fs::write(&file_path, "a\r\nb\r\nc\n").unwrap();
+            let path = CanonicalizedPath::try_from(file_path).unwrap();
+
+            let mut buffer = Buffer::from_path(&path, false).unwrap();
+            assert_eq!(buffer.line_ending(), LineEnding::Crlf);
+            assert!(buffer.has_mixed_line_endings());
+
+            buffer.update("a\r\nb\r\nc\nd\n");
+            buffer
+                .save(SelectionSet::default(), FinalNewline::Preserve)
+                .unwrap();
+            assert_eq!(path.read().unwrap(), "a\r\nb\r\nc\r\nd\r\n");
+            assert!(!buffer.has_mixed_line_endings());
+        }
+
+        #[test]
+        fn large_file_mode() -> anyhow::Result<()> {
+            let dir = tempdir()?;
+            let file_path = dir.path().join("main.rs");
+            std::fs::write(&file_path, "fn main() {}")?;
+            let path = CanonicalizedPath::try_from(file_path)?;
+
+            let buffer = Buffer::from_path_with_large_file_threshold(&path, 100)?;
+            assert!(!buffer.is_large_file_mode());
+            assert!(buffer.language().is_some());
+            assert!(buffer.tree().is_some());
+
+            let buffer = Buffer::from_path_with_large_file_threshold(&path, 10)?;
+            assert!(buffer.is_large_file_mode());
+            assert!(buffer.language().is_none());
+            assert!(buffer.tree().is_none());
+            assert_eq!(buffer.content(), "fn main() {}");
+            Ok(())
+        }
+
+        #[test]
+        fn should_preserve_utf8_bom_on_save() -> anyhow::Result<()> {
+            let dir = tempdir()?;
+            let file_path = dir.path().join("main.txt");
+            std::fs::write(&file_path, b"\xEF\xBB\xBFhello")?;
+            let path = CanonicalizedPath::try_from(file_path)?;
+
+            let mut buffer = Buffer::from_path(&path, false)?;
+            assert_eq!(buffer.encoding(), crate::encoding::Encoding::Utf8Bom);
+            assert_eq!(buffer.content(), "hello");
+
+            buffer.update("bye");
+            buffer.save_without_formatting()?;
+            assert_eq!(std::fs::read(path)?, b"\xEF\xBB\xBFbye");
+            Ok(())
+        }
+
+        #[test]
+        fn reload_should_decode_the_content() -> anyhow::Result<()> {
+            let dir = tempdir()?;
+            let file_path = dir.path().join("main.txt");
+            std::fs::write(&file_path, b"\xEF\xBB\xBFhello")?;
+            let path = CanonicalizedPath::try_from(file_path)?;
+
+            let mut buffer = Buffer::from_path(&path, false)?;
+            std::fs::write(&path, b"\xEF\xBB\xBFbye")?;
+            buffer.reload()?;
+            assert_eq!(buffer.content(), "bye");
+            buffer.save_without_formatting()?;
+            assert_eq!(std::fs::read(&path)?, b"\xEF\xBB\xBFbye");
+
+            std::fs::write(&path, crate::encoding::Encoding::Utf16Le.encode("héllo")?)?;
+            buffer.reload()?;
+            assert_eq!(buffer.content(), "héllo");
+            assert_eq!(buffer.encoding(), crate::encoding::Encoding::Utf16Le);
+            Ok(())
+        }
     }
 
     mod patch_edit {
@@ -1265,6 +1990,152 @@ fn main() {
             Ok(())
         }
     }
+
+    #[test]
+    fn incremental_reparse_should_produce_the_same_tree_as_fresh_parse() -> anyhow::Result<()> {
+        let language = shared::language::from_extension("rs")
+            .unwrap()
+            .tree_sitter_language();
+        let content = "fn f() { let x = 1; }\n".repeat(1000);
+        let mut buffer = Buffer::new(language.clone(), &content);
+        buffer.set_incremental_reparse_threshold(0);
+        assert!(buffer.is_large());
+
+        let edit_transaction = EditTransaction::from_action_groups(
+            [
+                (0..2, "pub fn"),
+                (100..100, "\nstruct 😀 {}\n"),
+                (500..520, ""),
+            ]
+            .into_iter()
+            .map(|(range, new)| {
+                ActionGroup::new(
+                    [Action::Edit(Edit {
+                        range: (CharIndex(range.start)..CharIndex(range.end)).into(),
+                        new: new.into(),
+                    })]
+                    .to_vec(),
+                )
+            })
+            .collect_vec(),
+        );
+        buffer.apply_edit_transaction(&edit_transaction, SelectionSet::default(), true)?;
+
+        let fresh = Buffer::new(language, &buffer.content());
+        assert_eq!(
+            buffer.tree().unwrap().root_node().to_sexp(),
+            fresh.tree().unwrap().root_node().to_sexp()
+        );
+        Ok(())
+    }
+
+    /// Run with `cargo test --release -- --ignored --nocapture bench_incremental_reparse`.
+    #[test]
+    #[ignore]
+    fn bench_incremental_reparse() -> anyhow::Result<()> {
+        let language = shared::language::from_extension("rs")
+            .unwrap()
+            .tree_sitter_language();
+        let content = "fn f() { let x = 1; }\n".repeat(50_000);
+        let iterations = 20;
+        let measure = |incremental: bool| -> anyhow::Result<std::time::Duration> {
+            let mut buffer = Buffer::new(language.clone(), &content);
+            let started_at = std::time::Instant::now();
+            for index in 0..iterations {
+                let edit_transaction = EditTransaction::from_action_groups(
+                    [ActionGroup::new(
+                        [Action::Edit(Edit {
+                            range: (CharIndex(index * 1000)..CharIndex(index * 1000)).into(),
+                            new: "x".into(),
+                        })]
+                        .to_vec(),
+                    )]
+                    .to_vec(),
+                );
+                buffer.apply_edit_transaction(&edit_transaction, SelectionSet::default(), false)?;
+                buffer.reparse_tree_(incremental)?;
+            }
+            Ok(started_at.elapsed() / iterations as u32)
+        };
+        let fresh = measure(false)?;
+        let incremental = measure(true)?;
+        println!(
+            "{} bytes: fresh parse {:?}, incremental reparse {:?}",
+            content.len(),
+            fresh,
+            incremental
+        );
+        assert!(incremental < fresh);
+        Ok(())
+    }
+
+    #[test]
+    fn update_highlighted_spans_should_drop_spans_beyond_the_end() {
+        use crate::{
+            grid::StyleKey,
+            syntax_highlight::{HighlighedSpan, HighlighedSpans},
+        };
+        let span = |byte_range: std::ops::Range<usize>| HighlighedSpan {
+            byte_range,
+            style_key: StyleKey::Syntax("keyword".to_string()),
+        };
+        let mut buffer = Buffer::new(None, "fn main() {}");
+        buffer.update_highlighted_spans(0..12, HighlighedSpans(vec![span(0..2), span(3..7)]));
+
+        // The content shrinks, then its highlighting arrives
+        buffer.update("fn");
+        buffer.update_highlighted_spans(0..2, HighlighedSpans(vec![span(0..2)]));
+        assert_eq!(
+            buffer
+                .highlighted_spans()
+                .into_iter()
+                .map(|span| span.byte_range)
+                .collect_vec(),
+            [0..2]
+        );
+    }
+
+    #[test]
+    fn highlighting_in_chunks_should_produce_the_same_spans_as_highlighting_at_once(
+    ) -> anyhow::Result<()> {
+        use crate::syntax_highlight::{GetHighlightConfig, Highlight};
+        let language = shared::language::from_extension("rs").unwrap();
+        let config = language.get_highlight_config()?.unwrap();
+        let content = "fn f() { let x = \"hello\"; }\n".repeat(100);
+        let spans = |spans: Vec<crate::syntax_highlight::HighlighedSpan>| {
+            spans
+                .into_iter()
+                .map(|span| (span.byte_range, span.style_key))
+                .collect_vec()
+        };
+
+        let mut buffer = Buffer::new(language.tree_sitter_language(), &content);
+
+        // Highlight the visible lines first, like how a newly opened file is highlighted
+        let visible_content = &content[..buffer.line_to_byte(10)?];
+        buffer
+            .update_highlighted_spans(0..visible_content.len(), config.highlight(visible_content)?);
+
+        let mut chunk_ranges = Vec::new();
+        config.highlight_in_chunks(&content, 100, &mut |byte_range, highlighted_spans| {
+            chunk_ranges.push(byte_range.clone());
+            buffer.update_highlighted_spans(byte_range, highlighted_spans);
+            true
+        })?;
+
+        assert!(chunk_ranges.len() > 1);
+        assert_eq!(chunk_ranges.first().unwrap().start, 0);
+        assert_eq!(chunk_ranges.last().unwrap().end, content.len());
+        assert!(chunk_ranges
+            .iter()
+            .tuple_windows()
+            .all(|(a, b)| a.end == b.start));
+        assert_eq!(
+            spans(buffer.highlighted_spans()),
+            spans(config.highlight(&content)?.0)
+        );
+        Ok(())
+    }
 }
 
 #[derive(Clone)]
@@ -1311,3 +2182,32 @@ impl PartialEq for Patch {
         false
     }
 }
+
+/// The group of an import statement, groups of smaller numbers are placed first.
+///
+/// In Rust, the groups are `std`, external crates and local modules.
+/// In other languages, relative imports are placed after the others.
+fn import_group(statement: &str, is_rust: bool) -> usize {
+    if is_rust {
+        let path =
+            Regex::new(r"^(?:pub(?:\([^)]*\))?\s+)?(?:extern\s+crate\s+\w+|use\s+(?:::)?(\w+))")
+                .ok()
+                .and_then(|regex| regex.captures(statement))
+                .map(|captures| captures.get(1).map(|root| root.as_str().to_string()));
+        match path {
+            // `extern crate`
+            Some(None) => 0,
+            Some(Some(root)) => match root.as_str() {
+                "std" | "core" | "alloc" => 0,
+                "crate" | "self" | "super" => 2,
+                _ => 1,
+            },
+            None => 1,
+        }
+    } else {
+        let relative = Regex::new(r#"from\s+\.|['"]\.\.?/"#)
+            .map(|regex| regex.is_match(statement))
+            .unwrap_or(false);
+        relative as usize
+    }
+}
@@ -0,0 +1,191 @@
+//! Information about characters, for diagnosing invisible and look-alike characters,
+//! such as non-breaking spaces and homoglyphs.
+//!
+//! The Unicode character database is not bundled,
+//! so names are only known for ASCII letters and the characters in [`NOTABLE_CHARACTERS`].
+
+use anyhow::Context;
+use itertools::Itertools;
+
+use crate::grid::get_char_width;
+
+/// Characters that are commonly mistaken for other characters, or that are invisible.
+const NOTABLE_CHARACTERS: &[(char, &str)] = &[
+    ('\t', "CHARACTER TABULATION"),
+    ('\n', "LINE FEED"),
+    ('\r', "CARRIAGE RETURN"),
+    (' ', "SPACE"),
+    ('\u{00A0}', "NO-BREAK SPACE"),
+    ('\u{00AD}', "SOFT HYPHEN"),
+    ('\u{2002}', "EN SPACE"),
+    ('\u{2003}', "EM SPACE"),
+    ('\u{2009}', "THIN SPACE"),
+    ('\u{200A}', "HAIR SPACE"),
+    ('\u{200B}', "ZERO WIDTH SPACE"),
+    ('\u{200C}', "ZERO WIDTH NON-JOINER"),
+    ('\u{200D}', "ZERO WIDTH JOINER"),
+    ('\u{200E}', "LEFT-TO-RIGHT MARK"),
+    ('\u{200F}', "RIGHT-TO-LEFT MARK"),
+    ('\u{2010}', "HYPHEN"),
+    ('\u{2011}', "NON-BREAKING HYPHEN"),
+    ('\u{2013}', "EN DASH"),
+    ('\u{2014}', "EM DASH"),
+    ('\u{2018}', "LEFT SINGLE QUOTATION MARK"),
+    ('\u{2019}', "RIGHT SINGLE QUOTATION MARK"),
+    ('\u{201C}', "LEFT DOUBLE QUOTATION MARK"),
+    ('\u{201D}', "RIGHT DOUBLE QUOTATION MARK"),
+    ('\u{2026}', "HORIZONTAL ELLIPSIS"),
+    ('\u{202F}', "NARROW NO-BREAK SPACE"),
+    ('\u{2060}', "WORD JOINER"),
+    ('\u{2212}', "MINUS SIGN"),
+    ('\u{3000}', "IDEOGRAPHIC SPACE"),
+    ('\u{FEFF}', "ZERO WIDTH NO-BREAK SPACE"),
+    ('\u{FFFD}', "REPLACEMENT CHARACTER"),
+    ('\u{0391}', "GREEK CAPITAL LETTER ALPHA"),
+    ('\u{03BF}', "GREEK SMALL LETTER OMICRON"),
+    ('\u{0430}', "CYRILLIC SMALL LETTER A"),
+    ('\u{0435}', "CYRILLIC SMALL LETTER IE"),
+    ('\u{043E}', "CYRILLIC SMALL LETTER O"),
+    ('\u{0440}', "CYRILLIC SMALL LETTER ER"),
+    ('\u{0441}', "CYRILLIC SMALL LETTER ES"),
+];
+
+/// Replace `char` with `replacement`, see [`crate::transformation::Transformation::ReplaceCharacters`].
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize)]
+pub(crate) struct CharacterReplacement {
+    pub(crate) char: char,
+    pub(crate) replacement: String,
+}
+
+impl CharacterReplacement {
+    fn new(char: char, replacement: &str) -> CharacterReplacement {
+        CharacterReplacement {
+            char,
+            replacement: replacement.to_string(),
+        }
+    }
+
+    /// Invisible characters and typographic look-alikes that are commonly pasted from web pages and documents,
+    /// replaced with their ASCII equivalents.
+    pub(crate) fn defaults() -> Vec<CharacterReplacement> {
+        [
+            CharacterReplacement::new('\u{00A0}', " "),
+            CharacterReplacement::new('\u{00AD}', ""),
+            CharacterReplacement::new('\u{2002}', " "),
+            CharacterReplacement::new('\u{2003}', " "),
+            CharacterReplacement::new('\u{2009}', " "),
+            CharacterReplacement::new('\u{200A}', " "),
+            CharacterReplacement::new('\u{200B}', ""),
+            CharacterReplacement::new('\u{200C}', ""),
+            CharacterReplacement::new('\u{200D}', ""),
+            CharacterReplacement::new('\u{2060}', ""),
+            CharacterReplacement::new('\u{202F}', " "),
+            CharacterReplacement::new('\u{3000}', " "),
+            CharacterReplacement::new('\u{FEFF}', ""),
+            CharacterReplacement::new('\u{2010}', "-"),
+            CharacterReplacement::new('\u{2011}', "-"),
+            CharacterReplacement::new('\u{2013}', "-"),
+            CharacterReplacement::new('\u{2014}', "-"),
+            CharacterReplacement::new('\u{2212}', "-"),
+            CharacterReplacement::new('\u{2018}', "'"),
+            CharacterReplacement::new('\u{2019}', "'"),
+            CharacterReplacement::new('\u{201C}', "\""),
+            CharacterReplacement::new('\u{201D}', "\""),
+            CharacterReplacement::new('\u{2026}', "..."),
+        ]
+        .to_vec()
+    }
+
+    /// Returns the default replacements if `character_replacements.json` does not exist.
+    pub(crate) fn load() -> anyhow::Result<Vec<CharacterReplacement>> {
+        let path = grammar::config_dir().join("character_replacements.json");
+        if !path.exists() {
+            return Ok(CharacterReplacement::defaults());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&content).with_context(|| {
+            format!(
+                "Failed to parse character replacements from {}",
+                path.display()
+            )
+        })
+    }
+}
+
+fn name(char: char) -> Option<String> {
+    if char.is_ascii_lowercase() {
+        Some(format!("LATIN SMALL LETTER {}", char.to_ascii_uppercase()))
+    } else if char.is_ascii_uppercase() {
+        Some(format!("LATIN CAPITAL LETTER {}", char))
+    } else {
+        NOTABLE_CHARACTERS
+            .iter()
+            .find(|(notable, _)| *notable == char)
+            .map(|(_, name)| name.to_string())
+    }
+}
+
+fn category(char: char) -> &'static str {
+    if char.is_control() {
+        "control"
+    } else if char.is_whitespace() {
+        "whitespace"
+    } else if char.is_alphabetic() {
+        "letter"
+    } else if char.is_numeric() {
+        "number"
+    } else {
+        "symbol"
+    }
+}
+
+/// The codepoint, name, UTF-8 bytes and display width of `char`, one per line.
+pub(crate) fn describe(char: char) -> String {
+    let mut buffer = [0; 4];
+    [
+        format!("Character: {:?}", char),
+        format!("Codepoint: U+{:04X}", char as u32),
+        format!(
+            "Name: {}",
+            name(char).unwrap_or_else(|| format!("unknown ({})", category(char)))
+        ),
+        format!(
+            "UTF-8: {}",
+            char.encode_utf8(&mut buffer)
+                .bytes()
+                .map(|byte| format!("{:02X}", byte))
+                .join(" ")
+        ),
+        format!("Width: {}", get_char_width(char)),
+    ]
+    .join("\n")
+}
+
+#[cfg(test)]
+mod test_char_info {
+    use super::describe;
+
+    #[test]
+    fn describe_invisible_character() {
+        assert_eq!(
+            describe('\u{00A0}'),
+            "Character: '\\u{a0}'
+Codepoint: U+00A0
+Name: NO-BREAK SPACE
+UTF-8: C2 A0
+Width: 1"
+        )
+    }
+
+    #[test]
+    fn describe_character_without_known_name() {
+        assert_eq!(
+            describe('🦀'),
+            "Character: '🦀'
+Codepoint: U+1F980
+Name: unknown (symbol)
+UTF-8: F0 9F A6 80
+Width: 2"
+        )
+    }
+}
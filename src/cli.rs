@@ -25,6 +25,9 @@ enum Commands {
     Log,
     /// Run Ki in the given path, treating the path as the working directory
     In(InArgs),
+    /// Open the file of the given path without the TUI, feed it with the given keys,
+    /// and print the final content of the file
+    Headless(HeadlessArgs),
 }
 #[derive(Args)]
 struct EditArgs {
@@ -34,6 +37,13 @@ struct EditArgs {
 struct InArgs {
     path: String,
 }
+#[derive(Args)]
+struct HeadlessArgs {
+    path: String,
+    /// Space-separated keys, for example "i h e l l o esc"
+    #[arg(long)]
+    keys: String,
+}
 #[derive(Subcommand)]
 enum Grammar {
     Build,
@@ -90,6 +100,25 @@ pub(crate) fn cli() -> anyhow::Result<()> {
                 working_directory: Some(args.path.try_into()?),
                 ..Default::default()
             }),
+            Commands::Headless(args) => {
+                let result = (|| -> anyhow::Result<String> {
+                    crate::headless::run(crate::headless::HeadlessConfig {
+                        path: args.path.try_into()?,
+                        keys: args.keys,
+                        working_directory: ".".try_into()?,
+                    })
+                })();
+                match result {
+                    Ok(content) => {
+                        print!("{}", content);
+                        Ok(())
+                    }
+                    Err(error) => {
+                        eprintln!("{:?}", error);
+                        std::process::exit(1)
+                    }
+                }
+            }
         }
     } else {
         crate::run(Default::default())
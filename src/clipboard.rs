@@ -19,7 +19,7 @@ impl CopiedTexts {
         Self { texts }
     }
 
-    fn join(&self, separator: &str) -> String {
+    pub(crate) fn join(&self, separator: &str) -> String {
         self.texts.clone().into_iter().join(separator)
     }
 
@@ -48,6 +48,11 @@ impl Clipboard {
         self.history.get(history_offset)
     }
 
+    /// Returns the copied texts from the latest to the earliest.
+    pub(crate) fn history(&self) -> impl Iterator<Item = &CopiedTexts> {
+        self.history.items.iter().rev()
+    }
+
     pub(crate) fn get_from_system_clipboard(&self) -> anyhow::Result<String> {
         Ok(arboard::Clipboard::new()?.get_text()?)
     }
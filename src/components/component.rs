@@ -118,7 +118,7 @@ pub trait Component: Any + AnyComponent {
         match event {
             Event::Key(event) => self.handle_key_event(context, event),
             Event::Paste(content) => self.handle_paste_event(content),
-            Event::Mouse(event) => self.handle_mouse_event(event),
+            Event::Mouse(event) => self.handle_mouse_event(context, event),
             _ => Ok(Default::default()),
         }
     }
@@ -129,9 +129,10 @@ pub trait Component: Any + AnyComponent {
 
     fn handle_mouse_event(
         &mut self,
-        _event: crossterm::event::MouseEvent,
+        context: &Context,
+        event: crossterm::event::MouseEvent,
     ) -> anyhow::Result<Dispatches> {
-        Ok(Default::default())
+        self.editor_mut().handle_mouse_event(context, event)
     }
 
     fn handle_key_event(
@@ -1,13 +1,16 @@
 use crate::{
-    app::{Dispatches, RequestParams},
+    app::{DispatchPrompt, Dispatches, RequestParams, Scope},
     buffer::Line,
     char_index_range::CharIndexRange,
     clipboard::CopiedTexts,
     context::{Context, GlobalMode, LocalSearchConfigMode, Search},
+    encoding::Encoding,
+    git::hunk::Hunk,
     history::History,
     lsp::{completion::CompletionItemEdit, process::ResponseContext},
     selection::Filter,
-    selection_mode::{self, CaseAgnostic},
+    selection_mode::{self, ByteRange, CaseAgnostic},
+    snippet::{Snippet, Tabstop},
     surround::EnclosureKind,
     transformation::Transformation,
 };
@@ -16,7 +19,7 @@ use nonempty::NonEmpty;
 use shared::canonicalized_path::CanonicalizedPath;
 use std::{
     cell::{Ref, RefCell, RefMut},
-    ops::Range,
+    ops::{Range, RangeInclusive},
     rc::Rc,
 };
 
@@ -31,6 +34,7 @@ use crate::{
     buffer::Buffer,
     components::component::Component,
     edit::{Action, ActionGroup, Edit, EditTransaction},
+    grid::get_char_width,
     lsp::completion::PositionalEdit,
     position::Position,
     rectangle::Rectangle,
@@ -41,7 +45,8 @@ use DispatchEditor::*;
 
 use super::{
     component::ComponentId,
-    dropdown::DropdownRender,
+    dropdown::{DropdownItem, DropdownRender},
+    prompt::{PromptConfig, PromptHistoryKey},
     render_editor::Source,
     suggestive_editor::{Decoration, Info},
 };
@@ -64,6 +69,10 @@ pub(crate) struct Jump {
 }
 const WINDOW_TITLE_HEIGHT: usize = 1;
 
+/// The maximum number of candidates that `get_valid_selection` validates
+/// before giving up.
+const MAX_VALID_SELECTION_ATTEMPTS: usize = 100;
+
 impl Component for Editor {
     fn id(&self) -> ComponentId {
         self.id
@@ -92,7 +101,12 @@ impl Component for Editor {
                     .display_relative_to(current_working_directory)
                     .unwrap_or_else(|_| path.display_absolute());
                 let icon = path.icon();
-                Some(format!(" {} {}", icon, string))
+                let large_file_mode = if self.buffer().is_large_file_mode() {
+                    " [LARGE FILE]"
+                } else {
+                    ""
+                };
+                Some(format!(" {} {}{}", icon, string, large_file_mode))
             })
             .unwrap_or_else(|| "[No title]".to_string())
     }
@@ -130,17 +144,16 @@ impl Component for Editor {
 
     fn handle_mouse_event(
         &mut self,
+        context: &Context,
         mouse_event: crossterm::event::MouseEvent,
     ) -> anyhow::Result<Dispatches> {
         const SCROLL_HEIGHT: usize = 1;
         match mouse_event.kind {
             MouseEventKind::ScrollUp => {
-                self.apply_scroll(Direction::Start, SCROLL_HEIGHT);
-                Ok(Default::default())
+                Ok(self.scroll_view(Direction::Start, SCROLL_HEIGHT, context.smooth_scroll()))
             }
             MouseEventKind::ScrollDown => {
-                self.apply_scroll(Direction::End, SCROLL_HEIGHT);
-                Ok(Default::default())
+                Ok(self.scroll_view(Direction::End, SCROLL_HEIGHT, context.smooth_scroll()))
             }
             MouseEventKind::Down(MouseButton::Left) => Ok(Default::default()),
             _ => Ok(Default::default()),
@@ -172,8 +185,9 @@ impl Component for Editor {
             event::event::Event::Paste(content) => self.paste_text(
                 Direction::End,
                 CopiedTexts::new(NonEmpty::singleton(content)),
+                self.reindents_on_paste(context),
             ),
-            event::event::Event::Mouse(event) => self.handle_mouse_event(event),
+            event::event::Event::Mouse(event) => self.handle_mouse_event(context, event),
             _ => Ok(Default::default()),
         }
     }
@@ -190,7 +204,10 @@ impl Component for Editor {
             AlignViewBottom => self.align_cursor_to_bottom(),
             Transform(transformation) => return self.transform_selection(transformation),
             SetSelectionMode(selection_mode) => {
-                return self.set_selection_mode(selection_mode);
+                let cursor_direction = context.default_cursor_direction(&selection_mode);
+                let dispatches = self.set_selection_mode(selection_mode)?;
+                self.cursor_direction = cursor_direction;
+                return Ok(dispatches);
             }
 
             FindOneChar => self.enter_single_character_mode(),
@@ -198,7 +215,20 @@ impl Component for Editor {
             MoveSelection(direction) => return self.handle_movement(context, direction),
             Copy {
                 use_system_clipboard,
-            } => return self.copy(use_system_clipboard),
+            } => {
+                let dispatches = self.copy(use_system_clipboard)?;
+                return Ok(dispatches.chain(self.flash_yank(context)));
+            }
+            SelectionToClipboardAsMarkdownCodeBlock {
+                with_location,
+                use_system_clipboard,
+            } => {
+                return self.copy_as_markdown_code_block(
+                    context,
+                    with_location,
+                    use_system_clipboard,
+                )
+            }
             ReplaceWithCopiedText {
                 cut,
                 use_system_clipboard,
@@ -213,15 +243,34 @@ impl Component for Editor {
             #[cfg(test)]
             MatchLiteral(literal) => return self.match_literal(&literal),
             ToggleBookmark => self.toggle_bookmarks(),
+            SelectToBookmark(direction) => return self.select_to_bookmark(direction),
             EnterNormalMode => self.enter_normal_mode()?,
             FilterPush(filter) => return Ok(self.filters_push(context, filter)),
             CursorAddToAllSelections => self.add_cursor_to_all_selections()?,
             FilterClear => return Ok(self.filters_clear()),
             CursorKeepPrimaryOnly => self.cursor_keep_primary_only(),
+            CursorCollapseToFirst => self.cursor_collapse_to_first(),
+            CursorCollapseToLast => self.cursor_collapse_to_last(),
+            CursorCollapseToNearest => self.cursor_collapse_to_nearest(),
+            CyclePrimaryCursor { backward } => self.cycle_primary_cursor(backward),
+            SelectBlock => return self.select_block(),
+            SelectNextOccurrenceInSelection => {
+                return self.select_next_occurrence_in_selection(context)
+            }
+            GoToParentLine => return self.go_to_parent_line(),
+            SelectToParentLine => return self.select_to_parent_line(),
             EnterExchangeMode => self.enter_exchange_mode(),
             ReplacePattern { config } => {
                 let selection_set = self.selection_set.clone();
-                let (_, selection_set) = self.buffer_mut().replace(config, selection_set)?;
+                let select_replacements = config.select_replacements();
+                let (modified, selection_set) = self.buffer_mut().replace(config, selection_set)?;
+                // The search no longer matches the replacements,
+                // so the replacements are navigated as they are
+                let selection_set = if select_replacements && modified {
+                    selection_set.set_mode(SelectionMode::Custom)
+                } else {
+                    selection_set
+                };
                 return Ok(self
                     .update_selection_set(selection_set, false)
                     .chain(self.get_document_did_change_dispatch()));
@@ -245,8 +294,8 @@ impl Component for Editor {
             } => return self.change_cut(use_system_clipboard),
             #[cfg(test)]
             SetRectangle(rectangle) => self.set_rectangle(rectangle),
-            ScrollPageDown => return self.scroll_page_down(),
-            ScrollPageUp => return self.scroll_page_up(),
+            ScrollPageDown => return self.scroll_page_down(context.smooth_scroll()),
+            ScrollPageUp => return self.scroll_page_up(context.smooth_scroll()),
             ShowJumps {
                 use_current_selection_mode,
             } => self.show_jumps(use_current_selection_mode)?,
@@ -259,13 +308,78 @@ impl Component for Editor {
             ApplySyntaxHighlight => {
                 self.apply_syntax_highlighting(context)?;
             }
-            Save => return self.save(),
+            Save => return self.save(context),
             ReplaceCurrentSelectionWith(string) => {
                 return self.replace_current_selection_with(|_| Some(Rope::from_str(&string)))
             }
             SelectLineAt(index) => return Ok(self.select_line_at(index)?.into_vec().into()),
             EnterMultiCursorMode => self.enter_multicursor_mode(),
             Surround(open, close) => return self.enclose(open, close),
+            WrapSelectionInTag(tag) => return self.wrap_selection_in_tag(tag),
+            PipeSelection(command) => return self.pipe_selection(&command),
+            SelectLinesMatching(pattern) => return self.select_lines_matching(&pattern, true),
+            SelectLinesNotMatching(pattern) => return self.select_lines_matching(&pattern, false),
+            SelectByQuery(query) => return self.select_by_query(&query),
+            NavigateToImportBlock => return self.navigate_to_import_block(),
+            SelectFunctionBody => return self.select_function_body(),
+            SelectWordUnderCursorOccurrences => return self.select_word_under_cursor_occurrences(),
+            ShowCharInfo => return Ok(self.show_char_info()),
+            InsertDateTime => return self.insert_date_time(context),
+            GenerateUuid { uppercase } => return self.generate_uuid(uppercase),
+            SelectInvisibleCharacters => return self.select_invisible_characters(context),
+            ReplaceInvisibleCharacters => {
+                return self.transform_selection(Transformation::ReplaceCharacters(
+                    context.character_replacements().to_vec(),
+                ))
+            }
+            RepeatSelectionModeSearchFromClipboard {
+                whole_word,
+                use_system_clipboard,
+            } => return self.search_clipboard_content(context, whole_word, use_system_clipboard),
+            GoToMatchingTag => return self.go_to_matching_tag(),
+            MoveSelectionToColumn(column) => return self.move_selection_to_column(column),
+            JumpToPercentOfLine(percent) => return self.jump_to_percent_of_line(percent),
+            GoToByteOffset(byte_offset) => return self.go_to_byte_offset(byte_offset),
+            GoToParagraphBoundary(direction) => return self.go_to_paragraph_boundary(direction),
+            ExpandSelection => return self.expand_selection(),
+            ShrinkSelection => return self.shrink_selection(),
+            SelectNextModifiedRegion => return self.select_next_modified_region(),
+            SortImports => return self.sort_imports(context),
+            ToggleLineComment => return self.toggle_line_comment(),
+            ToggleLineEnding => return self.toggle_line_ending(),
+            EvaluateSelection => return self.evaluate_selection(),
+            SumSelections => return Ok(self.sum_selections()),
+            SortSelectionsByContent => return self.sort_selections_by_content(),
+            SelectionToQuickfix => return self.selection_to_quickfix(),
+            ReplaceWithRegisterFromHistory => {
+                return Ok(self.replace_with_register_from_history(context))
+            }
+            ConvertEncoding(encoding) => {
+                let previous_encoding = self.buffer().encoding();
+                self.buffer_mut().set_encoding(encoding);
+                let result = self.save(context);
+                if result.is_err() {
+                    // Keep the encoding that the file is actually saved in
+                    self.buffer_mut().set_encoding(previous_encoding);
+                }
+                return result;
+            }
+            SelectEnclosingString { include_delimiters } => {
+                return self.select_enclosing_node("string", include_delimiters)
+            }
+            SelectEnclosingComment { include_delimiters } => {
+                return self.select_enclosing_node("comment", include_delimiters)
+            }
+            ToggleCaseOfChar => return self.toggle_case_of_char(),
+            GoToFirstNonWhitespace => return self.go_to_first_non_whitespace(),
+            ExpandSnippet => return self.expand_snippet(context),
+            GoToNextTabstop => return Ok(self.go_to_next_tabstop()?.unwrap_or_default()),
+            InsertShellOutput(command) => {
+                return Ok(Dispatches::one(Dispatch::InsertShellOutput {
+                    component_id: self.id(),
+                    command,
+                }))
+            }
             ShowKeymapLegendInsertMode => {
                 return Ok([Dispatch::ShowKeymapLegend(
                     self.insert_mode_keymap_legend_config(),
@@ -293,6 +407,7 @@ impl Component for Editor {
                 use_system_clipboard,
             } => return self.paste(direction, context, use_system_clipboard),
             SwapCursorWithAnchor => self.swap_cursor_with_anchor(),
+            AlignCursorsToAnchor(direction) => self.align_cursors_to_anchor(direction),
             SetDecorations(decorations) => self.buffer_mut().set_decorations(&decorations),
             MoveCharacterBack => self.selection_set.move_left(&self.cursor_direction),
             MoveCharacterForward => {
@@ -308,9 +423,15 @@ impl Component for Editor {
             GoForward => self.go_forward(),
             SelectSurround { enclosure, kind } => return self.select_surround(enclosure, kind),
             DeleteSurround(enclosure) => return self.delete_surround(enclosure),
+            DeleteToMatching { enclosure, kind } => {
+                return self.delete_to_matching(enclosure, kind)
+            }
+            ReplaceInsidePair(enclosure) => return self.replace_inside_pair(enclosure),
             ChangeSurround { from, to } => return self.change_surround(from, Some(to)),
             ReplaceWithPattern => return self.replace_with_pattern(context),
-            Replace(movement) => return self.replace_with_movement(&movement),
+            Replace(movement) => {
+                return self.replace_with_movement(&movement, context.validate_structural_edits())
+            }
             ApplyPositionalEdits(edits) => {
                 return self.apply_positional_edits(
                     edits
@@ -342,6 +463,10 @@ impl Clone for Editor {
             jumps: None,
             cursor_direction: self.cursor_direction.clone(),
             scroll_offset: self.scroll_offset,
+            scroll_animation_target: None,
+            yank_flash: None,
+            document_highlights: Vec::new(),
+            selection_ranges: Vec::new(),
             rectangle: self.rectangle.clone(),
             buffer: self.buffer.clone(),
             title: self.title.clone(),
@@ -350,6 +475,7 @@ impl Clone for Editor {
             regex_highlight_rules: Vec::new(),
             selection_set_history: History::new(),
             copied_text_history_offset: Default::default(),
+            possible_selections_cache: Default::default(),
         }
     }
 }
@@ -366,6 +492,15 @@ pub(crate) struct Editor {
     /// This means the number of lines to be skipped from the top during rendering.
     /// 2 means the first line to be rendered on the screen if the 3rd line of the text.
     scroll_offset: u16,
+    /// The `scroll_offset` that smooth scrolling is heading towards, if any.
+    scroll_animation_target: Option<u16>,
+    /// The copied ranges, which are highlighted until the given instant.
+    yank_flash: Option<(Vec<CharIndexRange>, std::time::Instant)>,
+    /// The occurrences of the symbol under the cursor, see [`Context::document_highlight`].
+    document_highlights: Vec<CharIndexRange>,
+    /// The nested ranges enclosing the primary cursor, from the innermost to the outermost,
+    /// which are walked by [`DispatchEditor::ExpandSelection`] and [`DispatchEditor::ShrinkSelection`].
+    selection_ranges: Vec<CharIndexRange>,
     rectangle: Rectangle,
 
     buffer: Rc<RefCell<Buffer>>,
@@ -374,6 +509,20 @@ pub(crate) struct Editor {
     pub(crate) current_view_alignment: Option<ViewAlignment>,
     selection_set_history: History<SelectionSet>,
     copied_text_history_offset: Counter,
+    /// Possible selections computed during the last render,
+    /// reused as long as none of their inputs changed.
+    pub(crate) possible_selections_cache:
+        RefCell<Option<(PossibleSelectionsCacheKey, Vec<ByteRange>)>>,
+}
+
+/// Everything that the possible selections of an editor depend on.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct PossibleSelectionsCacheKey {
+    buffer_version: usize,
+    selection: Selection,
+    selection_set: SelectionSet,
+    cursor_direction: Direction,
+    visible_line_range: Range<usize>,
 }
 
 #[derive(Default)]
@@ -402,6 +551,60 @@ impl Counter {
     }
 }
 
+/// The char offset of the given display `column` within `line`, clamped to its last char.
+///
+/// The width of each char, such as a tab, is consistent with how it is rendered.
+fn column_to_char_offset(line: &str, column: usize) -> usize {
+    let line = line.trim_end_matches(['\n', '\r']);
+    let mut width = 0;
+    for (offset, c) in line.chars().enumerate() {
+        width += get_char_width(c);
+        if width > column {
+            return offset;
+        }
+    }
+    line.chars().count().saturating_sub(1)
+}
+
+/// The display column of the char at `offset` within `line`, the inverse of [`column_to_char_offset`].
+fn char_offset_to_column(line: &str, offset: usize) -> usize {
+    line.chars().take(offset).map(get_char_width).sum()
+}
+
+fn is_function_node(node: &tree_sitter::Node) -> bool {
+    ["function", "method", "closure", "lambda"]
+        .iter()
+        .any(|kind| node.kind().contains(kind))
+}
+
+/// The byte range of `node` without its delimiters,
+/// such as the quotes of a string, or the `//` of a comment.
+fn inner_byte_range(node: tree_sitter::Node, content: &str) -> Range<usize> {
+    let range = node.byte_range();
+    let (first, last) = (
+        node.child(0),
+        node.child(node.child_count().saturating_sub(1)),
+    );
+    if let (Some(first), Some(last)) = (first, last) {
+        if node.child_count() >= 2 && !first.is_named() && !last.is_named() {
+            return first.end_byte()..last.start_byte();
+        }
+    }
+    // Comments usually have no children, so their delimiters are trimmed textually
+    let text = &content[range.clone()];
+    let start = regex::Regex::new(r"^(//+!?|/\*+!?|#+|--+|;+)\s*")
+        .ok()
+        .and_then(|regex| regex.find(text))
+        .map(|match_| match_.end())
+        .unwrap_or(0);
+    let end = regex::Regex::new(r"\s*\*+/$")
+        .ok()
+        .and_then(|regex| regex.find(&text[start..]))
+        .map(|match_| start + match_.start())
+        .unwrap_or(text.trim_end().len());
+    range.start + start..range.start + end
+}
+
 pub(crate) struct RegexHighlightRule {
     pub(crate) regex: regex::Regex,
     pub(crate) capture_styles: Vec<RegexHighlightRuleCaptureStyle>,
@@ -500,6 +703,10 @@ impl Editor {
             mode: Mode::Normal,
             cursor_direction: Direction::Start,
             scroll_offset: 0,
+            scroll_animation_target: None,
+            yank_flash: None,
+            document_highlights: Vec::new(),
+            selection_ranges: Vec::new(),
             rectangle: Rectangle::default(),
             buffer: Rc::new(RefCell::new(Buffer::new(language, text))),
             title: None,
@@ -508,6 +715,7 @@ impl Editor {
             regex_highlight_rules: Vec::new(),
             selection_set_history: History::new(),
             copied_text_history_offset: Default::default(),
+            possible_selections_cache: Default::default(),
         }
     }
 
@@ -518,6 +726,10 @@ impl Editor {
             mode: Mode::Normal,
             cursor_direction: Direction::Start,
             scroll_offset: 0,
+            scroll_animation_target: None,
+            yank_flash: None,
+            document_highlights: Vec::new(),
+            selection_ranges: Vec::new(),
             rectangle: Rectangle::default(),
             buffer,
             title: None,
@@ -526,6 +738,7 @@ impl Editor {
             regex_highlight_rules: Vec::new(),
             selection_set_history: History::new(),
             copied_text_history_offset: Default::default(),
+            possible_selections_cache: Default::default(),
         }
     }
 
@@ -565,6 +778,537 @@ impl Editor {
         Ok(self.update_selection_set(selection_set, false))
     }
 
+    /// Select every line whose match against the given regex equals `matching`,
+    /// with one cursor per line.
+    ///
+    /// Only the lines of the primary selection are considered if it spans multiple lines,
+    /// otherwise every line of the buffer.
+    fn select_lines_matching(
+        &mut self,
+        pattern: &str,
+        matching: bool,
+    ) -> anyhow::Result<Dispatches> {
+        let regex = crate::list::grep::RegexConfig {
+            escaped: false,
+            case_sensitive: true,
+            match_whole_word: false,
+            multiline: false,
+        }
+        .to_regex(pattern)?;
+        let buffer = self.buffer.borrow();
+        let range = self.selection_set.primary_selection().extended_range();
+        let start_line = buffer.char_to_line(range.start)?;
+        let end_line = buffer.char_to_line((range.end - 1).max(range.start))?;
+        let lines = if start_line == end_line {
+            0..buffer.len_lines()
+        } else {
+            start_line..end_line + 1
+        };
+        let selections = lines
+            .filter_map(|line| {
+                let content = buffer.get_line_by_line_index(line)?.to_string();
+                // Skip the empty line after the trailing newline of the buffer
+                if content.is_empty()
+                    || regex.is_match(content.trim_end_matches(['\n', '\r'])) != matching
+                {
+                    return None;
+                }
+                let start = buffer.line_to_char(line).ok()?;
+                Some(Selection::new(
+                    (start..start + content.chars().count()).into(),
+                ))
+            })
+            .collect_vec();
+        drop(buffer);
+        let Some(selections) = NonEmpty::from_vec(selections) else {
+            return Ok(Dispatches::one(Dispatch::ShowEditorInfo(Info::new(
+                "Select lines matching".to_string(),
+                format!(
+                    "No lines {} /{}/",
+                    if matching { "match" } else { "mismatch" },
+                    pattern
+                ),
+            ))));
+        };
+        let selection_set = SelectionSet::new(selections).set_mode(SelectionMode::LineFull);
+        Ok(self.update_selection_set(selection_set, true))
+    }
+
+    fn select_by_query(&mut self, query: &str) -> anyhow::Result<Dispatches> {
+        let show_info = |content: String| {
+            Ok(Dispatches::one(Dispatch::ShowEditorInfo(Info::new(
+                "Select by query".to_string(),
+                content,
+            ))))
+        };
+        let buffer = self.buffer.borrow();
+        let (Some(language), Some(tree)) = (buffer.treesitter_language(), buffer.tree()) else {
+            return show_info("This buffer has no tree-sitter grammar".to_string());
+        };
+        let query = match tree_sitter::Query::new(&language, query) {
+            Ok(query) => query,
+            Err(error) => {
+                return show_info(format!(
+                    "Invalid query ({:?} error at line {}, column {}): {}",
+                    error.kind,
+                    error.row + 1,
+                    error.column + 1,
+                    error.message
+                ))
+            }
+        };
+        let content = buffer.content();
+        let ranges = tree_sitter::QueryCursor::new()
+            .matches(&query, tree.root_node(), content.as_bytes())
+            .flat_map(|query_match| {
+                query_match
+                    .captures
+                    .iter()
+                    .map(|capture| capture.node.byte_range())
+                    .collect_vec()
+            })
+            .sorted_by_key(|range| (range.start, range.end))
+            .dedup()
+            .collect_vec();
+        let selections: Vec<_> = ranges
+            .iter()
+            .map(|range| {
+                Ok(Selection::new(
+                    buffer.byte_range_to_char_index_range(range)?,
+                ))
+            })
+            .collect::<anyhow::Result<_>>()?;
+        drop(buffer);
+        let Some(selections) = NonEmpty::from_vec(selections) else {
+            return show_info("No nodes are captured by the query".to_string());
+        };
+        let selection_set = SelectionSet::new(selections).set_mode(SelectionMode::Custom);
+        Ok(self.update_selection_set(selection_set, true))
+    }
+
+    /// Select the outermost node of a consecutive run of ancestors of each cursor
+    /// whose kind contains `kind`, for example `string_literal` and `string_content` for `"string"`.
+    fn select_enclosing_node(
+        &mut self,
+        kind: &'static str,
+        include_delimiters: bool,
+    ) -> anyhow::Result<Dispatches> {
+        self.select_enclosing(
+            &format!("Select enclosing {}", kind),
+            &format!("The cursor is not inside a {}", kind),
+            |node, content| {
+                let mut current = Some(node);
+                let mut found = None;
+                while let Some(node) = current {
+                    if node.kind().contains(kind) {
+                        found = Some(node)
+                    } else if found.is_some() {
+                        break;
+                    }
+                    current = node.parent();
+                }
+                let found = found?;
+                Some(if include_delimiters {
+                    found.byte_range()
+                } else {
+                    inner_byte_range(found, content)
+                })
+            },
+        )
+    }
+
+    /// Select the body of the innermost function enclosing each cursor, excluding its signature.
+    ///
+    /// The braces of a block body are excluded,
+    /// while an expression body, such as that of an arrow function, is selected entirely.
+    fn select_function_body(&mut self) -> anyhow::Result<Dispatches> {
+        self.select_enclosing(
+            "Select function body",
+            "The cursor is not inside a function",
+            |node, content| {
+                let mut current = Some(node);
+                while let Some(node) = current {
+                    if let Some(body) = node
+                        .child_by_field_name("body")
+                        .filter(|_| is_function_node(&node))
+                    {
+                        if body.child(0).map(|first| first.kind()) != Some("{") {
+                            return Some(body.byte_range());
+                        }
+                        // Exclude the whitespaces surrounding the statements
+                        let range = inner_byte_range(body, content);
+                        let text = &content[range.clone()];
+                        let start = range.start + text.len() - text.trim_start().len();
+                        let end = (range.start + text.trim_end().len()).max(start);
+                        return Some(start..end);
+                    }
+                    current = node.parent();
+                }
+                None
+            },
+        )
+    }
+
+    fn search_clipboard_content(
+        &mut self,
+        context: &Context,
+        whole_word: bool,
+        use_system_clipboard: bool,
+    ) -> anyhow::Result<Dispatches> {
+        let search = context
+            .get_clipboard_content(use_system_clipboard, 0)?
+            .map(|copied_texts| copied_texts.get(0))
+            .filter(|search| !search.is_empty());
+        let Some(search) = search else {
+            return Ok(Dispatches::one(Dispatch::ShowEditorInfo(Info::new(
+                "Search clipboard".to_string(),
+                "The clipboard is empty".to_string(),
+            ))));
+        };
+        let multiline = search.contains('\n');
+        self.set_selection_mode(SelectionMode::Find {
+            search: Search {
+                search,
+                mode: LocalSearchConfigMode::Regex(crate::list::grep::RegexConfig {
+                    escaped: true,
+                    case_sensitive: true,
+                    match_whole_word: whole_word,
+                    multiline,
+                }),
+            },
+        })
+    }
+
+    /// Select every occurrence of the word under the primary cursor,
+    /// within the innermost function enclosing it, or within the whole file outside of functions.
+    fn select_word_under_cursor_occurrences(&mut self) -> anyhow::Result<Dispatches> {
+        let buffer = self.buffer.borrow();
+        let content = buffer.content();
+        let cursor = buffer.char_to_byte(
+            self.selection_set
+                .primary_selection()
+                .to_char_index(&self.cursor_direction),
+        )?;
+        let word_chars = buffer.word_chars();
+        let is_word_char =
+            |char: char| char.is_alphanumeric() || char == '_' || word_chars.contains(char);
+        let start = content[..cursor]
+            .char_indices()
+            .rev()
+            .take_while(|(_, char)| is_word_char(*char))
+            .last()
+            .map(|(index, _)| index)
+            .unwrap_or(cursor);
+        let end = content[cursor..]
+            .char_indices()
+            .find(|(_, char)| !is_word_char(*char))
+            .map(|(index, _)| cursor + index)
+            .unwrap_or(content.len());
+        if start == end {
+            return Ok(Dispatches::one(Dispatch::ShowEditorInfo(Info::new(
+                "Select word occurrences".to_string(),
+                "The cursor is not on a word".to_string(),
+            ))));
+        }
+        let scope = buffer
+            .tree()
+            .and_then(|tree| {
+                std::iter::successors(
+                    tree.root_node().descendant_for_byte_range(start, end),
+                    |node| node.parent(),
+                )
+                .find(is_function_node)
+            })
+            .map(|node| node.byte_range())
+            .unwrap_or(0..content.len());
+        let regex = regex::Regex::new(&regex::escape(&content[start..end]))?;
+        // `\b` only knows about `\w`, so the boundaries are checked with the word chars instead
+        let selections: Vec<_> = regex
+            .find_iter(&content[scope.clone()])
+            .filter(|found| {
+                let byte_start = scope.start + found.start();
+                let byte_end = scope.start + found.end();
+                !content[..byte_start]
+                    .chars()
+                    .next_back()
+                    .is_some_and(is_word_char)
+                    && !content[byte_end..].chars().next().is_some_and(is_word_char)
+            })
+            .map(|found| -> anyhow::Result<_> {
+                let byte_range = scope.start + found.start()..scope.start + found.end();
+                Ok(Selection::new(
+                    buffer.byte_range_to_char_index_range(&byte_range)?,
+                ))
+            })
+            .try_collect()?;
+        drop(buffer);
+        let Some(selections) = NonEmpty::from_vec(selections) else {
+            return Ok(Default::default());
+        };
+        let selection_set = self
+            .selection_set
+            .clone()
+            .set_selections(selections)
+            .set_mode(SelectionMode::Custom);
+        Ok(self.update_selection_set(selection_set, true))
+    }
+
+    /// Select the name of the partner tag of the innermost markup element enclosing each cursor,
+    /// that is the closing tag, unless the cursor is already within the closing tag.
+    ///
+    /// Both HTML and JSX elements are recognized.
+    fn go_to_matching_tag(&mut self) -> anyhow::Result<Dispatches> {
+        self.select_enclosing(
+            "Go to matching tag",
+            "The cursor is not inside a markup element",
+            |node, _| {
+                let element =
+                    std::iter::successors(Some(node), |node| node.parent()).find(|element| {
+                        element
+                            .child(element.child_count().saturating_sub(1))
+                            .map(|last| matches!(last.kind(), "end_tag" | "jsx_closing_element"))
+                            .unwrap_or(false)
+                    })?;
+                let open = element.child(0)?;
+                let close = element.child(element.child_count() - 1)?;
+                let partner = if node.start_byte() >= close.start_byte() {
+                    open
+                } else {
+                    close
+                };
+                let name = partner.child_by_field_name("name").or_else(|| {
+                    partner
+                        .named_child(0)
+                        .filter(|child| child.kind() == "tag_name")
+                })?;
+                Some(name.byte_range())
+            },
+        )
+    }
+
+    /// Replace each selection with the byte range returned by `get_byte_range`,
+    /// which is given the smallest node at the cursor of the selection.
+    ///
+    /// Selections without such a range are left untouched,
+    /// and `not_found` is shown if none of the selections has one.
+    fn select_enclosing<F>(
+        &mut self,
+        title: &str,
+        not_found: &str,
+        get_byte_range: F,
+    ) -> anyhow::Result<Dispatches>
+    where
+        F: Fn(tree_sitter::Node, &str) -> Option<Range<usize>>,
+    {
+        let buffer = self.buffer.borrow();
+        let Some(tree) = buffer.tree() else {
+            return Ok(Default::default());
+        };
+        let content = buffer.content();
+        let selections: Vec<(Selection, Option<CharIndexRange>)> = self
+            .selection_set
+            .map(|selection| -> anyhow::Result<_> {
+                let byte = buffer.char_to_byte(selection.to_char_index(&self.cursor_direction))?;
+                let range = tree
+                    .root_node()
+                    .descendant_for_byte_range(byte, byte)
+                    .and_then(|node| get_byte_range(node, &content))
+                    .map(|byte_range| buffer.byte_range_to_char_index_range(&byte_range))
+                    .transpose()?;
+                Ok((selection.clone(), range))
+            })
+            .into_iter()
+            .try_collect()?;
+        drop(buffer);
+        if selections.iter().all(|(_, range)| range.is_none()) {
+            return Ok(Dispatches::one(Dispatch::ShowEditorInfo(Info::new(
+                title.to_string(),
+                not_found.to_string(),
+            ))));
+        }
+        let Some(selections) = NonEmpty::from_vec(
+            selections
+                .into_iter()
+                .map(|(selection, range)| match range {
+                    Some(range) => selection.set_range(range).set_initial_range(None),
+                    None => selection,
+                })
+                .collect_vec(),
+        ) else {
+            return Ok(Default::default());
+        };
+        let selection_set = self
+            .selection_set
+            .clone()
+            .set_selections(selections)
+            .set_mode(SelectionMode::Custom);
+        Ok(self.update_selection_set(selection_set, true))
+    }
+
+    fn navigate_to_import_block(&mut self) -> anyhow::Result<Dispatches> {
+        let buffer = self.buffer.borrow();
+        let Some(byte_range) = buffer.import_block_byte_range() else {
+            return Ok(Dispatches::one(Dispatch::ShowEditorInfo(Info::new(
+                "Navigate to import block".to_string(),
+                "No import block found".to_string(),
+            ))));
+        };
+        let range = buffer.byte_range_to_char_index_range(&byte_range)?;
+        drop(buffer);
+        let selection_set =
+            SelectionSet::new(NonEmpty::new(Selection::new(range))).set_mode(SelectionMode::Custom);
+        Ok(self.update_selection_set(selection_set, true))
+    }
+
+    fn sort_imports(&mut self, context: &Context) -> anyhow::Result<Dispatches> {
+        let edit_transaction = self
+            .buffer()
+            .get_edit_transaction_of_sort_imports(context.group_imports())?;
+        match edit_transaction {
+            Some(edit_transaction) => self.apply_edit_transaction(edit_transaction),
+            None => Ok(Default::default()),
+        }
+    }
+
+    /// Every selection is shifted by the length of the comment token inserted or removed before it,
+    /// so that the cursor stays on the same character.
+    fn toggle_line_comment(&mut self) -> anyhow::Result<Dispatches> {
+        let buffer = self.buffer();
+        let Some(token) = buffer
+            .language()
+            .and_then(|language| language.line_comment())
+        else {
+            return Ok(Dispatches::one(Dispatch::ShowEditorInfo(Info::new(
+                "Toggle line comment".to_string(),
+                "The language of this file has no line comment".to_string(),
+            ))));
+        };
+        let line_indices = self
+            .selection_set
+            .map(|selection| -> anyhow::Result<_> {
+                let range = selection.extended_range();
+                let start = buffer.char_to_line(range.start)?;
+                let end = buffer.char_to_line(range.end.max(range.start + 1) - 1)?;
+                Ok(start..=end)
+            })
+            .into_iter()
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .sorted()
+            .dedup()
+            .collect_vec();
+        let lines = line_indices
+            .into_iter()
+            .filter_map(|line_index| {
+                let line = buffer.get_line_by_line_index(line_index)?.to_string();
+                let indent = line.chars().take_while(|char| char.is_whitespace()).count();
+                (!line.trim().is_empty()).then_some((line_index, line, indent))
+            })
+            .collect_vec();
+        let Some(min_indent) = lines.iter().map(|(_, _, indent)| *indent).min() else {
+            return Ok(Default::default());
+        };
+        // The token must be followed by a whitespace or the end of the line,
+        // so that neither `/// doc` (for `//`) nor a `#!` shebang (for `#`) counts as commented
+        let commented = lines.iter().all(|(_, line, _)| {
+            line.trim_start()
+                .strip_prefix(token)
+                .is_some_and(|rest| rest.chars().next().map_or(true, char::is_whitespace))
+        });
+        // (position, removed chars count, inserted text), sorted by position
+        let edits = lines
+            .iter()
+            .map(|(line_index, line, indent)| -> anyhow::Result<_> {
+                let line_start = buffer.line_to_char(*line_index)?.0;
+                Ok(if commented {
+                    let after_token = line.chars().skip(indent + token.chars().count());
+                    let space_count = after_token.take(1).filter(|char| *char == ' ').count();
+                    (
+                        line_start + indent,
+                        token.chars().count() + space_count,
+                        String::new(),
+                    )
+                } else {
+                    (line_start + min_indent, 0, format!("{} ", token))
+                })
+            })
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let block_start = edits.first().map(|(position, _, _)| *position).unwrap_or(0);
+        let block_end = edits
+            .last()
+            .map(|(position, removed, _)| position + removed)
+            .unwrap_or(0);
+        let new = edits
+            .iter()
+            .zip(
+                edits
+                    .iter()
+                    .skip(1)
+                    .map(|(position, _, _)| *position)
+                    .chain(Some(block_end)),
+            )
+            .map(
+                |((position, removed, inserted), next)| -> anyhow::Result<_> {
+                    let unchanged =
+                        buffer.slice(&(CharIndex(position + removed)..CharIndex(next)).into())?;
+                    Ok(format!("{}{}", inserted, unchanged))
+                },
+            )
+            .collect::<anyhow::Result<String>>()?;
+        let shift = |char_index: CharIndex| -> CharIndex {
+            let mut offset: isize = 0;
+            for (position, removed, inserted) in &edits {
+                if char_index.0 < *position {
+                    break;
+                }
+                if char_index.0 < position + removed {
+                    // The cursor is on the removed token, so it is moved to where the token was
+                    return CharIndex((*position as isize + offset) as usize);
+                }
+                offset += inserted.chars().count() as isize - *removed as isize;
+            }
+            CharIndex((char_index.0 as isize + offset) as usize)
+        };
+        let selections = self.selection_set.map(|selection| {
+            let range = selection.extended_range();
+            Action::Select(
+                selection
+                    .clone()
+                    .set_range((shift(range.start)..shift(range.end)).into()),
+            )
+        });
+        drop(buffer);
+        let edit_transaction = EditTransaction::from_action_groups(
+            [ActionGroup::new(
+                Some(Action::Edit(Edit {
+                    range: (CharIndex(block_start)..CharIndex(block_end)).into(),
+                    new: new.into(),
+                }))
+                .into_iter()
+                .chain(selections)
+                .collect_vec(),
+            )]
+            .to_vec(),
+        );
+        self.apply_edit_transaction(edit_transaction)
+    }
+
+    fn toggle_line_ending(&mut self) -> anyhow::Result<Dispatches> {
+        let line_ending = self.buffer().line_ending().toggle();
+        let edit_transaction = self
+            .buffer_mut()
+            .get_edit_transaction_of_line_ending(line_ending)?;
+        let dispatches = match edit_transaction {
+            Some(edit_transaction) => self.apply_edit_transaction(edit_transaction)?,
+            None => Default::default(),
+        };
+        Ok(dispatches.append(Dispatch::ShowEditorInfo(Info::new(
+            "Line ending".to_string(),
+            format!("Converted to {}", line_ending.display()),
+        ))))
+    }
+
     #[cfg(test)]
     pub(crate) fn reset(&mut self) {
         self.selection_set.escape_highlight_mode();
@@ -625,6 +1369,7 @@ impl Editor {
         {
             self.align_cursor_to_center();
             self.current_view_alignment = None;
+            self.scroll_animation_target = None;
         }
     }
 
@@ -821,6 +1566,111 @@ impl Editor {
         }))
     }
 
+    fn flash_yank(&mut self, context: &Context) -> Dispatches {
+        if !context.yank_flash() {
+            self.yank_flash = None;
+            return Default::default();
+        }
+        let duration = context.yank_flash_duration();
+        self.yank_flash = Some((
+            self.selection_set
+                .map(|selection| selection.extended_range())
+                .into(),
+            std::time::Instant::now() + duration,
+        ));
+        Dispatches::one(Dispatch::ScheduleRender(duration))
+    }
+
+    pub(crate) fn set_document_highlights(&mut self, ranges: Vec<Range<Position>>) {
+        let buffer = self.buffer();
+        self.document_highlights = ranges
+            .iter()
+            .filter_map(|range| buffer.position_range_to_char_index_range(range).ok())
+            .collect();
+    }
+
+    pub(crate) fn document_highlights(&self) -> &[CharIndexRange] {
+        &self.document_highlights
+    }
+
+    pub(crate) fn clear_document_highlights(&mut self) {
+        self.document_highlights.clear()
+    }
+
+    /// The highlights are kept while the cursor is still on one of the occurrences,
+    /// so that they do not flicker while the new ones are being requested.
+    pub(crate) fn clear_document_highlights_unless_under_cursor(&mut self) {
+        let cursor = self.get_cursor_char_index();
+        if !self
+            .document_highlights
+            .iter()
+            .any(|range| range.start <= cursor && cursor < range.end)
+        {
+            self.document_highlights.clear()
+        }
+    }
+
+    /// Returns the ranges to be highlighted as the yank flash, if it has not expired.
+    pub(crate) fn yank_flash_ranges(&self) -> Vec<CharIndexRange> {
+        self.yank_flash
+            .as_ref()
+            .filter(|(_, until)| std::time::Instant::now() < *until)
+            .map(|(ranges, _)| ranges.clone())
+            .unwrap_or_default()
+    }
+
+    fn copy_as_markdown_code_block(
+        &self,
+        context: &Context,
+        with_location: bool,
+        use_system_clipboard: bool,
+    ) -> anyhow::Result<Dispatches> {
+        let buffer = self.buffer();
+        let path = buffer.path();
+        let language = buffer
+            .language()
+            .and_then(|language| language.id())
+            .map(|id| id.to_string())
+            .or_else(|| Some(path.as_ref()?.extension()?.to_string()))
+            .unwrap_or_default();
+        let code_blocks = self
+            .selection_set
+            .map(|selection| -> anyhow::Result<String> {
+                let range = selection.extended_range();
+                let content = buffer.slice(&range)?.to_string();
+                let header = match &path {
+                    Some(path) if with_location => {
+                        let start_line = buffer.char_to_line(range.start)? + 1;
+                        let end_line = buffer.char_to_line((range.end - 1).max(range.start))? + 1;
+                        let path = path
+                            .display_relative_to(context.current_working_directory())
+                            .unwrap_or_else(|_| path.display_absolute());
+                        if start_line == end_line {
+                            format!("{}:{}\n", path, start_line)
+                        } else {
+                            format!("{}:{}-{}\n", path, start_line, end_line)
+                        }
+                    }
+                    _ => String::new(),
+                };
+                Ok(format!(
+                    "{}```{}\n{}\n```",
+                    header,
+                    language,
+                    content.trim_end_matches('\n')
+                ))
+            })
+            .into_iter()
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        let Some(code_blocks) = NonEmpty::from_vec(code_blocks) else {
+            return Ok(Dispatches::default());
+        };
+        Ok(Dispatches::one(Dispatch::SetClipboardContent {
+            use_system_clipboard,
+            copied_texts: CopiedTexts::new(code_blocks),
+        }))
+    }
+
     fn replace_current_selection_with<F>(&mut self, f: F) -> anyhow::Result<Dispatches>
     where
         F: Fn(&Selection) -> Option<Rope>,
@@ -893,11 +1743,19 @@ impl Editor {
         self.apply_edit_transaction(edit_transaction)
     }
 
+    /// Plain-text buffers are never reindented.
+    fn reindents_on_paste(&self, context: &Context) -> bool {
+        context.reindent_on_paste() && self.buffer().language().is_some()
+    }
+
     fn paste_text(
         &mut self,
         direction: Direction,
         copied_texts: CopiedTexts,
+        reindent: bool,
     ) -> anyhow::Result<Dispatches> {
+        // The line of each insertion, and the number of lines it adds
+        let mut pasted_lines = Vec::new();
         let edit_transaction = EditTransaction::from_action_groups({
             self.get_selection_set_with_gap()
                 .into_iter()
@@ -909,7 +1767,34 @@ impl Editor {
                         Direction::End => current_range.end,
                     };
                     let insertion_range = insertion_range_start..insertion_range_start;
-                    let copied_text: Rope = copied_texts.get(index).into();
+                    let copied_text = copied_texts.get(index);
+                    let copied_text: Rope = if reindent {
+                        let buffer = self.buffer();
+                        let indentation = buffer
+                            .get_line_by_char_index(insertion_range_start)
+                            .map(|line| {
+                                line.chars()
+                                    .take_while(|char| [' ', '\t'].contains(char))
+                                    .collect::<String>()
+                            })
+                            .unwrap_or_default();
+                        // The gap is inserted before the copied text when pasting after the selection
+                        let starts_at_column_0 =
+                            match (self.mode == Mode::Normal, &direction, gap.len_chars()) {
+                                (true, Direction::End, len) if len > 0 => gap.char(len - 1) == '\n',
+                                _ => buffer
+                                    .char_to_position(insertion_range_start)
+                                    .is_ok_and(|position| position.column == 0),
+                            };
+                        crate::transformation::reindent(
+                            &copied_text,
+                            &indentation,
+                            starts_at_column_0,
+                        )
+                        .into()
+                    } else {
+                        copied_text.into()
+                    };
                     let copied_text_len = copied_text.len_chars();
 
                     let (selection_range, paste_text) = if self.mode == Mode::Normal {
@@ -940,6 +1825,9 @@ impl Editor {
                         let paste_text = copied_text;
                         (selection_range, paste_text)
                     };
+                    if let Ok(line) = self.buffer().char_to_line(insertion_range_start) {
+                        pasted_lines.push((line, paste_text.len_lines() - 1));
+                    }
                     ActionGroup::new(
                         [
                             Action::Edit(Edit {
@@ -953,10 +1841,63 @@ impl Editor {
                 })
                 .collect()
         });
-        self.apply_edit_transaction(edit_transaction)
-    }
-
-    pub(crate) fn paste(
+        let dispatches = self.apply_edit_transaction(edit_transaction)?;
+        let added_lines: usize = pasted_lines.iter().map(|(_, count)| count).sum();
+        let lines = pasted_lines
+            .iter()
+            .map(|(line, _)| *line)
+            .minmax()
+            .into_option()
+            .map(|(start, end)| start..=end + added_lines);
+        match lines {
+            // Single-line pastes are left as is, there is hardly anything to format
+            Some(lines) if reindent && added_lines > 0 => {
+                Ok(dispatches.append(self.format_pasted_lines(lines)))
+            }
+            _ => Ok(dispatches),
+        }
+    }
+
+    /// Formats the pasted `lines` in the background with the formatter of the language,
+    /// or with the language server if the language has no formatter.
+    fn format_pasted_lines(&self, lines: RangeInclusive<usize>) -> Dispatch {
+        let has_formatter = self
+            .buffer()
+            .language()
+            .is_some_and(|language| language.formatter().is_some());
+        if has_formatter {
+            Dispatch::FormatLines {
+                component_id: self.id(),
+                lines,
+            }
+        } else {
+            Dispatch::RequestRangeFormatting(
+                Position::new(*lines.start(), 0)..Position::new(*lines.end() + 1, 0),
+            )
+        }
+    }
+
+    /// Apply the changes of the `formatted` content that touch the given `lines`,
+    /// unless the buffer has been modified since its `version` was formatted.
+    pub(crate) fn apply_formatted_lines(
+        &mut self,
+        version: usize,
+        lines: RangeInclusive<usize>,
+        formatted: &str,
+    ) -> anyhow::Result<Dispatches> {
+        if self.buffer().version() != version {
+            return Ok(Default::default());
+        }
+        let edit_transaction = self
+            .buffer()
+            .get_edit_transaction_of_formatted_lines(formatted, lines)?;
+        if edit_transaction.edits().is_empty() {
+            return Ok(Default::default());
+        }
+        self.apply_edit_transaction(edit_transaction)
+    }
+
+    pub(crate) fn paste(
         &mut self,
         direction: Direction,
         context: &Context,
@@ -965,7 +1906,7 @@ impl Editor {
         let Some(copied_texts) = context.get_clipboard_content(use_system_clipboard, 0)? else {
             return Ok(Default::default());
         };
-        self.paste_text(direction, copied_texts)
+        self.paste_text(direction, copied_texts, self.reindents_on_paste(context))
     }
 
     /// If `cut` if true, the replaced text will override the clipboard.  
@@ -1035,6 +1976,9 @@ impl Editor {
 
         self.set_selection_set(new_selection_set);
 
+        // The ranges of the yank flash are not shifted by the edit, so they would be stale
+        self.yank_flash = None;
+
         self.recalculate_scroll_offset();
 
         Ok(self.get_document_did_change_dispatch())
@@ -1078,6 +2022,45 @@ impl Editor {
         self.navigate_undo_tree(Movement::Next)
     }
 
+    /// Extend each selection to the nearest bookmark in the given direction,
+    /// while its other end stays as the anchor.
+    ///
+    /// Selections without a bookmark in the given direction are left untouched.
+    fn select_to_bookmark(&mut self, direction: Direction) -> anyhow::Result<Dispatches> {
+        let bookmarks = self.buffer().bookmarks();
+        let cursor_direction = self.cursor_direction.clone();
+        let selection_set = self
+            .selection_set
+            .apply(SelectionMode::Custom, |selection| {
+                let cursor = selection.to_char_index(&cursor_direction);
+                let range = selection.extended_range();
+                let range = match direction {
+                    Direction::End => bookmarks
+                        .iter()
+                        .filter(|bookmark| bookmark.start > cursor)
+                        .min_by_key(|bookmark| bookmark.start)
+                        .map(|bookmark| range.start..bookmark.end),
+                    Direction::Start => bookmarks
+                        .iter()
+                        .filter(|bookmark| bookmark.end <= cursor)
+                        .max_by_key(|bookmark| bookmark.end)
+                        .map(|bookmark| bookmark.start..range.end),
+                };
+                Ok(match range {
+                    Some(range) => selection
+                        .clone()
+                        .set_range(range.into())
+                        .set_initial_range(None),
+                    None => selection.clone(),
+                })
+            })?;
+        let dispatches = self.update_selection_set(selection_set, true);
+        // Set after `update_selection_set`, which resets the cursor direction
+        self.cursor_direction = direction;
+        self.recalculate_scroll_offset();
+        Ok(dispatches)
+    }
+
     pub(crate) fn swap_cursor_with_anchor(&mut self) {
         self.cursor_direction = match self.cursor_direction {
             Direction::Start => Direction::End,
@@ -1086,6 +2069,11 @@ impl Editor {
         self.recalculate_scroll_offset()
     }
 
+    pub(crate) fn align_cursors_to_anchor(&mut self, direction: Direction) {
+        self.cursor_direction = direction;
+        self.recalculate_scroll_offset()
+    }
+
     fn get_selection_set(
         &self,
         mode: &SelectionMode,
@@ -1213,41 +2201,85 @@ impl Editor {
     }
 
     pub(crate) fn insert(&mut self, s: &str) -> anyhow::Result<Dispatches> {
+        let action_groups: Vec<ActionGroup> = self
+            .selection_set
+            .map(|selection| {
+                let range = selection.extended_range();
+                ActionGroup::new(
+                    [
+                        Action::Edit(Edit {
+                            range: {
+                                let start = selection.to_char_index(&Direction::End);
+                                (start..start).into()
+                            },
+                            new: Rope::from_str(s),
+                        }),
+                        Action::Select(
+                            selection
+                                .clone()
+                                .set_range((range.start + s.len()..range.start + s.len()).into()),
+                        ),
+                    ]
+                    .to_vec(),
+                )
+            })
+            .into();
         let edit_transaction =
-            EditTransaction::from_action_groups(
-                self.selection_set
-                    .map(|selection| {
-                        let range = selection.extended_range();
-                        ActionGroup::new(
-                            [
-                                Action::Edit(Edit {
-                                    range: {
-                                        let start = selection.to_char_index(&Direction::End);
-                                        (start..start).into()
-                                    },
-                                    new: Rope::from_str(s),
-                                }),
-                                Action::Select(selection.clone().set_range(
-                                    (range.start + s.len()..range.start + s.len()).into(),
-                                )),
-                            ]
-                            .to_vec(),
-                        )
-                    })
-                    .into(),
-            );
+            EditTransaction::from_action_groups(self.with_paired_tag_renames(action_groups));
 
         self.apply_edit_transaction(edit_transaction)
     }
 
+    /// Append to `action_groups` the edits that mirror each of its edits made within a tag name
+    /// to the name of the matching opening/closing tag, so that editing one tag name of a
+    /// markup element renames its partner as well, in the same undo entry.
+    fn with_paired_tag_renames(&self, action_groups: Vec<ActionGroup>) -> Vec<ActionGroup> {
+        let buffer = self.buffer();
+        let edits = action_groups
+            .iter()
+            .flat_map(|action_group| &action_group.actions)
+            .filter_map(|action| match action {
+                Action::Edit(edit) => Some(edit),
+                _ => None,
+            })
+            .collect_vec();
+        let paired_tag_renames = edits
+            .iter()
+            .filter_map(|edit| {
+                let (name, partner_name) = buffer.get_paired_tag_names(edit.range)?;
+                // Skip names that are out of sync, and partners that are already being edited
+                if buffer.slice(&name).ok()? != buffer.slice(&partner_name).ok()?
+                    || edits.iter().any(|other| {
+                        partner_name.start <= other.range.start
+                            && other.range.end <= partner_name.end
+                    })
+                {
+                    return None;
+                }
+                let start = partner_name.start + (edit.range.start.0 - name.start.0);
+                Some(Edit {
+                    range: (start..start + edit.range.len()).into(),
+                    new: edit.new.clone(),
+                })
+            })
+            .unique_by(|edit| edit.range)
+            .map(|edit| ActionGroup::new([Action::Edit(edit)].to_vec()))
+            .collect_vec();
+        action_groups
+            .into_iter()
+            .chain(paired_tag_renames)
+            .collect()
+    }
+
     pub(crate) fn get_request_params(&self) -> Option<RequestParams> {
         let position = self.get_cursor_position().ok()?;
         self.path().map(|path| RequestParams {
-            path,
+            path: path.clone(),
             position,
             context: ResponseContext {
                 scope: None,
                 description: None,
+                path: Some(path),
             },
         })
     }
@@ -1298,8 +2330,10 @@ impl Editor {
                 movement,
                 self.selection_set.mode.clone(),
             ),
-            Mode::Exchange => self.exchange(movement),
-            Mode::Replace => self.replace_with_movement(&movement),
+            Mode::Exchange => self.exchange(movement, context.validate_structural_edits()),
+            Mode::Replace => {
+                self.replace_with_movement(&movement, context.validate_structural_edits())
+            }
             Mode::UndoTree => self.navigate_undo_tree(movement),
             Mode::MultiCursor => self.add_cursor(&movement).map(|_| Default::default()),
             _ => Ok(Default::default()),
@@ -1359,7 +2393,9 @@ impl Editor {
                 },
             )?);
             self.clamp()?;
-            self.buffer_mut().reparse_tree()?
+            self.buffer_mut().reparse_tree()?;
+            // Leaving insert mode ends the navigation of the tabstops of the last expanded snippet
+            self.buffer_mut().take_tabstops();
         }
         // TODO: continue from here, need to add test: upon exiting insert mode, should close all panels
         // Maybe we should call this function the exit_insert_mode?
@@ -1408,11 +2444,14 @@ impl Editor {
     /// Get the selection that preserves the syntactic structure of the current selection.
     ///
     /// Returns a valid edit transaction if there is any, otherwise `Left(current_selection)`.
+    ///
+    /// If `validate` is false, the edit transaction of the next selection is returned as is.
     fn get_valid_selection(
         &self,
         current_selection: &Selection,
         selection_mode: &SelectionMode,
         direction: &Movement,
+        validate: bool,
         get_actual_edit_transaction: impl Fn(
             /* current */ &Selection,
             /* next */ &Selection,
@@ -1438,53 +2477,62 @@ impl Editor {
             return Ok(Either::Left(current_selection));
         }
 
-        loop {
+        // Validating the syntactic structure requires reparsing a clone of the buffer
+        // per candidate, which is too slow for large buffers
+        if !validate || buffer.is_large() {
+            return Ok(Either::Right(get_actual_edit_transaction(
+                &current_selection,
+                &next_selection,
+            )?));
+        }
+
+        // A single clone is reused for every candidate, by reverting the edits after validation
+        let mut new_buffer = buffer.clone();
+        for _ in 0..MAX_VALID_SELECTION_ATTEMPTS {
             let edit_transaction =
                 get_actual_edit_transaction(&current_selection, &next_selection)?;
             let current_node = buffer.get_current_node(&current_selection, false)?;
 
-            let new_buffer = {
-                let mut new_buffer = self.buffer.borrow().clone();
-                if new_buffer
-                    .apply_edit_transaction(&edit_transaction, self.selection_set.clone(), true)
-                    .is_err()
-                {
-                    continue;
-                }
-                new_buffer
-            };
-
             let text_at_next_selection: Rope = buffer.slice(&next_selection.extended_range())?;
 
-            let next_nodes = edit_transaction
-                .selections()
-                .into_iter()
-                .map(|selection| -> anyhow::Result<_> {
-                    new_buffer.get_current_node(selection, false)
-                })
-                .collect::<Result<Vec<_>, _>>()?;
-
-            // Why don't we just use `tree.root_node().has_error()` instead?
-            // Because I assume we want to be able to exchange even if some part of the tree
-            // contains error
-            if !selection_mode.is_node()
-                || (!text_at_next_selection.to_string().trim().is_empty()
-                    && next_nodes
-                        .iter()
-                        .all(|next_node| match (current_node, next_node) {
-                            (Some(current_node), Some(next_node)) => {
-                                current_node.kind_id() == next_node.kind_id()
-                                    && current_node.byte_range().len()
-                                        == next_node.byte_range().len()
-                            }
-                            (_, _) => true,
+            let is_valid = match new_buffer.apply_edits_transiently(&edit_transaction) {
+                Ok(reverse_edits) => {
+                    let next_nodes = edit_transaction
+                        .selections()
+                        .into_iter()
+                        .map(|selection| -> anyhow::Result<_> {
+                            new_buffer.get_current_node(selection, false)
                         })
-                    && !new_buffer.has_syntax_error_at(edit_transaction.range()))
-            {
-                return Ok(Either::Right(get_actual_edit_transaction(
-                    &current_selection,
-                    &next_selection,
-                )?));
+                        .collect::<Result<Vec<_>, _>>()?;
+
+                    // Why don't we just use `tree.root_node().has_error()` instead?
+                    // Because I assume we want to be able to exchange even if some part of the tree
+                    // contains error
+                    let is_valid = !selection_mode.is_node()
+                        || (!text_at_next_selection.to_string().trim().is_empty()
+                            && next_nodes.iter().all(|next_node| {
+                                match (current_node, next_node) {
+                                    (Some(current_node), Some(next_node)) => {
+                                        current_node.kind_id() == next_node.kind_id()
+                                            && current_node.byte_range().len()
+                                                == next_node.byte_range().len()
+                                    }
+                                    (_, _) => true,
+                                }
+                            })
+                            && !new_buffer.has_syntax_error_at(edit_transaction.range()));
+                    new_buffer.revert_edits(reverse_edits)?;
+                    is_valid
+                }
+                Err(_) => {
+                    // The clone might be partially edited, so start afresh
+                    new_buffer = buffer.clone();
+                    false
+                }
+            };
+
+            if is_valid {
+                return Ok(Either::Right(edit_transaction));
             }
 
             // Get the next selection
@@ -1500,11 +2548,12 @@ impl Editor {
             .selection;
 
             if next_selection.eq(&new_selection) {
-                return Ok(Either::Left(current_selection));
+                break;
             }
 
             next_selection = new_selection;
         }
+        Ok(Either::Left(current_selection))
     }
 
     /// Replace the next selection with the current selection without
@@ -1513,6 +2562,7 @@ impl Editor {
         &mut self,
         selection_mode: &SelectionMode,
         movement: Movement,
+        validate: bool,
     ) -> anyhow::Result<Dispatches> {
         let buffer = self.buffer.borrow().clone();
         let get_edit_transaction = |current_selection: &Selection,
@@ -1554,7 +2604,13 @@ impl Editor {
         };
 
         let edit_transactions = self.selection_set.map(|selection| {
-            self.get_valid_selection(selection, selection_mode, &movement, get_edit_transaction)
+            self.get_valid_selection(
+                selection,
+                selection_mode,
+                &movement,
+                validate,
+                get_edit_transaction,
+            )
         });
 
         self.apply_edit_transaction(EditTransaction::merge(
@@ -1566,9 +2622,13 @@ impl Editor {
         ))
     }
 
-    pub(crate) fn exchange(&mut self, movement: Movement) -> anyhow::Result<Dispatches> {
+    pub(crate) fn exchange(
+        &mut self,
+        movement: Movement,
+        validate: bool,
+    ) -> anyhow::Result<Dispatches> {
         let mode = self.selection_set.mode.clone();
-        self.replace_faultlessly(&mode, movement)
+        self.replace_faultlessly(&mode, movement, validate)
     }
 
     pub(crate) fn add_cursor(&mut self, movement: &Movement) -> anyhow::Result<()> {
@@ -1612,31 +2672,61 @@ impl Editor {
         self.rectangle.dimension()
     }
 
+    /// Move the view without moving the selections.
+    ///
+    /// If `smooth` is true, the view is moved gradually by `advance_scroll_animation`.
+    fn scroll_view(
+        &mut self,
+        direction: Direction,
+        scroll_height: usize,
+        smooth: bool,
+    ) -> Dispatches {
+        if !smooth || self.scroll_animation_target.is_some() {
+            self.apply_scroll(direction, scroll_height);
+            return Default::default();
+        }
+        let scroll_offset = self.scroll_offset;
+        self.apply_scroll(direction, scroll_height);
+        if self.scroll_offset == scroll_offset {
+            return Default::default();
+        }
+        self.scroll_animation_target = Some(self.scroll_offset);
+        self.scroll_offset = scroll_offset;
+        Dispatches::one(Dispatch::StartScrollAnimation(self.id()))
+    }
+
     fn apply_scroll(&mut self, direction: Direction, scroll_height: usize) {
-        self.scroll_offset = match direction {
-            Direction::Start => self.scroll_offset.saturating_sub(scroll_height as u16),
-            Direction::End => self.scroll_offset.saturating_add(scroll_height as u16),
+        let apply = |offset: u16| match direction {
+            Direction::Start => offset.saturating_sub(scroll_height as u16),
+            Direction::End => offset.saturating_add(scroll_height as u16),
         };
+        // Shift the destination of an ongoing smooth scroll, so that they do not fight each other
+        if let Some(target) = self.scroll_animation_target {
+            self.scroll_animation_target = Some(apply(target));
+        } else {
+            self.scroll_offset = apply(self.scroll_offset);
+        }
     }
 
     pub(crate) fn backspace(&mut self) -> anyhow::Result<Dispatches> {
-        let edit_transaction = EditTransaction::from_action_groups(
-            self.selection_set
-                .map(|selection| {
-                    let start = CharIndex(selection.extended_range().start.0.saturating_sub(1));
-                    ActionGroup::new(
-                        [
-                            Action::Edit(Edit {
-                                range: (start..selection.extended_range().start).into(),
-                                new: Rope::from(""),
-                            }),
-                            Action::Select(selection.clone().set_range((start..start).into())),
-                        ]
-                        .to_vec(),
-                    )
-                })
-                .into(),
-        );
+        let action_groups: Vec<ActionGroup> = self
+            .selection_set
+            .map(|selection| {
+                let start = CharIndex(selection.extended_range().start.0.saturating_sub(1));
+                ActionGroup::new(
+                    [
+                        Action::Edit(Edit {
+                            range: (start..selection.extended_range().start).into(),
+                            new: Rope::from(""),
+                        }),
+                        Action::Select(selection.clone().set_range((start..start).into())),
+                    ]
+                    .to_vec(),
+                )
+            })
+            .into();
+        let edit_transaction =
+            EditTransaction::from_action_groups(self.with_paired_tag_renames(action_groups));
 
         self.apply_edit_transaction(edit_transaction)
     }
@@ -1709,6 +2799,7 @@ impl Editor {
     pub(crate) fn replace_with_movement(
         &mut self,
         movement: &Movement,
+        validate: bool,
     ) -> anyhow::Result<Dispatches> {
         let buffer = self.buffer.borrow().clone();
         let edit_transactions = self.selection_set.map(|selection| {
@@ -1745,6 +2836,7 @@ impl Editor {
                 selection,
                 &self.selection_set.mode,
                 movement,
+                validate,
                 get_edit_transaction,
             )
         });
@@ -1766,6 +2858,11 @@ impl Editor {
         self.buffer.clone()
     }
 
+    /// Revert to the title derived from the path of the buffer.
+    pub(crate) fn clear_title(&mut self) {
+        self.title = None
+    }
+
     pub(crate) fn buffer_mut(&mut self) -> RefMut<Buffer> {
         self.buffer.borrow_mut()
     }
@@ -1774,7 +2871,15 @@ impl Editor {
         self.buffer.borrow_mut().update(s)
     }
 
-    fn scroll(&mut self, direction: Direction, scroll_height: usize) -> anyhow::Result<Dispatches> {
+    /// If `smooth` is true, only the selections are moved immediately,
+    /// while the view is moved gradually by `advance_scroll_animation`.
+    fn scroll(
+        &mut self,
+        direction: Direction,
+        scroll_height: usize,
+        smooth: bool,
+    ) -> anyhow::Result<Dispatches> {
+        let scroll_offset = self.scroll_offset;
         let dispatch = self.update_selection_set(
             self.selection_set
                 .apply(self.selection_set.mode.clone(), |selection| {
@@ -1791,10 +2896,39 @@ impl Editor {
             false,
         );
         self.align_cursor_to_center();
+        if smooth && self.scroll_offset != scroll_offset {
+            // An ongoing animation is already ticking towards the target
+            let animating = self.scroll_animation_target.is_some();
+            self.scroll_animation_target = Some(self.scroll_offset);
+            self.scroll_offset = scroll_offset;
+            if !animating {
+                return Ok(dispatch.append(Dispatch::StartScrollAnimation(self.id())));
+            }
+        }
 
         Ok(dispatch)
     }
 
+    /// Move `scroll_offset` one frame closer to the smooth scrolling target.
+    ///
+    /// Returns true if the target is not reached yet.
+    pub(crate) fn advance_scroll_animation(&mut self) -> bool {
+        let Some(target) = self.scroll_animation_target else {
+            return false;
+        };
+        // Ease out: cover a third of the remaining distance on every frame
+        let step = (target.abs_diff(self.scroll_offset) / 3).max(1);
+        self.scroll_offset = if target > self.scroll_offset {
+            self.scroll_offset.saturating_add(step).min(target)
+        } else {
+            self.scroll_offset.saturating_sub(step).max(target)
+        };
+        if self.scroll_offset == target {
+            self.scroll_animation_target = None;
+        }
+        self.scroll_animation_target.is_some()
+    }
+
     /// This returns a vector of selections
     /// with a gap that is the maximum of previous-current gap and current-next gap.
     ///
@@ -1918,9 +3052,16 @@ impl Editor {
         self.apply_edit_transaction(edit_transaction)
     }
 
-    pub(crate) fn save(&mut self) -> anyhow::Result<Dispatches> {
-        let Some(path) = self.buffer.borrow_mut().save(self.selection_set.clone())? else {
-            return Ok(Default::default());
+    pub(crate) fn save(&mut self, context: &Context) -> anyhow::Result<Dispatches> {
+        let Some(path) = self
+            .buffer
+            .borrow_mut()
+            .save(self.selection_set.clone(), context.final_newline())?
+        else {
+            // Unnamed buffers, such as scratch buffers, are saved by prompting for a path
+            return Ok(Dispatches::one(Dispatch::OpenSaveScratchAsPrompt(
+                self.id(),
+            )));
         };
 
         self.clamp()?;
@@ -1978,30 +3119,27 @@ impl Editor {
         self.apply_edit_transaction(edit_transaction)
     }
 
-    fn transform_selection(
-        &mut self,
-        transformation: Transformation,
-    ) -> anyhow::Result<Dispatches> {
+    fn wrap_selection_in_tag(&mut self, tag: String) -> anyhow::Result<Dispatches> {
+        let tag = tag.trim();
+        let Some(name) = tag.split_whitespace().next() else {
+            return Ok(Default::default());
+        };
+        let open = format!("<{}>", tag);
+        let close = format!("</{}>", name);
         let edit_transaction = EditTransaction::from_action_groups(
             self.selection_set
                 .map(|selection| -> anyhow::Result<_> {
-                    let new: Rope = transformation
-                        .apply(
-                            self.buffer()
-                                .slice(&selection.extended_range())?
-                                .to_string(),
-                        )
-                        .into();
-                    let new_char_count = new.chars().count();
                     let range = selection.extended_range();
+                    let old = self.buffer().slice(&range)?;
+                    // Before the `>` of the opening tag, so that attributes can be added
+                    let cursor = range.start + (open.chars().count() - 1);
                     Ok(ActionGroup::new(
                         [
-                            Action::Edit(Edit { range, new }),
-                            Action::Select(
-                                selection
-                                    .clone()
-                                    .set_range((range.start..range.start + new_char_count).into()),
-                            ),
+                            Action::Edit(Edit {
+                                range,
+                                new: format!("{}{}{}", open, old, close).into(),
+                            }),
+                            Action::Select(selection.clone().set_range((cursor..cursor).into())),
                         ]
                         .to_vec(),
                     ))
@@ -2010,55 +3148,565 @@ impl Editor {
                 .flatten()
                 .collect_vec(),
         );
+        Ok(self
+            .apply_edit_transaction(edit_transaction)?
+            .chain(self.enter_insert_mode(Direction::Start)?))
+    }
+
+    fn toggle_case_of_char(&mut self) -> anyhow::Result<Dispatches> {
+        let buffer = self.buffer();
+        // Non-alphabetic characters are left as-is, so the cursors just advance
+        let len_chars = buffer.len_chars();
+        let edit_transaction = EditTransaction::from_action_groups(
+            self.selection_set
+                .map(|selection| -> anyhow::Result<_> {
+                    let cursor = selection.to_char_index(&self.cursor_direction);
+                    // There is no character under a cursor at the end of the buffer
+                    if cursor.0 >= len_chars {
+                        return Ok(None);
+                    }
+                    let range: CharIndexRange = (cursor..cursor + 1).into();
+                    let old = buffer.slice(&range)?.to_string();
+                    let new: String = old
+                        .chars()
+                        .flat_map(|c| {
+                            if c.is_lowercase() {
+                                c.to_uppercase().collect_vec()
+                            } else {
+                                c.to_lowercase().collect_vec()
+                            }
+                        })
+                        .collect();
+                    let new_len_chars = new.chars().count();
+                    let next = cursor + new_len_chars;
+                    let new_buffer_len_chars = len_chars + new_len_chars - old.chars().count();
+                    let selected = if next.0 < new_buffer_len_chars {
+                        next..next + 1
+                    } else {
+                        cursor..next
+                    };
+                    Ok(Some(ActionGroup::new(
+                        [
+                            Action::Edit(Edit {
+                                range,
+                                new: new.into(),
+                            }),
+                            Action::Select(selection.clone().set_range(selected.into())),
+                        ]
+                        .to_vec(),
+                    )))
+                })
+                .into_iter()
+                .collect::<anyhow::Result<Vec<_>>>()?
+                .into_iter()
+                .flatten()
+                .collect_vec(),
+        );
+        drop(buffer);
         self.apply_edit_transaction(edit_transaction)
     }
 
-    pub(crate) fn display_mode(&self) -> String {
-        let selection_mode = self.selection_set.mode.display();
-        let filters = self
-            .selection_set
-            .filters
-            .display()
-            .map(|display| format!("({})", display))
-            .unwrap_or_default();
-        let mode = match &self.mode {
-            Mode::Normal => "MOVE",
-            Mode::Insert => "INSERT",
-            Mode::MultiCursor => "MULTI CURSOR",
-            Mode::FindOneChar => "FIND ONE CHAR",
-            Mode::Exchange => "EXCHANGE",
-            Mode::UndoTree => "UNDO TREE",
-            Mode::Replace => "REPLACE",
-        };
-        let cursor_count = self.selection_set.len();
-        let mode = format!("{}:{}{} x {}", mode, selection_mode, filters, cursor_count);
-        if self.jumps.is_some() {
-            format!("{} (SNEAK)", mode)
-        } else {
-            mode
+    fn expand_snippet(&mut self, context: &Context) -> anyhow::Result<Dispatches> {
+        if let Some(expansions) = self.get_snippet_expansions(context) {
+            return self.apply_snippet_expansions(expansions);
         }
+        if let Some(dispatches) = self.go_to_next_tabstop()? {
+            return Ok(dispatches);
+        }
+        self.insert("\t")
     }
 
-    pub(crate) fn visible_line_range(&self) -> Range<usize> {
-        let start = self.scroll_offset;
-        let end = (start as usize + self.rectangle.height as usize).min(self.buffer().len_lines());
-
-        start as usize..end
+    /// Returns the prefix range and the snippet to be expanded of every cursor,
+    /// or `None` if any of the cursors is not preceded by the prefix of a snippet.
+    fn get_snippet_expansions(&self, context: &Context) -> Option<Vec<(CharIndexRange, Snippet)>> {
+        let buffer = self.buffer();
+        let language_id = buffer.language()?.id()?.to_string();
+        self.selection_set
+            .map(|selection| {
+                let cursor = selection.extended_range().start;
+                let prefix_length = buffer
+                    .rope()
+                    .get_chars_at(cursor.0)?
+                    .reversed()
+                    .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '-')
+                    .count();
+                let range: CharIndexRange = (cursor - prefix_length..cursor).into();
+                let prefix = buffer.slice(&range).ok()?.to_string();
+                Some((range, context.snippets().get(&language_id, &prefix)?))
+            })
+            .into_iter()
+            .collect()
     }
 
-    fn handle_multi_cursor_mode(
+    fn apply_snippet_expansions(
         &mut self,
-        context: &Context,
-        key_event: KeyEvent,
-    ) -> Result<Dispatches, anyhow::Error> {
-        match key_event {
-            key!("esc") => {
-                self.mode = Mode::Normal;
-                Ok(Default::default())
-            }
-
-            other => self.handle_normal_mode(context, other),
-        }
+        expansions: Vec<(CharIndexRange, Snippet)>,
+    ) -> anyhow::Result<Dispatches> {
+        // The tabstops are positioned against the content after all expansions,
+        // so each snippet is offset by the expansions that come before it
+        let mut offset: isize = 0;
+        let mut tabstops = Vec::new();
+        let mut action_groups = Vec::new();
+        for (range, snippet) in expansions
+            .into_iter()
+            .sorted_by_key(|(range, _)| range.start)
+        {
+            let new: Rope = snippet.text.as_str().into();
+            let start = (range.start.0 as isize + offset) as usize;
+            offset += new.len_chars() as isize - range.len() as isize;
+            tabstops.extend(snippet.tabstops.into_iter().map(|tabstop| Tabstop {
+                range: tabstop.range.shift_right(start),
+                ..tabstop
+            }));
+            let end = range.start + new.len_chars();
+            action_groups.push(ActionGroup::new(
+                [
+                    Action::Edit(Edit { range, new }),
+                    Action::Select(Selection::new((end..end).into())),
+                ]
+                .to_vec(),
+            ));
+        }
+        let dispatches =
+            self.apply_edit_transaction(EditTransaction::from_action_groups(action_groups))?;
+        self.buffer_mut().set_tabstops(
+            tabstops
+                .into_iter()
+                .sorted_by_key(|tabstop| (tabstop.number == 0, tabstop.number))
+                .collect(),
+        );
+        Ok(dispatches.chain(self.go_to_next_tabstop()?.unwrap_or_default()))
+    }
+
+    /// Select the next tabstop of the last expanded snippet, returns `None` if there is none.
+    ///
+    /// A placeholder is selected in normal mode so that it can be either kept or changed,
+    /// while an empty tabstop is edited in insert mode.
+    fn go_to_next_tabstop(&mut self) -> anyhow::Result<Option<Dispatches>> {
+        let ranges = self.buffer_mut().take_next_tabstop();
+        let Some(selections) = NonEmpty::from_vec(ranges.into_iter().map(Selection::new).collect())
+        else {
+            return Ok(None);
+        };
+        let is_placeholder = selections
+            .iter()
+            .any(|selection| selection.extended_range().len() > 0);
+        if is_placeholder {
+            // Selecting a placeholder does not end the navigation of the remaining tabstops
+            let tabstops = self.buffer_mut().take_tabstops();
+            self.enter_normal_mode()?;
+            self.buffer_mut().set_tabstops(tabstops);
+            self.set_selection_set(self.selection_set.clone().set_selections(selections));
+            Ok(Some(Default::default()))
+        } else {
+            self.set_selection_set(self.selection_set.clone().set_selections(selections));
+            Ok(Some(self.enter_insert_mode(Direction::Start)?))
+        }
+    }
+
+    /// Insert `text` before each selection, or after it if the cursor is at the end,
+    /// while keeping the selections.
+    pub(crate) fn insert_at_cursor(&mut self, text: &str) -> anyhow::Result<Dispatches> {
+        self.insert_at_cursor_with(|| text.to_string())
+    }
+
+    /// Like [`Editor::insert_at_cursor`], but the text of each selection is produced by `text`.
+    fn insert_at_cursor_with(&mut self, text: impl Fn() -> String) -> anyhow::Result<Dispatches> {
+        let edit_transaction = EditTransaction::from_action_groups(
+            self.selection_set
+                .map(|selection| {
+                    let text: Rope = text().into();
+                    let len = text.len_chars();
+                    let range = selection.extended_range();
+                    let (insertion, selected) = match self.cursor_direction {
+                        Direction::Start => (range.start, range.start + len..range.end + len),
+                        Direction::End => (range.end, range.start..range.end),
+                    };
+                    ActionGroup::new(
+                        [
+                            Action::Edit(Edit {
+                                range: (insertion..insertion).into(),
+                                new: text,
+                            }),
+                            Action::Select(selection.clone().set_range(selected.into())),
+                        ]
+                        .to_vec(),
+                    )
+                })
+                .into_iter()
+                .collect_vec(),
+        );
+        self.apply_edit_transaction(edit_transaction)
+    }
+
+    fn insert_date_time(&mut self, context: &Context) -> anyhow::Result<Dispatches> {
+        let text =
+            crate::date_time::format_now(context.date_time_format(), context.date_time_utc())?;
+        self.insert_at_cursor(&text)
+    }
+
+    fn generate_uuid(&mut self, uppercase: bool) -> anyhow::Result<Dispatches> {
+        self.insert_at_cursor_with(|| {
+            let uuid = uuid::Uuid::new_v4().to_string();
+            if uppercase {
+                uuid.to_uppercase()
+            } else {
+                uuid
+            }
+        })
+    }
+
+    fn pipe_selection(&mut self, command: &str) -> anyhow::Result<Dispatches> {
+        let outputs = self
+            .selection_set
+            .map(|selection| -> anyhow::Result<_> {
+                let input = self
+                    .buffer()
+                    .slice(&selection.extended_range())?
+                    .to_string();
+                Ok((
+                    selection.clone(),
+                    crate::utils::run_shell_command(command, &input)?,
+                ))
+            })
+            .into_iter()
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if let Some(stderr) = outputs.iter().find_map(|(_, output)| output.clone().err()) {
+            return Ok(Dispatches::one(Dispatch::ShowEditorInfo(Info::new(
+                format!("`{}` failed", command),
+                stderr.trim_end().to_string(),
+            ))));
+        }
+        let edit_transaction = EditTransaction::from_action_groups(
+            outputs
+                .into_iter()
+                .filter_map(|(selection, output)| {
+                    let new: Rope = output.ok()?.into();
+                    let range = selection.extended_range();
+                    let new_char_count = new.len_chars();
+                    Some(ActionGroup::new(
+                        [
+                            Action::Edit(Edit { range, new }),
+                            Action::Select(
+                                selection
+                                    .set_range((range.start..range.start + new_char_count).into()),
+                            ),
+                        ]
+                        .to_vec(),
+                    ))
+                })
+                .collect_vec(),
+        );
+        self.apply_edit_transaction(edit_transaction)
+    }
+
+    fn transform_selection(
+        &mut self,
+        transformation: Transformation,
+    ) -> anyhow::Result<Dispatches> {
+        let indent_width = self
+            .buffer()
+            .language()
+            .map(|language| language.indent_width())
+            .unwrap_or(4);
+        let edit_transaction = EditTransaction::from_action_groups(
+            self.selection_set
+                .map(|selection| -> anyhow::Result<_> {
+                    let new: Rope = transformation
+                        .apply(
+                            self.buffer()
+                                .slice(&selection.extended_range())?
+                                .to_string(),
+                            indent_width,
+                        )
+                        .into();
+                    let new_char_count = new.chars().count();
+                    let range = selection.extended_range();
+                    Ok(ActionGroup::new(
+                        [
+                            Action::Edit(Edit { range, new }),
+                            Action::Select(
+                                selection
+                                    .clone()
+                                    .set_range((range.start..range.start + new_char_count).into()),
+                            ),
+                        ]
+                        .to_vec(),
+                    ))
+                })
+                .into_iter()
+                .flatten()
+                .collect_vec(),
+        );
+        self.apply_edit_transaction(edit_transaction)
+    }
+
+    /// Nothing is replaced if any of the selections cannot be evaluated,
+    /// instead the error is shown.
+    fn evaluate_selection(&mut self) -> anyhow::Result<Dispatches> {
+        let results = self
+            .selection_set
+            .map(|selection| -> anyhow::Result<_> {
+                let range = selection.extended_range();
+                let text = self.buffer().slice(&range)?.to_string();
+                let value = crate::arithmetic::evaluate(&text)
+                    .map_err(|error| anyhow::anyhow!("{:?}: {}", text.trim(), error))?;
+                Ok((
+                    selection.clone(),
+                    Rope::from(crate::arithmetic::display(value)),
+                ))
+            })
+            .into_iter()
+            .collect::<anyhow::Result<Vec<_>>>();
+        let results = match results {
+            Ok(results) => results,
+            Err(error) => {
+                return Ok(Dispatches::one(Dispatch::ShowEditorInfo(Info::new(
+                    "Evaluate selection".to_string(),
+                    error.to_string(),
+                ))))
+            }
+        };
+        let edit_transaction = EditTransaction::from_action_groups(
+            results
+                .into_iter()
+                .map(|(selection, new)| {
+                    let range = selection.extended_range();
+                    let new_char_count = new.len_chars();
+                    ActionGroup::new(
+                        [
+                            Action::Edit(Edit { range, new }),
+                            Action::Select(
+                                selection
+                                    .set_range((range.start..range.start + new_char_count).into()),
+                            ),
+                        ]
+                        .to_vec(),
+                    )
+                })
+                .collect_vec(),
+        );
+        self.apply_edit_transaction(edit_transaction)
+    }
+
+    fn select_invisible_characters(&mut self, context: &Context) -> anyhow::Result<Dispatches> {
+        let replacements = context.character_replacements();
+        if replacements.is_empty() {
+            return Ok(Dispatches::one(Dispatch::ShowEditorInfo(Info::new(
+                "Select invisible characters".to_string(),
+                "No character replacements are configured".to_string(),
+            ))));
+        }
+        self.set_selection_mode(SelectionMode::Find {
+            search: Search {
+                search: format!(
+                    "[{}]",
+                    replacements
+                        .iter()
+                        .map(|replacement| regex::escape(&replacement.char.to_string()))
+                        .join("")
+                ),
+                mode: LocalSearchConfigMode::Regex(crate::list::grep::RegexConfig {
+                    escaped: false,
+                    case_sensitive: true,
+                    match_whole_word: false,
+                    multiline: false,
+                }),
+            },
+        })
+    }
+
+    fn show_char_info(&self) -> Dispatches {
+        let char = self
+            .buffer()
+            .rope()
+            .get_char(self.get_cursor_char_index().0);
+        Dispatches::one(Dispatch::ShowEditorInfo(Info::new(
+            "Character info".to_string(),
+            char.map(crate::char_info::describe)
+                .unwrap_or_else(|| "No character under the cursor".to_string()),
+        )))
+    }
+
+    /// Sort the texts of the selections, then write them back into the selections in document order,
+    /// so that unlike sorting lines, the sorted items can be any regions, such as syntax nodes.
+    fn sort_selections_by_content(&mut self) -> anyhow::Result<Dispatches> {
+        let selections = self
+            .selection_set
+            .map(|selection| -> anyhow::Result<_> {
+                let text = self.buffer().slice(&selection.extended_range())?;
+                Ok((selection.clone(), text))
+            })
+            .into_iter()
+            .collect::<anyhow::Result<Vec<_>>>()?
+            .into_iter()
+            .sorted_by_key(|(selection, _)| selection.extended_range().start)
+            .collect_vec();
+        let sorted_texts = selections
+            .iter()
+            .map(|(_, text)| text.clone())
+            .sorted()
+            .collect_vec();
+        let edit_transaction = EditTransaction::from_action_groups(
+            selections
+                .into_iter()
+                .zip(sorted_texts)
+                .map(|((selection, _), new)| {
+                    let range = selection.extended_range();
+                    let new_char_count = new.len_chars();
+                    ActionGroup::new(
+                        [
+                            Action::Edit(Edit { range, new }),
+                            Action::Select(
+                                selection
+                                    .set_range((range.start..range.start + new_char_count).into()),
+                            ),
+                        ]
+                        .to_vec(),
+                    )
+                })
+                .collect_vec(),
+        );
+        self.apply_edit_transaction(edit_transaction)
+    }
+
+    /// Replace the quickfix list with the selections, so that they can be navigated
+    /// after the cursors are collapsed.
+    fn selection_to_quickfix(&self) -> anyhow::Result<Dispatches> {
+        use crate::quickfix_list::{Location, QuickfixListItem, QuickfixListType};
+        let buffer = self.buffer();
+        let path = buffer
+            .path()
+            .ok_or_else(|| anyhow::anyhow!("The buffer of the selections has no path"))?;
+        let items = self
+            .selection_set
+            .map(|selection| -> anyhow::Result<_> {
+                let range = selection.extended_range();
+                Ok(QuickfixListItem::new(
+                    Location {
+                        path: path.clone(),
+                        range: buffer.char_index_range_to_position_range(range)?,
+                    },
+                    Some(Info::new(
+                        "Selection".to_string(),
+                        buffer.slice(&range)?.to_string(),
+                    )),
+                ))
+            })
+            .into_iter()
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        Ok(Dispatches::one(Dispatch::SetQuickfixList(
+            QuickfixListType::Items(items),
+        )))
+    }
+
+    fn sum_selections(&self) -> Dispatches {
+        let (numbers, skipped): (Vec<_>, Vec<_>) = self
+            .get_selected_texts()
+            .into_iter()
+            .map(|text| {
+                text.trim()
+                    .replace('_', "")
+                    .parse::<f64>()
+                    .ok()
+                    // Words such as "inf" and "nan" are parsed as non-finite numbers
+                    .filter(|number| number.is_finite())
+                    .ok_or_else(|| format!("{:?}", text))
+            })
+            .partition_result();
+        let count = numbers.len();
+        let sum: f64 = numbers.into_iter().sum();
+        let display = crate::arithmetic::display;
+        let content = [
+            format!("Sum: {}", display(sum)),
+            format!(
+                "Average: {}",
+                if count == 0 {
+                    "-".to_string()
+                } else {
+                    display(sum / count as f64)
+                }
+            ),
+            format!("Count: {}", count),
+        ]
+        .into_iter()
+        .chain((!skipped.is_empty()).then(|| format!("Skipped: {}", skipped.join(", "))))
+        .join("\n");
+        Dispatches::one(Dispatch::ShowEditorInfo(Info::new(
+            "Sum selections".to_string(),
+            content,
+        )))
+    }
+
+    fn replace_with_register_from_history(&self, context: &Context) -> Dispatches {
+        Dispatches::one(Dispatch::OpenPrompt {
+            config: PromptConfig {
+                title: "Replace with (copied or searched text)".to_string(),
+                on_enter: DispatchPrompt::Null,
+                items: context
+                    .register_history()
+                    .into_iter()
+                    .map(|text| {
+                        DropdownItem::new(text.clone()).set_dispatches(Dispatches::one(
+                            Dispatch::ToEditor(ReplaceCurrentSelectionWith(text)),
+                        ))
+                    })
+                    .collect_vec(),
+                enter_selects_first_matching_item: true,
+                leaves_current_line_empty: true,
+                fire_dispatches_on_change: None,
+            },
+            key: PromptHistoryKey::RegisterHistory,
+            current_line: None,
+        })
+    }
+
+    pub(crate) fn display_mode(&self) -> String {
+        let selection_mode = self.selection_set.mode.display();
+        let filters = self
+            .selection_set
+            .filters
+            .display()
+            .map(|display| format!("({})", display))
+            .unwrap_or_default();
+        let mode = match &self.mode {
+            Mode::Normal => "MOVE",
+            Mode::Insert => "INSERT",
+            Mode::MultiCursor => "MULTI CURSOR",
+            Mode::FindOneChar => "FIND ONE CHAR",
+            Mode::Exchange => "EXCHANGE",
+            Mode::UndoTree => "UNDO TREE",
+            Mode::Replace => "REPLACE",
+        };
+        let cursor_count = self.selection_set.len();
+        let mode = format!("{}:{}{} x {}", mode, selection_mode, filters, cursor_count);
+        if self.jumps.is_some() {
+            format!("{} (SNEAK)", mode)
+        } else {
+            mode
+        }
+    }
+
+    pub(crate) fn visible_line_range(&self) -> Range<usize> {
+        let start = self.scroll_offset;
+        let end = (start as usize + self.rectangle.height as usize).min(self.buffer().len_lines());
+
+        start as usize..end
+    }
+
+    fn handle_multi_cursor_mode(
+        &mut self,
+        context: &Context,
+        key_event: KeyEvent,
+    ) -> Result<Dispatches, anyhow::Error> {
+        match key_event {
+            key!("esc") => {
+                self.mode = Mode::Normal;
+                Ok(Default::default())
+            }
+
+            other => self.handle_normal_mode(context, other),
+        }
     }
 
     pub(crate) fn add_cursor_to_all_selections(&mut self) -> Result<(), anyhow::Error> {
@@ -2072,6 +3720,134 @@ impl Editor {
         self.selection_set.only();
     }
 
+    fn cursor_collapse_to_first(&mut self) {
+        self.selection_set
+            .only_min_by_key(|selection| selection.extended_range().start);
+        self.recalculate_scroll_offset();
+    }
+
+    fn cursor_collapse_to_last(&mut self) {
+        self.selection_set
+            .only_min_by_key(|selection| std::cmp::Reverse(selection.extended_range().start));
+        self.recalculate_scroll_offset();
+    }
+
+    /// Select the nearest parent line (the lines shown as sticky headers) of each cursor.
+    ///
+    /// Unlike `MoveSelection(ToParentLine)`, this always switches to line selection,
+    /// so that repeated invocations keep climbing out of the nested blocks.
+    fn go_to_parent_line(&mut self) -> anyhow::Result<Dispatches> {
+        if let Some(selection_set) =
+            self.get_selection_set(&SelectionMode::LineTrimmed, Movement::ToParentLine)?
+        {
+            Ok(self.update_selection_set(selection_set, true))
+        } else {
+            Ok(Default::default())
+        }
+    }
+
+    /// Extend each selection up to its nearest parent line.
+    ///
+    /// The anchor stays at the original selection, while the cursor is placed on the parent line.
+    fn select_to_parent_line(&mut self) -> anyhow::Result<Dispatches> {
+        let selection_set = {
+            let buffer = self.buffer.borrow();
+            self.selection_set
+                .apply(SelectionMode::LineTrimmed, |selection| {
+                    Ok(
+                        match Selection::get_selection_(
+                            &buffer,
+                            selection,
+                            &SelectionMode::LineTrimmed,
+                            &Movement::ToParentLine,
+                            &self.cursor_direction,
+                            &self.selection_set.filters,
+                        )? {
+                            Some(result) => result
+                                .selection
+                                .set_initial_range(Some(selection.extended_range())),
+                            None => selection.clone(),
+                        },
+                    )
+                })?
+        };
+        // `update_selection_set` resets the cursor direction, which should be left as is
+        let cursor_direction = self.cursor_direction.clone();
+        let dispatches = self.update_selection_set(selection_set, true);
+        self.cursor_direction = cursor_direction;
+        self.recalculate_scroll_offset();
+        Ok(dispatches)
+    }
+
+    /// Turn the primary selection into a rectangular block,
+    /// where the anchor and the cursor are the opposite corners of the block.
+    ///
+    /// One selection is produced for each line, clipped to the column range of the block.
+    /// Lines that are shorter than the left edge of the block are skipped.
+    ///
+    /// Columns are display columns, like in [`column_to_char_offset`],
+    /// so that the block stays rectangular on screen across tabs and wide chars.
+    pub(crate) fn select_block(&mut self) -> anyhow::Result<Dispatches> {
+        let range = self.selection_set.primary_selection().extended_range();
+        let buffer = self.buffer.borrow();
+        let display_position = |char_index: CharIndex| -> anyhow::Result<Position> {
+            let position = buffer.char_to_position(char_index)?;
+            let line = buffer
+                .get_line_by_line_index(position.line)
+                .map(|line| line.to_string())
+                .unwrap_or_default();
+            Ok(Position::new(
+                position.line,
+                char_offset_to_column(&line, position.column),
+            ))
+        };
+        let start = display_position(range.start)?;
+        let end = display_position((range.end - 1).max(range.start))?;
+        let left = start.column.min(end.column);
+        let right = start.column.max(end.column);
+        let selections = (start.line..=end.line)
+            .filter_map(|line| {
+                let line_start = buffer.line_to_char(line).ok()?;
+                let line = buffer.get_line_by_line_index(line)?.to_string();
+                let line = line.trim_end_matches(['\n', '\r']);
+                let line_width = char_offset_to_column(line, line.chars().count());
+                (left < line_width).then(|| {
+                    Selection::new(
+                        (line_start + column_to_char_offset(line, left)
+                            ..line_start + column_to_char_offset(line, right) + 1)
+                            .into(),
+                    )
+                })
+            })
+            .collect_vec();
+        drop(buffer);
+        let Some(selections) = NonEmpty::from_vec(selections) else {
+            return Ok(Default::default());
+        };
+        let selection_set = SelectionSet::new(selections).set_mode(self.selection_set.mode.clone());
+        Ok(self.update_selection_set(selection_set, true))
+    }
+
+    fn cycle_primary_cursor(&mut self, backward: bool) {
+        self.selection_set.cycle_primary(backward);
+        self.recalculate_scroll_offset();
+    }
+
+    /// Keep only the cursor that is closest to the center line of the view.
+    fn cursor_collapse_to_nearest(&mut self) {
+        let center_line = self.scroll_offset as usize + (self.rectangle.height as usize / 2);
+        let buffer = self.buffer.borrow();
+        self.selection_set.only_min_by_key(|selection| {
+            buffer
+                .char_to_line(selection.to_char_index(&self.cursor_direction))
+                .map(|line| line.abs_diff(center_line))
+                .unwrap_or(usize::MAX)
+        });
+        drop(buffer);
+        // The nearest cursor might still be out of view if none of the cursors is in view
+        self.recalculate_scroll_offset();
+    }
+
     fn enter_single_character_mode(&mut self) {
         self.mode = Mode::FindOneChar;
     }
@@ -2090,6 +3866,7 @@ impl Editor {
                             escaped: true,
                             case_sensitive: true,
                             match_whole_word: false,
+                            multiline: false,
                         }),
                     },
                 })
@@ -2118,6 +3895,7 @@ impl Editor {
                     escaped: true,
                     case_sensitive: false,
                     match_whole_word: false,
+                    multiline: false,
                 }),
                 search: search.to_string(),
             },
@@ -2133,6 +3911,311 @@ impl Editor {
         .into())
     }
 
+    /// Move each selection to the first non-whitespace character of the line of its cursor,
+    /// then reselect it using the current selection mode.
+    ///
+    /// For a selection that spans multiple lines, the result is kept within the selection.
+    fn go_to_first_non_whitespace(&mut self) -> anyhow::Result<Dispatches> {
+        let selection_set = {
+            let buffer = self.buffer.borrow();
+            self.selection_set
+                .apply(self.selection_set.mode.clone(), |selection| {
+                    let range = selection.extended_range();
+                    let cursor = selection.to_char_index(&self.cursor_direction);
+                    let line = buffer.char_to_line(cursor)?;
+                    let line_start = buffer.line_to_char(line)?;
+                    let indentation = buffer
+                        .get_line_by_line_index(line)
+                        .map(|line| {
+                            line.chars()
+                                .take_while(|c| c.is_whitespace() && *c != '\n')
+                                .count()
+                        })
+                        .unwrap_or_default();
+                    let target = line_start + indentation;
+                    let is_multiline =
+                        buffer.char_to_line(range.start)? != buffer.char_to_line(range.end - 1)?;
+                    let target = if is_multiline {
+                        target.max(range.start).min(range.end - 1)
+                    } else {
+                        target
+                    };
+                    let end = (target + 1).min(CharIndex(buffer.len_chars()));
+                    Ok(selection
+                        .clone()
+                        .set_range((target..end).into())
+                        .set_initial_range(None))
+                })?
+        };
+        // Reselect using the current selection mode, like `move_selection_to_column`
+        Ok(self.update_selection_set(selection_set, true).append_some(
+            if self.selection_set.mode.is_contiguous() {
+                Some(Dispatch::ToEditor(MoveSelection(Movement::Current)))
+            } else {
+                None
+            },
+        ))
+    }
+
+    /// Move each selection to the given 0-based display `column` of its own line,
+    /// then reselect it using the current selection mode.
+    fn move_selection_to_column(&mut self, column: usize) -> anyhow::Result<Dispatches> {
+        self.move_selection_within_line(|line| column_to_char_offset(line, column))
+    }
+
+    /// Useful for navigating very long lines, such as those of CSV files or logs.
+    fn jump_to_percent_of_line(&mut self, percent: usize) -> anyhow::Result<Dispatches> {
+        self.move_selection_within_line(|line| {
+            let length = line.trim_end_matches(['\r', '\n']).chars().count();
+            (length * percent.min(100) / 100).min(length.saturating_sub(1))
+        })
+    }
+
+    /// Move each selection to the char offset returned by `get_offset`,
+    /// which is given the content of the line of the cursor of the selection.
+    fn move_selection_within_line(
+        &mut self,
+        get_offset: impl Fn(&str) -> usize,
+    ) -> anyhow::Result<Dispatches> {
+        let selection_set = {
+            let buffer = self.buffer.borrow();
+            self.selection_set
+                .apply(self.selection_set.mode.clone(), |selection| {
+                    let cursor = selection.to_char_index(&self.cursor_direction);
+                    let line = buffer.char_to_line(cursor)?;
+                    let line_start = buffer.line_to_char(line)?;
+                    let offset = buffer
+                        .get_line_by_line_index(line)
+                        .map(|line| get_offset(&line.to_string()))
+                        .unwrap_or_default();
+                    let target = line_start + offset;
+                    let end = (target + 1).min(CharIndex(buffer.len_chars()));
+                    Ok(selection
+                        .clone()
+                        .set_range((target..end).into())
+                        .set_initial_range(None))
+                })?
+        };
+        Ok(self.update_selection_set(selection_set, true).append_some(
+            if self.selection_set.mode.is_contiguous() {
+                Some(Dispatch::ToEditor(MoveSelection(Movement::Current)))
+            } else {
+                None
+            },
+        ))
+    }
+
+    fn go_to_byte_offset(&mut self, byte_offset: usize) -> anyhow::Result<Dispatches> {
+        self.cursor_keep_primary_only();
+        let selection_set = {
+            let buffer = self.buffer.borrow();
+            let start = buffer.byte_to_char(byte_offset.min(buffer.rope().len_bytes()))?;
+            let end = (start + 1).min(CharIndex(buffer.len_chars()));
+            self.selection_set
+                .apply(self.selection_set.mode.clone(), |selection| {
+                    Ok(selection
+                        .clone()
+                        .set_range((start..end).into())
+                        .set_initial_range(None))
+                })?
+        };
+        Ok(self.update_selection_set(selection_set, true).append_some(
+            if self.selection_set.mode.is_contiguous() {
+                Some(Dispatch::ToEditor(MoveSelection(Movement::Current)))
+            } else {
+                None
+            },
+        ))
+    }
+
+    /// Select the smallest of the `selection_ranges` that encloses the primary selection.
+    ///
+    /// If there is none, the ranges are requested from the language server,
+    /// or computed from the tree-sitter nodes, see [`Editor::set_selection_ranges`].
+    fn expand_selection(&mut self) -> anyhow::Result<Dispatches> {
+        self.forget_stale_selection_ranges();
+        Ok(self
+            .select_selection_range(true)?
+            .unwrap_or_else(|| Dispatches::one(Dispatch::RequestSelectionRange)))
+    }
+
+    /// Select the largest of the `selection_ranges` that is within the primary selection.
+    fn shrink_selection(&mut self) -> anyhow::Result<Dispatches> {
+        self.forget_stale_selection_ranges();
+        Ok(self.select_selection_range(false)?.unwrap_or_default())
+    }
+
+    /// Clear the `selection_ranges` if the primary selection has moved away from them,
+    /// i.e. it is neither one of them nor within the innermost of them.
+    fn forget_stale_selection_ranges(&mut self) {
+        let current = self.selection_set.primary_selection().extended_range();
+        let is_stale = self.selection_ranges.first().is_some_and(|innermost| {
+            !(innermost.start <= current.start && current.end <= innermost.end)
+                && !self.selection_ranges.contains(&current)
+        });
+        if is_stale {
+            self.selection_ranges.clear()
+        }
+    }
+
+    /// Returns `None` if none of the `selection_ranges` is larger (`expand`),
+    /// or smaller, than the primary selection.
+    fn select_selection_range(&mut self, expand: bool) -> anyhow::Result<Option<Dispatches>> {
+        let current = self.selection_set.primary_selection().extended_range();
+        let encloses = |outer: &CharIndexRange, inner: &CharIndexRange| {
+            outer != inner && outer.start <= inner.start && inner.end <= outer.end
+        };
+        let range = if expand {
+            self.selection_ranges
+                .iter()
+                .find(|range| encloses(range, &current))
+        } else {
+            self.selection_ranges
+                .iter()
+                .rev()
+                .find(|range| encloses(&current, range))
+        };
+        let Some(range) = range.copied() else {
+            return Ok(None);
+        };
+        self.cursor_keep_primary_only();
+        let selection_set = self
+            .selection_set
+            .apply(SelectionMode::Custom, |selection| {
+                Ok(selection.clone().set_range(range).set_initial_range(None))
+            })?;
+        Ok(Some(self.update_selection_set(selection_set, true)))
+    }
+
+    /// Select the next region that differs from the saved content of the file.
+    ///
+    /// Unlike the git hunk selection mode, this compares against the saved file instead of the
+    /// last commit, and it wraps around to the first region after the last one.
+    fn select_next_modified_region(&mut self) -> anyhow::Result<Dispatches> {
+        let ranges = {
+            let buffer = self.buffer();
+            let Some(path) = buffer.path() else {
+                return Ok(Default::default());
+            };
+            // A file that is not saved yet is entirely modified
+            let saved_content = match std::fs::read(&path) {
+                Ok(bytes) => Encoding::decode(&bytes)?.1,
+                Err(error) if error.kind() == std::io::ErrorKind::NotFound => String::new(),
+                Err(error) => return Err(error.into()),
+            };
+            Hunk::get(&saved_content, &buffer.content())
+                .iter()
+                .map(|hunk| {
+                    let line_range = hunk.line_range();
+                    // The range of deleted lines is empty, so the line after them is selected
+                    let end = line_range
+                        .end
+                        .max(line_range.start + 1)
+                        .min(buffer.len_lines());
+                    buffer.line_range_to_char_index_range(line_range.start..end)
+                })
+                .collect::<anyhow::Result<Vec<_>>>()?
+        };
+        let current = self.selection_set.primary_selection().extended_range();
+        let Some(range) = ranges
+            .iter()
+            .find(|range| range.start > current.start)
+            .or_else(|| ranges.first())
+            .copied()
+        else {
+            return Ok(Default::default());
+        };
+        self.cursor_keep_primary_only();
+        let selection_set = self
+            .selection_set
+            .apply(SelectionMode::Custom, |selection| {
+                Ok(selection.clone().set_range(range).set_initial_range(None))
+            })?;
+        let dispatches = self.update_selection_set(selection_set, true);
+        self.align_cursor_to_center();
+        Ok(dispatches)
+    }
+
+    /// Replace the `selection_ranges` with `ranges`, which are usually from the
+    /// `textDocument/selectionRange` of the language server, then expand the selection.
+    ///
+    /// The ranges of the tree-sitter nodes enclosing the primary selection are used instead
+    /// if none of `ranges` encloses the primary selection.
+    pub(crate) fn set_selection_ranges(
+        &mut self,
+        ranges: Vec<Range<Position>>,
+    ) -> anyhow::Result<Dispatches> {
+        self.selection_ranges = {
+            let buffer = self.buffer();
+            ranges
+                .iter()
+                .map(|range| buffer.position_range_to_char_index_range(range))
+                .collect::<anyhow::Result<Vec<_>>>()?
+        };
+        if let Some(dispatches) = self.select_selection_range(true)? {
+            return Ok(dispatches);
+        }
+        self.selection_ranges = self.syntax_node_ranges()?;
+        Ok(self.select_selection_range(true)?.unwrap_or_default())
+    }
+
+    /// The ranges of the tree-sitter nodes enclosing the primary selection, from the innermost.
+    fn syntax_node_ranges(&self) -> anyhow::Result<Vec<CharIndexRange>> {
+        let buffer = self.buffer();
+        let Some(tree) = buffer.tree() else {
+            return Ok(Vec::new());
+        };
+        let range = self.selection_set.primary_selection().extended_range();
+        let mut node = tree.root_node().descendant_for_byte_range(
+            buffer.char_to_byte(range.start)?,
+            buffer.char_to_byte(range.end)?,
+        );
+        let mut ranges = Vec::new();
+        while let Some(current) = node {
+            ranges.push(buffer.byte_range_to_char_index_range(&current.byte_range())?);
+            node = current.parent();
+        }
+        Ok(ranges)
+    }
+
+    /// Lines with only whitespaces are blank.
+    /// The first or the last line is the boundary if there is no more blank line.
+    fn go_to_paragraph_boundary(&mut self, direction: Direction) -> anyhow::Result<Dispatches> {
+        let selection_set = {
+            let buffer = self.buffer.borrow();
+            let is_blank = |line: &usize| {
+                buffer
+                    .get_line_by_line_index(*line)
+                    .map(|line| line.chars().all(char::is_whitespace))
+                    .unwrap_or(true)
+            };
+            let last_line = buffer.len_lines().saturating_sub(1);
+            self.selection_set
+                .apply(self.selection_set.mode.clone(), |selection| {
+                    let line =
+                        buffer.char_to_line(selection.to_char_index(&self.cursor_direction))?;
+                    // The boundary is the first blank line whose neighbour toward the cursor is not blank,
+                    // so that consecutive blank lines are treated as one boundary
+                    let target = match direction {
+                        Direction::End => (line + 1..=last_line)
+                            .find(|line| is_blank(line) && !is_blank(&(line - 1)))
+                            .unwrap_or(last_line),
+                        Direction::Start => (0..line)
+                            .rev()
+                            .find(|line| is_blank(line) && !is_blank(&(line + 1)))
+                            .unwrap_or(0),
+                    };
+                    let start = buffer.line_to_char(target)?;
+                    let end = (start + 1).min(CharIndex(buffer.len_chars()));
+                    Ok(selection
+                        .clone()
+                        .set_range((start..end).into())
+                        .set_initial_range(None))
+                })?
+        };
+        Ok(self.update_selection_set(selection_set, true))
+    }
+
     pub(crate) fn move_to_line_end(&mut self) -> anyhow::Result<Dispatches> {
         Ok([
             Dispatch::ToEditor(SelectLine(Movement::Current)),
@@ -2163,12 +4246,12 @@ impl Editor {
         Ok(dispatches)
     }
 
-    pub(crate) fn scroll_page_down(&mut self) -> Result<Dispatches, anyhow::Error> {
-        self.scroll(Direction::End, self.half_page_height())
+    pub(crate) fn scroll_page_down(&mut self, smooth: bool) -> Result<Dispatches, anyhow::Error> {
+        self.scroll(Direction::End, self.half_page_height(), smooth)
     }
 
-    pub(crate) fn scroll_page_up(&mut self) -> Result<Dispatches, anyhow::Error> {
-        self.scroll(Direction::Start, self.half_page_height())
+    pub(crate) fn scroll_page_up(&mut self, smooth: bool) -> Result<Dispatches, anyhow::Error> {
+        self.scroll(Direction::Start, self.half_page_height(), smooth)
     }
 
     #[cfg(test)]
@@ -2195,6 +4278,7 @@ impl Editor {
 
     fn navigate_undo_tree(&mut self, movement: Movement) -> Result<Dispatches, anyhow::Error> {
         let selection_set = self.buffer_mut().undo_tree_apply_movement(movement)?;
+        self.yank_flash = None;
 
         Ok(selection_set
             .map(|selection_set| self.update_selection_set(selection_set, false))
@@ -2232,7 +4316,7 @@ impl Editor {
         let mut buffer = self.buffer_mut();
         if let Some(language) = buffer.language() {
             let highlighted_spans = context.highlight(language, &source_code)?;
-            buffer.update_highlighted_spans(highlighted_spans);
+            buffer.update_highlighted_spans(0..source_code.len(), highlighted_spans);
         }
         Ok(())
     }
@@ -2255,6 +4339,25 @@ impl Editor {
         Ok(result.into())
     }
 
+    /// Confine the selections to the range of the primary selection,
+    /// and select the first match of the last search within it.
+    fn select_next_occurrence_in_selection(
+        &mut self,
+        context: &Context,
+    ) -> anyhow::Result<Dispatches> {
+        let bound = self.selection_set.primary_selection().extended_range();
+        let selection_set = self.selection_set.clone().set_bound(bound);
+        let dispatches = self.update_selection_set(selection_set, true);
+        let mode = context
+            .get_local_search_config(Scope::Local)
+            .last_search()
+            .map(|search| SelectionMode::Find { search })
+            .unwrap_or_else(|| self.selection_set.mode.clone());
+        Ok(dispatches.chain(
+            self.move_selection_with_selection_mode_without_global_mode(Movement::First, mode)?,
+        ))
+    }
+
     fn filters_clear(&mut self) -> Dispatches {
         let selection_set = self.selection_set.clone().filter_clear();
         self.update_selection_set(selection_set, true)
@@ -2367,36 +4470,84 @@ impl Editor {
     ) -> anyhow::Result<Dispatches> {
         let edit_transaction = EditTransaction::from_action_groups(
             self.selection_set
-                .map(|selection| -> anyhow::Result<_> {
-                    let buffer = self.buffer();
-                    let cursor_char_index = selection.get_anchor(&self.cursor_direction);
-                    if let Some((open_index, close_index)) =
-                        crate::surround::get_surrounding_indices(
-                            &buffer.content(),
-                            enclosure,
-                            cursor_char_index,
-                        )
-                    {
-                        let offset = match kind {
-                            SurroundKind::Inside => 1,
-                            SurroundKind::Around => 0,
-                        };
-                        let range = ((open_index + offset)..(close_index + 1 - offset)).into();
-                        Ok(ActionGroup::new(
+                .map(|selection| {
+                    if let Some(range) = self.get_surround_range(selection, enclosure, &kind) {
+                        ActionGroup::new(
                             [Action::Select(selection.clone().set_range(range))].to_vec(),
-                        ))
+                        )
                     } else {
-                        Ok(ActionGroup::new(Default::default()))
+                        ActionGroup::new(Default::default())
                     }
                 })
                 .into_iter()
-                .flatten()
                 .collect_vec(),
         );
         let _ = self.set_selection_mode(SelectionMode::Custom);
         self.apply_edit_transaction(edit_transaction)
     }
 
+    /// Returns the range inside or around the nearest `enclosure` that surrounds the anchor of the given `selection`.
+    fn get_surround_range(
+        &self,
+        selection: &Selection,
+        enclosure: EnclosureKind,
+        kind: &SurroundKind,
+    ) -> Option<CharIndexRange> {
+        let cursor_char_index = selection.get_anchor(&self.cursor_direction);
+        let (open_index, close_index) = crate::surround::get_surrounding_indices(
+            &self.buffer().content(),
+            enclosure,
+            cursor_char_index,
+        )?;
+        let offset = match kind {
+            SurroundKind::Inside => 1,
+            SurroundKind::Around => 0,
+        };
+        Some(((open_index + offset)..(close_index + 1 - offset)).into())
+    }
+
+    /// Delete the content inside (or around) the nearest `enclosure` of each selection,
+    /// leaving the cursor where the deleted content used to be.
+    fn delete_to_matching(
+        &mut self,
+        enclosure: EnclosureKind,
+        kind: SurroundKind,
+    ) -> anyhow::Result<Dispatches> {
+        let edit_transaction = EditTransaction::from_action_groups(
+            self.selection_set
+                .map(|selection| {
+                    if let Some(range) = self.get_surround_range(selection, enclosure, &kind) {
+                        ActionGroup::new(
+                            [
+                                Action::Edit(Edit {
+                                    range,
+                                    new: Rope::new(),
+                                }),
+                                Action::Select(
+                                    selection
+                                        .clone()
+                                        .set_range((range.start..range.start).into()),
+                                ),
+                            ]
+                            .to_vec(),
+                        )
+                    } else {
+                        ActionGroup::new(Default::default())
+                    }
+                })
+                .into_iter()
+                .collect_vec(),
+        );
+        self.apply_edit_transaction(edit_transaction)
+    }
+
+    /// Delete the content inside the nearest `enclosure`, and start inserting there.
+    fn replace_inside_pair(&mut self, enclosure: EnclosureKind) -> anyhow::Result<Dispatches> {
+        Ok(self
+            .delete_to_matching(enclosure, SurroundKind::Inside)?
+            .chain(self.enter_insert_mode(Direction::Start)?))
+    }
+
     fn delete_surround(&mut self, enclosure: EnclosureKind) -> Result<Dispatches, anyhow::Error> {
         self.change_surround(enclosure, None)
     }
@@ -2588,6 +4739,99 @@ pub(crate) enum HandleEventResult {
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum DispatchEditor {
     Surround(String, String),
+    /// Wrap each selection with an opening and closing tag of the given name,
+    /// then place the cursor before the `>` of the opening tag.
+    WrapSelectionInTag(String),
+    /// Replace each selection with the stdout of the given shell command,
+    /// which receives the selected text through its stdin.
+    PipeSelection(String),
+    /// Select every line that matches the given regex, like Vim's `:g/pattern/`.
+    SelectLinesMatching(String),
+    /// Select every line that does not match the given regex, like Vim's `:v/pattern/`.
+    SelectLinesNotMatching(String),
+    /// Select every node captured by the given tree-sitter query,
+    /// for example `(function_item name: (identifier) @name)`.
+    SelectByQuery(String),
+    /// Select the import statements at the top of the file.
+    NavigateToImportBlock,
+    /// Select the body of the function enclosing each cursor, excluding its signature.
+    SelectFunctionBody,
+    /// Select the occurrences of the word under the cursor within the enclosing function,
+    /// which is a local rename that works without a language server.
+    SelectWordUnderCursorOccurrences,
+    /// Show the codepoint, name, UTF-8 bytes and display width of the character under the cursor.
+    ShowCharInfo,
+    /// Insert the current date/time formatted with [`Context::date_time_format`] at each cursor.
+    InsertDateTime,
+    /// Insert a distinct random UUID (version 4) at each cursor.
+    GenerateUuid {
+        uppercase: bool,
+    },
+    /// Enter `Find` selection mode with the characters of [`Context::character_replacements`],
+    /// which are usually invisible or look-alike characters.
+    SelectInvisibleCharacters,
+    /// Replace the characters of [`Context::character_replacements`] within the selections.
+    ReplaceInvisibleCharacters,
+    /// Enter `Find` selection mode with the literal content of the clipboard, like `*` but on the clipboard.
+    RepeatSelectionModeSearchFromClipboard {
+        whole_word: bool,
+        use_system_clipboard: bool,
+    },
+    /// Select the name of the matching opening/closing tag of the markup element enclosing each cursor.
+    GoToMatchingTag,
+    /// Move each selection to the given 0-based display column of its own line.
+    MoveSelectionToColumn(usize),
+    /// Move each selection to the given percentage across the length of its own line.
+    JumpToPercentOfLine(usize),
+    /// Move the primary selection to the character containing the given 0-based byte offset,
+    /// which is clamped to the length of the buffer, and remove the other selections.
+    GoToByteOffset(usize),
+    /// Move each selection to the next (`End`) or previous (`Start`) blank line
+    /// that follows a non-blank line, like Vim's `}` and `{`.
+    GoToParagraphBoundary(Direction),
+    /// Select the smallest enclosing range of the primary selection, see [`Editor::expand_selection`].
+    ExpandSelection,
+    /// Select the previous range before the last [`DispatchEditor::ExpandSelection`].
+    ShrinkSelection,
+    /// Select the lines of the next hunk that differs from the saved content of the file.
+    SelectNextModifiedRegion,
+    /// Convert the line endings of the buffer between LF and CRLF.
+    ToggleLineEnding,
+    /// Replace each selection with the result of evaluating it as an arithmetic expression,
+    /// see [`crate::arithmetic`].
+    EvaluateSelection,
+    /// Show the sum, average and count of the selections that are numbers.
+    SumSelections,
+    /// Sort the texts of the selections among the selections, see [`Editor::sort_selections_by_content`].
+    SortSelectionsByContent,
+    /// Replace the quickfix list with the selections, using the selected texts as the infos.
+    SelectionToQuickfix,
+    /// Pick a previously copied or searched text from a prompt,
+    /// then replace the current selection with it.
+    ReplaceWithRegisterFromHistory,
+    /// Save the buffer in the given encoding.
+    ConvertEncoding(Encoding),
+    /// Sort the statements of the import block, see [`Buffer::get_edit_transaction_of_sort_imports`].
+    SortImports,
+    /// Comment the lines of the selections, or uncomment them if all of them are commented.
+    ToggleLineComment,
+    /// Select the string literal enclosing each cursor.
+    SelectEnclosingString {
+        include_delimiters: bool,
+    },
+    /// Select the comment enclosing each cursor.
+    SelectEnclosingComment {
+        include_delimiters: bool,
+    },
+    /// Insert the stdout of the given shell command at the cursor of each selection,
+    /// without replacing the selections.
+    InsertShellOutput(String),
+    /// Toggle the case of the character under each cursor, then move to the next character.
+    ToggleCaseOfChar,
+    /// Expand the user snippet whose prefix is before each cursor.
+    /// Without such snippet, go to the next tabstop, or insert a tab if there is none.
+    ExpandSnippet,
+    GoToNextTabstop,
     #[cfg(test)]
     SetScrollOffset(u16),
     ShowJumps {
@@ -2608,6 +4852,12 @@ pub(crate) enum DispatchEditor {
     Copy {
         use_system_clipboard: bool,
     },
+    /// Copy each selection wrapped in a fenced markdown code block tagged with the language of the buffer.
+    /// If `with_location` is true, the code block is preceded by the file path and the line range.
+    SelectionToClipboardAsMarkdownCodeBlock {
+        with_location: bool,
+        use_system_clipboard: bool,
+    },
     GoBack,
     GoForward,
     SelectAll,
@@ -2634,6 +4884,8 @@ pub(crate) enum DispatchEditor {
     },
     Insert(String),
     MoveToLineStart,
+    /// Select the first non-whitespace character of the current line, like Vim's `^`.
+    GoToFirstNonWhitespace,
     MoveToLineEnd,
     #[cfg(test)]
     MatchLiteral(String),
@@ -2643,14 +4895,28 @@ pub(crate) enum DispatchEditor {
     },
     Open(Direction),
     ToggleBookmark,
+    /// Extend each selection to the nearest bookmark after (`End`) or before (`Start`) its cursor.
+    SelectToBookmark(Direction),
     EnterNormalMode,
     EnterExchangeMode,
     EnterReplaceMode,
     EnterMultiCursorMode,
     FilterPush(Filter),
     FilterClear,
+    /// Confine `Next`/`Previous` to the range of the current selection,
+    /// starting from the first match of the last search within it.
+    SelectNextOccurrenceInSelection,
     CursorAddToAllSelections,
     CursorKeepPrimaryOnly,
+    CursorCollapseToFirst,
+    CursorCollapseToLast,
+    CursorCollapseToNearest,
+    CyclePrimaryCursor {
+        backward: bool,
+    },
+    SelectBlock,
+    GoToParentLine,
+    SelectToParentLine,
     ReplacePattern {
         config: crate::context::LocalSearchConfig,
     },
@@ -2676,10 +4942,18 @@ pub(crate) enum DispatchEditor {
         use_system_clipboard: bool,
     },
     SwapCursorWithAnchor,
+    /// Place the cursor of every selection at the given end,
+    /// unlike `SwapCursorWithAnchor`, which flips it.
+    AlignCursorsToAnchor(Direction),
     MoveCharacterBack,
     MoveCharacterForward,
     ShowKeymapLegendHelp,
     DeleteSurround(EnclosureKind),
+    DeleteToMatching {
+        enclosure: EnclosureKind,
+        kind: SurroundKind,
+    },
+    ReplaceInsidePair(EnclosureKind),
     ChangeSurround {
         from: EnclosureKind,
         to: EnclosureKind,
@@ -9,10 +9,12 @@ use crate::{
     app::{Dispatch, Dispatches, FilePickerKind, MakeFilterMechanism, Scope},
     components::{editor::Movement, keymap_legend::KeymapLegendSection},
     context::{Context, LocalSearchConfigMode, Search},
+    encoding::Encoding,
     git::DiffMode,
     list::grep::RegexConfig,
     quickfix_list::{DiagnosticSeverityRange, QuickfixListType},
     selection::{FilterKind, FilterTarget, SelectionMode},
+    selection_mode::AttentionComposition,
     surround::EnclosureKind,
     transformation::Transformation,
 };
@@ -68,11 +70,36 @@ impl Editor {
                     "Parent Line".to_string(),
                     Dispatch::ToEditor(MoveSelection(ToParentLine)),
                 ),
+                Keymap::new(
+                    "_",
+                    "Go to Parent Line".to_string(),
+                    Dispatch::ToEditor(GoToParentLine),
+                ),
+                Keymap::new(
+                    "+",
+                    "Select to Parent Line".to_string(),
+                    Dispatch::ToEditor(SelectToParentLine),
+                ),
                 Keymap::new(
                     "0",
                     "To Index (1-based)".to_string(),
                     Dispatch::OpenMoveToIndexPrompt,
                 ),
+                Keymap::new(
+                    "|",
+                    "To Column (1-based)".to_string(),
+                    Dispatch::OpenMoveToColumnPrompt,
+                ),
+                Keymap::new(
+                    "#",
+                    "To Percent of Line".to_string(),
+                    Dispatch::OpenJumpToPercentOfLinePrompt,
+                ),
+                Keymap::new(
+                    "@",
+                    "To Byte Offset (0-based)".to_string(),
+                    Dispatch::OpenGoToByteOffsetPrompt,
+                ),
             ]),
         }
     }
@@ -86,6 +113,56 @@ impl Editor {
                     "Swap cursor with anchor".to_string(),
                     Dispatch::ToEditor(DispatchEditor::SwapCursorWithAnchor),
                 ),
+                Keymap::new(
+                    "alt+h",
+                    "Align cursors to start of selections".to_string(),
+                    Dispatch::ToEditor(AlignCursorsToAnchor(Direction::Start)),
+                ),
+                Keymap::new(
+                    "alt+l",
+                    "Align cursors to end of selections".to_string(),
+                    Dispatch::ToEditor(AlignCursorsToAnchor(Direction::End)),
+                ),
+                Keymap::new(
+                    "alt+j",
+                    "Go to next paragraph boundary".to_string(),
+                    Dispatch::ToEditor(GoToParagraphBoundary(Direction::End)),
+                ),
+                Keymap::new(
+                    "alt+k",
+                    "Go to previous paragraph boundary".to_string(),
+                    Dispatch::ToEditor(GoToParagraphBoundary(Direction::Start)),
+                ),
+                Keymap::new(
+                    "alt+o",
+                    "Expand selection".to_string(),
+                    Dispatch::ToEditor(ExpandSelection),
+                ),
+                Keymap::new(
+                    "alt+i",
+                    "Shrink selection".to_string(),
+                    Dispatch::ToEditor(ShrinkSelection),
+                ),
+                Keymap::new(
+                    "alt+m",
+                    "Select next modified region (against saved file)".to_string(),
+                    Dispatch::ToEditor(SelectNextModifiedRegion),
+                ),
+                Keymap::new(
+                    "ctrl+a",
+                    "Go to first non-whitespace character of line".to_string(),
+                    Dispatch::ToEditor(GoToFirstNonWhitespace),
+                ),
+                Keymap::new(
+                    "ctrl+g",
+                    "Go to import block".to_string(),
+                    Dispatch::ToEditor(NavigateToImportBlock),
+                ),
+                Keymap::new(
+                    "ctrl+t",
+                    "Go to matching tag".to_string(),
+                    Dispatch::ToEditor(GoToMatchingTag),
+                ),
                 Keymap::new(
                     "ctrl+d",
                     "Scroll page down".to_string(),
@@ -213,6 +290,27 @@ impl Editor {
                     "Mark (Toggle)".to_string(),
                     Dispatch::ToEditor(ToggleBookmark),
                 ),
+                Keymap::new(
+                    "M",
+                    "Select to mark".to_string(),
+                    Dispatch::ShowKeymapLegend(KeymapLegendConfig {
+                        title: "Select to mark".to_string(),
+                        body: KeymapLegendBody::SingleSection {
+                            keymaps: Keymaps::new(&[
+                                Keymap::new(
+                                    "n",
+                                    "Next mark".to_string(),
+                                    Dispatch::ToEditor(SelectToBookmark(Direction::End)),
+                                ),
+                                Keymap::new(
+                                    "p",
+                                    "Previous mark".to_string(),
+                                    Dispatch::ToEditor(SelectToBookmark(Direction::Start)),
+                                ),
+                            ]),
+                        },
+                    }),
+                ),
                 Keymap::new(
                     "o",
                     "Open (after selection)".to_string(),
@@ -238,6 +336,11 @@ impl Editor {
                     "Replace (with next copied text)".to_string(),
                     Dispatch::ToEditor(ReplaceWithNextCopiedText),
                 ),
+                Keymap::new(
+                    "ctrl+x",
+                    "Replace (with copied or searched text from history)".to_string(),
+                    Dispatch::ToEditor(ReplaceWithRegisterFromHistory),
+                ),
                 Keymap::new(
                     "v",
                     "Toggle Visual Mode".to_string(),
@@ -249,6 +352,11 @@ impl Editor {
                     "Transform".to_string(),
                     Dispatch::ShowKeymapLegend(self.transform_keymap_legend_config()),
                 ),
+                Keymap::new(
+                    "~",
+                    "Toggle case of character".to_string(),
+                    Dispatch::ToEditor(ToggleCaseOfChar),
+                ),
             ]),
         }
     }
@@ -416,8 +524,8 @@ impl Editor {
                             ),
                             Keymap::new(
                                 "tab",
-                                "Enter tab".to_string(),
-                                Dispatch::ToEditor(Insert("\t".to_string())),
+                                "Expand snippet / Next tabstop / Enter tab".to_string(),
+                                Dispatch::ToEditor(ExpandSnippet),
                             ),
                             Keymap::new(
                                 "home",
@@ -472,6 +580,11 @@ impl Editor {
         KeymapLegendSection {
             title: "Others".to_string(),
             keymaps: Keymaps::new(&[
+                Keymap::new(
+                    "tab",
+                    "Go to next snippet tabstop".to_string(),
+                    Dispatch::ToEditor(GoToNextTabstop),
+                ),
                 Keymap::new(
                     "space",
                     "Search (List)".to_string(),
@@ -645,6 +758,70 @@ impl Editor {
                                 "Wrap".to_string(),
                                 Dispatch::ToEditor(Transform(Transformation::Wrap)),
                             ),
+                            Keymap::new(
+                                "=",
+                                "Evaluate arithmetic".to_string(),
+                                Dispatch::ToEditor(EvaluateSelection),
+                            ),
+                            Keymap::new(
+                                "o",
+                                "Sort selections".to_string(),
+                                Dispatch::ToEditor(SortSelectionsByContent),
+                            ),
+                            Keymap::new(
+                                "W",
+                                "Reflow comment".to_string(),
+                                Dispatch::ToEditor(Transform(Transformation::ReflowComment(80))),
+                            ),
+                            Keymap::new(
+                                ">",
+                                "Indent".to_string(),
+                                Dispatch::ToEditor(Transform(Transformation::Indent(1))),
+                            ),
+                            Keymap::new(
+                                "<",
+                                "Dedent".to_string(),
+                                Dispatch::ToEditor(Transform(Transformation::Dedent(1))),
+                            ),
+                            Keymap::new(
+                                "v",
+                                "Replace invisible characters".to_string(),
+                                Dispatch::ToEditor(ReplaceInvisibleCharacters),
+                            ),
+                            Keymap::new(
+                                "b",
+                                "Collapse blank lines".to_string(),
+                                Dispatch::ToEditor(Transform(Transformation::CollapseWhitespace {
+                                    inline: false,
+                                })),
+                            ),
+                            Keymap::new(
+                                "B",
+                                "Collapse blank lines and whitespaces".to_string(),
+                                Dispatch::ToEditor(Transform(Transformation::CollapseWhitespace {
+                                    inline: true,
+                                })),
+                            ),
+                            Keymap::new(
+                                "|",
+                                "Pipe through command".to_string(),
+                                Dispatch::OpenPipeSelectionPrompt,
+                            ),
+                            Keymap::new(
+                                "!",
+                                "Insert command output".to_string(),
+                                Dispatch::OpenInsertShellOutputPrompt,
+                            ),
+                            Keymap::new(
+                                "i",
+                                "Sort imports".to_string(),
+                                Dispatch::ToEditor(SortImports),
+                            ),
+                            Keymap::new(
+                                "/",
+                                "Toggle line comment".to_string(),
+                                Dispatch::ToEditor(ToggleLineComment),
+                            ),
                         ]),
                     },
                 ]
@@ -671,6 +848,7 @@ impl Editor {
                                     "Files (Non git ignored)",
                                     FilePickerKind::NonGitIgnored,
                                 ),
+                                ("B", "Recent files", FilePickerKind::Recent),
                             ]
                             .into_iter()
                             .map(|(key, description, kind)| {
@@ -706,6 +884,11 @@ impl Editor {
                                 "Theme".to_string(),
                                 Dispatch::OpenThemePrompt,
                             )))
+                            .chain(Some(Keymap::new(
+                                "T",
+                                "TODOs".to_string(),
+                                Dispatch::ListTodos,
+                            )))
                             .collect_vec(),
                         ),
                     }])
@@ -722,6 +905,55 @@ impl Editor {
                                 "Keep only primary cursor".to_string(),
                                 Dispatch::ToEditor(DispatchEditor::CursorKeepPrimaryOnly),
                             ),
+                            Keymap::new(
+                                "F",
+                                "Keep only first cursor".to_string(),
+                                Dispatch::ToEditor(DispatchEditor::CursorCollapseToFirst),
+                            ),
+                            Keymap::new(
+                                "L",
+                                "Keep only last cursor".to_string(),
+                                Dispatch::ToEditor(DispatchEditor::CursorCollapseToLast),
+                            ),
+                            Keymap::new(
+                                "N",
+                                "Keep only cursor nearest to view center".to_string(),
+                                Dispatch::ToEditor(DispatchEditor::CursorCollapseToNearest),
+                            ),
+                            Keymap::new(
+                                "n",
+                                "Make next cursor primary".to_string(),
+                                Dispatch::ToEditor(DispatchEditor::CyclePrimaryCursor {
+                                    backward: false,
+                                }),
+                            ),
+                            Keymap::new(
+                                "p",
+                                "Make previous cursor primary".to_string(),
+                                Dispatch::ToEditor(DispatchEditor::CyclePrimaryCursor {
+                                    backward: true,
+                                }),
+                            ),
+                            Keymap::new(
+                                "v",
+                                "Visual block (one cursor per line)".to_string(),
+                                Dispatch::ToEditor(DispatchEditor::SelectBlock),
+                            ),
+                            Keymap::new(
+                                "k",
+                                "Select lines matching (regex)".to_string(),
+                                Dispatch::OpenSelectLinesMatchingPrompt { matching: true },
+                            ),
+                            Keymap::new(
+                                "K",
+                                "Select lines not matching (regex)".to_string(),
+                                Dispatch::OpenSelectLinesMatchingPrompt { matching: false },
+                            ),
+                            Keymap::new(
+                                "q",
+                                "Select by tree-sitter query".to_string(),
+                                Dispatch::OpenSelectByQueryPrompt,
+                            ),
                         ]),
                     }))
                     .chain(Some(KeymapLegendSection {
@@ -735,11 +967,242 @@ impl Editor {
                                 })),
                             ))
                             .into_iter()
+                            .chain(Some(Keymap::new(
+                                "j",
+                                format!(
+                                    "Toggle file explorer sidebar ({})",
+                                    if context.file_explorer_sidebar() {
+                                        "on"
+                                    } else {
+                                        "off"
+                                    }
+                                ),
+                                Dispatch::ToggleFileExplorerSidebar,
+                            )))
                             .chain(Some(Keymap::new(
                                 "z",
                                 "Undo Tree".to_string(),
                                 Dispatch::ToEditor(DispatchEditor::EnterUndoTreeMode),
                             )))
+                            .chain(Some(Keymap::new(
+                                "m",
+                                format!(
+                                    "Toggle smooth scrolling ({})",
+                                    if context.smooth_scroll() { "on" } else { "off" }
+                                ),
+                                Dispatch::ToggleSmoothScroll,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "i",
+                                format!(
+                                    "Toggle indent guides ({})",
+                                    if context.show_indent_guides() {
+                                        "on"
+                                    } else {
+                                        "off"
+                                    }
+                                ),
+                                Dispatch::ToggleIndentGuides,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "I",
+                                format!(
+                                    "Toggle grouping of sorted imports ({})",
+                                    if context.group_imports() { "on" } else { "off" }
+                                ),
+                                Dispatch::ToggleGroupImports,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "V",
+                                format!(
+                                    "Toggle structural validation of exchange/replace ({})",
+                                    if context.validate_structural_edits() {
+                                        "on"
+                                    } else {
+                                        "off"
+                                    }
+                                ),
+                                Dispatch::ToggleValidateStructuralEdits,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "P",
+                                format!(
+                                    "Toggle reindent on paste ({})",
+                                    if context.reindent_on_paste() {
+                                        "on"
+                                    } else {
+                                        "off"
+                                    }
+                                ),
+                                Dispatch::ToggleReindentOnPaste,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "H",
+                                format!(
+                                    "Toggle highlight on yank ({})",
+                                    if context.yank_flash() { "on" } else { "off" }
+                                ),
+                                Dispatch::ToggleYankFlash,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "O",
+                                format!(
+                                    "Toggle highlight of symbol occurrences ({})",
+                                    if context.document_highlight() {
+                                        "on"
+                                    } else {
+                                        "off"
+                                    }
+                                ),
+                                Dispatch::ToggleDocumentHighlight,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "W",
+                                format!(
+                                    "Set highlight on yank duration ({}ms)",
+                                    context.yank_flash_duration().as_millis()
+                                ),
+                                Dispatch::OpenYankFlashDurationPrompt,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "M",
+                                format!(
+                                    "Set large file threshold ({} bytes)",
+                                    context.large_file_threshold()
+                                ),
+                                Dispatch::OpenLargeFileThresholdPrompt,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "J",
+                                format!(
+                                    "Set incremental reparse threshold ({} bytes)",
+                                    context.incremental_reparse_threshold()
+                                ),
+                                Dispatch::OpenIncrementalReparseThresholdPrompt,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "D",
+                                format!(
+                                    "Toggle default cursor direction of {} ({})",
+                                    self.selection_set.mode.display(),
+                                    match context.default_cursor_direction(&self.selection_set.mode)
+                                    {
+                                        Direction::Start => "start",
+                                        Direction::End => "end",
+                                    }
+                                ),
+                                Dispatch::ToggleDefaultCursorDirection(
+                                    self.selection_set.mode.clone(),
+                                ),
+                            )))
+                            .chain(Some(Keymap::new(
+                                "l",
+                                format!(
+                                    "Cycle final newline on save ({})",
+                                    context.final_newline().display()
+                                ),
+                                Dispatch::CycleFinalNewline,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "E",
+                                format!(
+                                    "Toggle line ending ({})",
+                                    self.buffer().line_ending().display()
+                                ),
+                                Dispatch::ToEditor(DispatchEditor::ToggleLineEnding),
+                            )))
+                            .chain(Some(Keymap::new(
+                                "8",
+                                format!(
+                                    "Convert encoding ({})",
+                                    self.buffer().encoding().display()
+                                ),
+                                Dispatch::ShowKeymapLegend(
+                                    self.convert_encoding_keymap_legend_config(),
+                                ),
+                            )))
+                            .chain(Some(Keymap::new(
+                                "S",
+                                "Open scratch buffer".to_string(),
+                                Dispatch::OpenScratchPrompt,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "R",
+                                "Run task".to_string(),
+                                Dispatch::OpenRunTaskPrompt,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "Q",
+                                "Parse errors to quickfix".to_string(),
+                                Dispatch::OpenParseErrorsToQuickfixPrompt,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "y",
+                                "Copy as markdown code block".to_string(),
+                                Dispatch::ToEditor(
+                                    DispatchEditor::SelectionToClipboardAsMarkdownCodeBlock {
+                                        with_location: false,
+                                        use_system_clipboard: true,
+                                    },
+                                ),
+                            )))
+                            .chain(Some(Keymap::new(
+                                "Y",
+                                "Copy as markdown code block with location".to_string(),
+                                Dispatch::ToEditor(
+                                    DispatchEditor::SelectionToClipboardAsMarkdownCodeBlock {
+                                        with_location: true,
+                                        use_system_clipboard: true,
+                                    },
+                                ),
+                            )))
+                            .chain(Some(Keymap::new(
+                                "u",
+                                "Character info".to_string(),
+                                Dispatch::ToEditor(DispatchEditor::ShowCharInfo),
+                            )))
+                            .chain(Some(Keymap::new(
+                                "d",
+                                format!("Insert date/time ({})", context.date_time_format()),
+                                Dispatch::ToEditor(DispatchEditor::InsertDateTime),
+                            )))
+                            .chain(Some(Keymap::new(
+                                "C",
+                                "Insert date/time with format".to_string(),
+                                Dispatch::OpenInsertDateTimePrompt,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "Z",
+                                format!(
+                                    "Toggle UTC date/time ({})",
+                                    if context.date_time_utc() { "on" } else { "off" }
+                                ),
+                                Dispatch::ToggleDateTimeUtc,
+                            )))
+                            .chain(Some(Keymap::new(
+                                "U",
+                                "Insert UUID".to_string(),
+                                Dispatch::ToEditor(DispatchEditor::GenerateUuid {
+                                    uppercase: false,
+                                }),
+                            )))
+                            .chain(Some(Keymap::new(
+                                "X",
+                                "Insert UUID (uppercase)".to_string(),
+                                Dispatch::ToEditor(DispatchEditor::GenerateUuid {
+                                    uppercase: true,
+                                }),
+                            )))
+                            .chain(Some(Keymap::new(
+                                "+",
+                                "Sum selections".to_string(),
+                                Dispatch::ToEditor(DispatchEditor::SumSelections),
+                            )))
+                            .chain(Some(Keymap::new(
+                                "w",
+                                "Selections to quickfix".to_string(),
+                                Dispatch::ToEditor(DispatchEditor::SelectionToQuickfix),
+                            )))
                             .chain(
                                 self.buffer()
                                     .get_current_node(self.selection_set.primary_selection(), false)
@@ -804,6 +1267,38 @@ impl Editor {
                                 )
                             }),
                     )
+                    .chain((scope == Scope::Local).then(|| {
+                        Keymap::new(
+                            "v",
+                            "Invisible characters".to_string(),
+                            Dispatch::ToEditor(SelectInvisibleCharacters),
+                        )
+                    }))
+                    .chain(
+                        (scope == Scope::Local)
+                            .then(|| {
+                                [
+                                    ("y", "Clipboard", false),
+                                    ("Y", "Clipboard (whole word)", true),
+                                ]
+                                .map(
+                                    |(key, description, whole_word)| {
+                                        Keymap::new(
+                                            key,
+                                            description.to_string(),
+                                            Dispatch::ToEditor(
+                                                RepeatSelectionModeSearchFromClipboard {
+                                                    whole_word,
+                                                    use_system_clipboard: false,
+                                                },
+                                            ),
+                                        )
+                                    },
+                                )
+                            })
+                            .into_iter()
+                            .flatten(),
+                    )
                     .chain(config.last_search().map(|search| {
                         Keymap::new(
                             "p",
@@ -847,6 +1342,20 @@ impl Editor {
                     ),
                 ]
                 .into_iter()
+                .chain((scope == Scope::Global).then(|| {
+                    Keymap::new(
+                        "Q",
+                        "Do at each quickfix item".to_string(),
+                        Dispatch::ShowKeymapLegend(self.quickfix_do_keymap_legend_config()),
+                    )
+                }))
+                .chain((scope == Scope::Local).then(|| {
+                    Keymap::new(
+                        "o",
+                        "Occurrences of word in function".to_string(),
+                        Dispatch::ToEditor(SelectWordUnderCursorOccurrences),
+                    )
+                }))
                 .chain(
                     [
                         ("g", DiffMode::UnstagedAgainstCurrentBranch),
@@ -865,6 +1374,25 @@ impl Editor {
                         )
                     }),
                 )
+                .chain(
+                    [
+                        ("E", AttentionComposition::Intersection, "Git hunk ∩ Error"),
+                        ("U", AttentionComposition::Union, "Git hunk ∪ Error"),
+                    ]
+                    .into_iter()
+                    .filter(|_| scope == Scope::Local)
+                    .map(|(key, composition, description)| {
+                        Keymap::new(
+                            key,
+                            description.to_string(),
+                            Dispatch::ToEditor(SetSelectionMode(Attention {
+                                diff_mode: DiffMode::UnstagedAgainstCurrentBranch,
+                                severity_range: DiagnosticSeverityRange::Error,
+                                composition,
+                            })),
+                        )
+                    }),
+                )
                 .collect_vec(),
             ),
         };
@@ -960,6 +1488,55 @@ impl Editor {
         }
     }
 
+    fn quickfix_do_keymap_legend_config(&self) -> KeymapLegendConfig {
+        KeymapLegendConfig {
+            title: "Do at each quickfix item".to_string(),
+            body: KeymapLegendBody::SingleSection {
+                keymaps: Keymaps::new(
+                    &[
+                        ("d", "Delete", Delete { backward: false }),
+                        (
+                            "r",
+                            "Replace",
+                            ReplaceWithCopiedText {
+                                cut: false,
+                                use_system_clipboard: false,
+                            },
+                        ),
+                        ("p", "Replace with pattern", ReplaceWithPattern),
+                        ("=", "Evaluate arithmetic", EvaluateSelection),
+                    ]
+                    .into_iter()
+                    .map(|(key, description, dispatch)| {
+                        Keymap::new(key, description.to_string(), Dispatch::QuickfixDo(dispatch))
+                    })
+                    .collect_vec(),
+                ),
+            },
+        }
+    }
+
+    fn convert_encoding_keymap_legend_config(&self) -> KeymapLegendConfig {
+        KeymapLegendConfig {
+            title: "Convert encoding".to_string(),
+            body: KeymapLegendBody::SingleSection {
+                keymaps: Keymaps::new(
+                    &["u", "U", "l", "b", "1"]
+                        .into_iter()
+                        .zip(Encoding::all())
+                        .map(|(key, encoding)| {
+                            Keymap::new(
+                                key,
+                                encoding.display().to_string(),
+                                Dispatch::ToEditor(ConvertEncoding(encoding)),
+                            )
+                        })
+                        .collect_vec(),
+                ),
+            },
+        }
+    }
+
     pub(crate) fn between_mode_keymap_legend_config(&self) -> KeymapLegendConfig {
         KeymapLegendConfig {
             title: "Between".to_string(),
@@ -1002,6 +1579,42 @@ impl Editor {
                                     self.delete_surround_keymap_legend_config(),
                                 ),
                             ),
+                            Keymap::new(
+                                "x",
+                                "Delete Inside".to_string(),
+                                Dispatch::ShowKeymapLegend(
+                                    self.delete_to_matching_keymap_legend_config(
+                                        SurroundKind::Inside,
+                                    ),
+                                ),
+                            ),
+                            Keymap::new(
+                                "X",
+                                "Delete Around".to_string(),
+                                Dispatch::ShowKeymapLegend(
+                                    self.delete_to_matching_keymap_legend_config(
+                                        SurroundKind::Around,
+                                    ),
+                                ),
+                            ),
+                            Keymap::new(
+                                "r",
+                                "Replace Inside".to_string(),
+                                Dispatch::ShowKeymapLegend(KeymapLegendConfig {
+                                    title: "Replace Inside".to_string(),
+
+                                    body: KeymapLegendBody::SingleSection {
+                                        keymaps: generate_enclosures_keymaps(|enclosure| {
+                                            Dispatch::ToEditor(ReplaceInsidePair(enclosure))
+                                        }),
+                                    },
+                                }),
+                            ),
+                            Keymap::new(
+                                "t",
+                                "Wrap in Tag".to_string(),
+                                Dispatch::OpenWrapInTagPrompt,
+                            ),
                         ]),
                     },
                     KeymapLegendSection {
@@ -1024,13 +1637,49 @@ impl Editor {
         KeymapLegendConfig {
             title: format!("Select Surround ({:?})", kind),
 
-            body: KeymapLegendBody::SingleSection {
-                keymaps: generate_enclosures_keymaps(|enclosure| {
-                    Dispatch::ToEditor(SelectSurround {
-                        enclosure,
-                        kind: kind.clone(),
-                    })
-                }),
+            body: KeymapLegendBody::MultipleSections {
+                sections: [
+                    KeymapLegendSection {
+                        title: "Enclosures".to_string(),
+                        keymaps: generate_enclosures_keymaps(|enclosure| {
+                            Dispatch::ToEditor(SelectSurround {
+                                enclosure,
+                                kind: kind.clone(),
+                            })
+                        }),
+                    },
+                    KeymapLegendSection {
+                        title: "Syntax nodes".to_string(),
+                        keymaps: Keymaps::new(
+                            &[
+                                Keymap::new(
+                                    "s",
+                                    "String".to_string(),
+                                    Dispatch::ToEditor(SelectEnclosingString {
+                                        include_delimiters: kind == SurroundKind::Around,
+                                    }),
+                                ),
+                                Keymap::new(
+                                    "c",
+                                    "Comment".to_string(),
+                                    Dispatch::ToEditor(SelectEnclosingComment {
+                                        include_delimiters: kind == SurroundKind::Around,
+                                    }),
+                                ),
+                            ]
+                            .into_iter()
+                            .chain((kind == SurroundKind::Inside).then(|| {
+                                Keymap::new(
+                                    "f",
+                                    "Function body".to_string(),
+                                    Dispatch::ToEditor(SelectFunctionBody),
+                                )
+                            }))
+                            .collect_vec(),
+                        ),
+                    },
+                ]
+                .to_vec(),
             },
         }
     }
@@ -1047,6 +1696,24 @@ impl Editor {
         }
     }
 
+    pub(crate) fn delete_to_matching_keymap_legend_config(
+        &self,
+        kind: SurroundKind,
+    ) -> KeymapLegendConfig {
+        KeymapLegendConfig {
+            title: format!("Delete ({:?})", kind),
+
+            body: KeymapLegendBody::SingleSection {
+                keymaps: generate_enclosures_keymaps(|enclosure| {
+                    Dispatch::ToEditor(DeleteToMatching {
+                        enclosure,
+                        kind: kind.clone(),
+                    })
+                }),
+            },
+        }
+    }
+
     pub(crate) fn change_surround_from_keymap_legend_config(
         &self,
     ) -> super::keymap_legend::KeymapLegendConfig {
@@ -1130,6 +1797,11 @@ impl Editor {
                                 "Info".to_string(),
                                 filter_mechanism_keymaps(kind, FilterTarget::Info),
                             ),
+                            Keymap::new(
+                                "l",
+                                "Line content".to_string(),
+                                filter_mechanism_keymaps(kind, FilterTarget::LineContent),
+                            ),
                         ]
                         .as_ref(),
                     ),
@@ -1143,6 +1815,11 @@ impl Editor {
                 keymaps: Keymaps::new(
                     [
                         Keymap::new("c", "Clear".to_string(), Dispatch::ToEditor(FilterClear)),
+                        Keymap::new(
+                            "w",
+                            "Within selection".to_string(),
+                            Dispatch::ToEditor(SelectNextOccurrenceInSelection),
+                        ),
                         Keymap::new(
                             "k",
                             "keep".to_string(),
@@ -1179,6 +1856,7 @@ impl Editor {
                             mode: LocalSearchConfigMode::Regex(RegexConfig {
                                 escaped: false,
                                 match_whole_word: false,
+                                multiline: false,
                                 case_sensitive: false,
                             }),
                         };
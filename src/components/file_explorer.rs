@@ -57,13 +57,22 @@ impl FileExplorer {
     }
 }
 
+/// Git-ignored paths are excluded, like in the file picker.
 fn get_nodes(path: &CanonicalizedPath) -> anyhow::Result<Vec<Node>> {
-    let directory = std::fs::read_dir(path)?;
+    let directory = ignore::WalkBuilder::new(path)
+        .max_depth(Some(1))
+        .hidden(false)
+        .build();
     Ok(directory
         .flatten()
+        // The first entry is `path` itself
+        .filter(|entry| entry.depth() > 0)
         .flat_map(|entry| -> anyhow::Result<Node> {
             let path: CanonicalizedPath = entry.path().try_into()?;
-            let kind = if entry.file_type()?.is_dir() {
+            let kind = if entry
+                .file_type()
+                .map_or(false, |file_type| file_type.is_dir())
+            {
                 NodeKind::Directory {
                     open: false,
                     children: None,
@@ -393,6 +402,7 @@ mod test_file_explorer {
     use my_proc_macros::{key, keys};
 
     use crate::test_app::*;
+    use crate::ui_tree::ComponentKind;
 
     #[test]
     fn reveal() -> Result<(), anyhow::Error> {
@@ -419,6 +429,33 @@ mod test_file_explorer {
         })
     }
 
+    #[test]
+    fn exclude_git_ignored_paths() -> Result<(), anyhow::Error> {
+        execute_test(|s| {
+            Box::new([
+                App(OpenFile(s.gitignore())),
+                Editor(Insert("*.txt\n".to_string())),
+                App(SaveAll),
+                App(AddPath(s.new_path("temp.txt").display().to_string())),
+                App(AddPath(s.new_path("src/temp.txt").display().to_string())),
+                App(RevealInExplorer(s.main_rs())),
+                Expect(FileExplorerContent(
+                    "
+ - 📁  .git/ :
+ - 🙈  .gitignore
+ - 🔒  Cargo.lock
+ - 📄  Cargo.toml
+ - 📂  src/ :
+   - 🦀  foo.rs
+   - 🦀  main.rs
+"
+                    .trim_matches('\n')
+                    .to_string(),
+                )),
+            ])
+        })
+    }
+
     #[test]
     fn move_path() -> anyhow::Result<()> {
         execute_test(|s| {
@@ -450,4 +487,33 @@ mod test_file_explorer {
             ])
         })
     }
+
+    #[test]
+    fn sidebar() -> anyhow::Result<()> {
+        execute_test(|s| {
+            Box::new([
+                App(OpenFile(s.main_rs())),
+                App(ToggleFileExplorerSidebar),
+                Expect(ComponentsOrder(
+                    [ComponentKind::SuggestiveEditor, ComponentKind::FileExplorer].to_vec(),
+                )),
+                Expect(CurrentComponentPath(Some(s.main_rs()))),
+                // Revealing focuses the sidebar without closing the editor
+                App(RevealInExplorer(s.foo_rs())),
+                Expect(CurrentComponentTitle("File Explorer")),
+                Expect(CurrentSelectedTexts(&["   - 🦀  foo.rs\n"])),
+                Expect(ComponentsOrder(
+                    [ComponentKind::SuggestiveEditor, ComponentKind::FileExplorer].to_vec(),
+                )),
+                // Opening a file keeps the sidebar
+                App(HandleKeyEvent(key!("enter"))),
+                Expect(CurrentComponentPath(Some(s.foo_rs()))),
+                Expect(ComponentsOrder(
+                    [ComponentKind::SuggestiveEditor, ComponentKind::FileExplorer].to_vec(),
+                )),
+                App(ToggleFileExplorerSidebar),
+                Expect(ComponentsOrder([ComponentKind::SuggestiveEditor].to_vec())),
+            ])
+        })
+    }
 }
@@ -41,8 +41,18 @@ pub(crate) struct PromptConfig {
 #[derive(Hash, PartialEq, Eq, Debug, Clone, Copy)]
 pub(crate) enum PromptHistoryKey {
     MoveToIndex,
+    MoveToColumn,
+    JumpToPercentOfLine,
+    GoToByteOffset,
+    DateTimeFormat,
+    OpenScratch,
     Search(Scope),
     Rename,
+    WrapInTag,
+    PipeSelection,
+    InsertShellOutput,
+    SelectLinesMatching,
+    SelectByQuery,
     AddPath,
     MovePath,
     Symbol,
@@ -52,6 +62,13 @@ pub(crate) enum PromptHistoryKey {
     FilterGlob(GlobalSearchFilterGlob),
     Replacement(Scope),
     CodeAction,
+    SearchPreset,
+    RegisterHistory,
+    RunTask,
+    ParseErrorsToQuickfix,
+    YankFlashDuration,
+    LargeFileThreshold,
+    IncrementalReparseThreshold,
     #[cfg(test)]
     Null,
     Theme,
@@ -13,13 +13,17 @@ use crate::{
     },
     context::Context,
     grid::{CellUpdate, Grid, LineUpdate, RenderContentLineNumber, StyleKey},
+    position::Position,
     selection::{CharIndex, Selection},
     selection_mode::{self, ByteRange},
     style::Style,
     themes::Theme,
 };
 
-use super::{component::GetGridResult, editor::Editor};
+use super::{
+    component::GetGridResult,
+    editor::{Editor, PossibleSelectionsCacheKey},
+};
 
 use StyleKey::*;
 
@@ -77,6 +81,26 @@ impl Editor {
                 ranges: HighlightSpanRange::CharIndexRange(bookmark),
             })
             .collect_vec();
+        let yank_flash = editor
+            .yank_flash_ranges()
+            .into_iter()
+            .map(|range| HighlightSpan {
+                set_symbol: None,
+                is_cursor: false,
+                source: Source::StyleKey(StyleKey::YankFlash),
+                ranges: HighlightSpanRange::CharIndexRange(range),
+            })
+            .collect_vec();
+        let document_highlights = editor
+            .document_highlights()
+            .iter()
+            .map(|range| HighlightSpan {
+                set_symbol: None,
+                is_cursor: false,
+                source: Source::StyleKey(StyleKey::DocumentHighlight),
+                ranges: HighlightSpanRange::CharIndexRange(*range),
+            })
+            .collect_vec();
         let secondary_selections = &editor.selection_set.secondary_selections();
         let primary_selection = HighlightSpan {
             set_symbol: None,
@@ -275,11 +299,13 @@ impl Editor {
             .chain(highlighted_spans)
             .chain(extra_decorations)
             .chain(possible_selections)
+            .chain(document_highlights)
             .chain(Some(primary_selection))
             .chain(secondary_selection)
             .chain(primary_selection_anchors)
             .chain(seconday_selection_anchors)
             .chain(bookmarks)
+            .chain(yank_flash)
             .chain(diagnostics)
             .chain(jumps)
             .chain(primary_selection_secondary_cursor)
@@ -287,11 +313,23 @@ impl Editor {
             .chain(custom_regex_highlights)
             .chain(regex_highlight_rules)
             .collect_vec();
+        let indent_guides = if context.show_indent_guides() {
+            let cursor_line = buffer
+                .char_to_line(selection.to_char_index(&editor.cursor_direction))
+                .unwrap_or_default();
+            self.get_indent_guides(visible_lines, cursor_line, theme)
+        } else {
+            Vec::new()
+        };
         let visible_lines_updates = {
             let boundaries = [Boundary::new(&buffer, self.visible_line_range())];
-            updates
-                .iter()
-                .flat_map(|span| span.to_cell_update(&buffer, theme, &boundaries))
+            indent_guides
+                .into_iter()
+                .chain(
+                    updates
+                        .iter()
+                        .flat_map(|span| span.to_cell_update(&buffer, theme, &boundaries)),
+                )
                 .chain(primary_selection_primary_cursor)
                 .collect_vec()
         };
@@ -416,25 +454,111 @@ impl Editor {
         }
     }
 
+    /// Returns the indent guides of the given `lines`,
+    /// the guide of the innermost block containing `cursor_line` is highlighted.
+    fn get_indent_guides(
+        &self,
+        lines: &[(usize, String)],
+        cursor_line: usize,
+        theme: &Theme,
+    ) -> Vec<CellUpdate> {
+        // `None` means the line is blank
+        let indentations = lines
+            .iter()
+            .map(|(line_index, line)| {
+                let line = line.trim_end_matches(['\n', '\r']);
+                let indentation = (!line.trim().is_empty())
+                    .then(|| line.chars().take_while(|c| c.is_whitespace()).count());
+                (*line_index, indentation)
+            })
+            .collect_vec();
+        let indent_size = indentations
+            .iter()
+            .filter_map(|(_, indentation)| *indentation)
+            .filter(|indentation| *indentation > 0)
+            .min()
+            .unwrap_or(4);
+        let active_guide = indentations
+            .iter()
+            .position(|(line_index, _)| *line_index == cursor_line)
+            .and_then(|cursor_index| {
+                let column = indentations[cursor_index].1?.checked_sub(indent_size)?;
+                let is_inside = |(_, indentation): &(usize, Option<usize>)| {
+                    !matches!(indentation, Some(indentation) if *indentation <= column)
+                };
+                let start = indentations[..cursor_index]
+                    .iter()
+                    .rposition(|line| !is_inside(line))
+                    .map_or(0, |index| index + 1);
+                let end = indentations[cursor_index..]
+                    .iter()
+                    .position(|line| !is_inside(line))
+                    .map_or(indentations.len(), |index| cursor_index + index);
+                Some((column, indentations[start].0..indentations[end - 1].0 + 1))
+            });
+        let active_guide = &active_guide;
+        indentations
+            .iter()
+            .filter_map(|(line, indentation)| Some((*line, (*indentation)?)))
+            .flat_map(|(line, indentation)| {
+                (0..indentation).step_by(indent_size).map(move |column| {
+                    let is_active = matches!(
+                        active_guide,
+                        Some((active_column, active_lines))
+                            if *active_column == column && active_lines.contains(&line)
+                    );
+                    let style_key = if is_active {
+                        IndentGuideActive
+                    } else {
+                        IndentGuide
+                    };
+                    CellUpdate {
+                        position: Position { line, column },
+                        symbol: Some("│".to_string()),
+                        style: theme.get_style(&style_key),
+                        is_cursor: false,
+                        source: Some(style_key),
+                    }
+                })
+            })
+            .collect_vec()
+    }
+
     pub(crate) fn possible_selections_in_line_number_range(
         &self,
         selection: &Selection,
     ) -> anyhow::Result<Vec<ByteRange>> {
-        let object = self.get_selection_mode_trait_object(selection, true)?;
-        if self.selection_set.mode.is_contiguous() && self.selection_set.filters.is_empty() {
-            return Ok(Vec::new());
+        let line_range = self.visible_line_range();
+        let key = PossibleSelectionsCacheKey {
+            buffer_version: self.buffer().version(),
+            selection: selection.clone(),
+            selection_set: self.selection_set.clone(),
+            cursor_direction: self.cursor_direction.clone(),
+            visible_line_range: line_range.clone(),
+        };
+        if let Some((cached_key, ranges)) = self.possible_selections_cache.borrow().as_ref() {
+            if cached_key == &key {
+                return Ok(ranges.clone());
+            }
         }
 
-        let line_range = self.visible_line_range();
-        object.selections_in_line_number_range(
-            &selection_mode::SelectionModeParams {
-                buffer: &self.buffer(),
-                current_selection: selection,
-                cursor_direction: &self.cursor_direction,
-                filters: &self.selection_set.filters,
-            },
-            line_range,
-        )
+        let object = self.get_selection_mode_trait_object(selection, true)?;
+        let ranges =
+            if self.selection_set.mode.is_contiguous() && self.selection_set.filters.is_empty() {
+                Vec::new()
+            } else {
+                object.selections_in_line_number_range(
+                    &selection_mode::SelectionModeParams {
+                        buffer: &self.buffer(),
+                        current_selection: selection,
+                        cursor_direction: &self.cursor_direction,
+                        filters: &self.selection_set.filters,
+                    },
+                    line_range,
+                )?
+            };
+        *self.possible_selections_cache.borrow_mut() = Some((key, ranges.clone()));
+        Ok(ranges)
     }
 }
 
@@ -565,6 +689,8 @@ mod test_render_editor {
         components::{component::Component, editor::Editor},
         context::Context,
         rectangle::Rectangle,
+        selection::SelectionMode,
+        selection_mode::ByteRange,
     };
 
     impl Arbitrary for Rectangle {
@@ -593,4 +719,41 @@ mod test_render_editor {
                 && cell.position.column < (rectangle.width as usize)
         })
     }
+
+    #[test]
+    fn possible_selections_should_be_cached_until_their_inputs_change() -> anyhow::Result<()> {
+        let mut editor = Editor::from_text(
+            Some(tree_sitter_rust::language()),
+            "fn f() {}\n\nfn g() {}\n\nfn h() {}",
+        );
+        editor.set_rectangle(Rectangle {
+            origin: Default::default(),
+            width: 20,
+            height: 5,
+        });
+        editor.set_selection_mode(SelectionMode::EmptyLine)?;
+        let selection = editor.selection_set.primary_selection().clone();
+        let possible_selections = editor.possible_selections_in_line_number_range(&selection)?;
+        assert!(!possible_selections.is_empty());
+
+        // Tamper with the cache to prove that it is used when nothing changes
+        let sentinel: Vec<ByteRange> = Vec::new();
+        editor
+            .possible_selections_cache
+            .borrow_mut()
+            .as_mut()
+            .unwrap()
+            .1 = sentinel.clone();
+        assert_eq!(
+            editor.possible_selections_in_line_number_range(&selection)?,
+            sentinel
+        );
+
+        // Any change to the buffer invalidates the cache
+        editor.buffer_mut().update("fn f() {}\n\nfn g() {}");
+        let recomputed = editor.possible_selections_in_line_number_range(&selection)?;
+        assert_ne!(recomputed, sentinel);
+        assert_ne!(recomputed, possible_selections);
+        Ok(())
+    }
 }
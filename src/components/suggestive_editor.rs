@@ -144,6 +144,11 @@ impl Component for SuggestiveEditor {
                     }
                 }),
                 Keymap::new("h", "Hover".to_string(), Dispatch::RequestHover),
+                Keymap::new(
+                    "A",
+                    "Organize Imports".to_string(),
+                    Dispatch::OrganizeImports,
+                ),
                 Keymap::new("r", "Rename".to_string(), Dispatch::PrepareRename),
             ]),
         }]
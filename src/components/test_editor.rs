@@ -203,6 +203,130 @@ fn toggle_untoggle_bookmark() -> anyhow::Result<()> {
     })
 }
 
+#[test]
+fn cursor_collapse_to_first_and_last() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo bar spam".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(MoveSelection(Next)),
+            Editor(CursorAddToAllSelections),
+            Expect(CurrentSelectedTexts(&["foo", "bar", "spam"])),
+            Editor(CursorCollapseToLast),
+            Expect(CurrentSelectedTexts(&["spam"])),
+            Editor(CursorAddToAllSelections),
+            Editor(CursorCollapseToFirst),
+            Expect(CurrentSelectedTexts(&["foo"])),
+        ])
+    })
+}
+
+#[test]
+fn cursor_collapse_to_nearest_should_scroll_to_the_kept_cursor() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("x\nb\nc\nd\ne\nf\ng\nh\nx".to_string())),
+            Editor(SetRectangle(Rectangle {
+                origin: Position::default(),
+                width: 100,
+                height: 4,
+            })),
+            Editor(MatchLiteral("x".to_string())),
+            Editor(CursorAddToAllSelections),
+            // Neither of the cursors is in view
+            Editor(SetScrollOffset(3)),
+            Editor(CursorCollapseToNearest),
+            Expect(CurrentSelectedTexts(&["x"])),
+            // The last line is nearer to the center line of the view, and it is scrolled into view
+            Expect(EditorGridCursorPosition(Position { line: 3, column: 2 })),
+        ])
+    })
+}
+
+#[test]
+fn cycle_primary_cursor() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo bar spam".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(CursorAddToAllSelections),
+            Editor(CyclePrimaryCursor { backward: false }),
+            Expect(CurrentPrimarySelection("bar")),
+            Editor(CyclePrimaryCursor { backward: true }),
+            Editor(CyclePrimaryCursor { backward: true }),
+            Expect(CurrentPrimarySelection("spam")),
+        ])
+    })
+}
+
+#[test]
+fn select_block() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("abc\nd\nghi".to_string())),
+            Editor(MatchLiteral("bc\nd\ngh".to_string())),
+            Editor(SelectBlock),
+            // The second line is skipped because it is shorter than the block
+            Expect(CurrentSelectedTexts(&["b", "h"])),
+            Editor(EnterInsertMode(Direction::Start)),
+            Editor(Insert("x".to_string())),
+            Expect(CurrentComponentContent("axbc\nd\ngxhi")),
+            Editor(EnterNormalMode),
+            // Columns are display columns, so a tab spans several columns
+            Editor(SetContent("\tab\n0123456".to_string())),
+            Editor(MatchLiteral("ab\n01234".to_string())),
+            Editor(SelectBlock),
+            Expect(CurrentSelectedTexts(&["a", "4"])),
+        ])
+    })
+}
+
+#[test]
+fn go_to_parent_line() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "fn f() {\n    if x {\n        y\n    }\n}".to_string(),
+            )),
+            Editor(MatchLiteral("y".to_string())),
+            Editor(GoToParentLine),
+            Expect(CurrentSelectedTexts(&["if x {"])),
+            Expect(CurrentSelectionMode(LineTrimmed)),
+            Editor(GoToParentLine),
+            Expect(CurrentSelectedTexts(&["fn f() {"])),
+        ])
+    })
+}
+
+#[test]
+fn select_to_parent_line() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "fn f() {\n    if x {\n        y\n    }\n}".to_string(),
+            )),
+            Editor(MatchLiteral("y".to_string())),
+            Editor(SelectToParentLine),
+            Expect(CurrentSelectedTexts(&["if x {\n        y"])),
+            // The cursor is on the parent line, which is the extended part of the selection
+            Expect(EditorCursorPosition(Position::new(1, 4))),
+            // The cursor direction of the editor is left as is
+            Editor(MatchLiteral("y".to_string())),
+            Editor(SwapCursorWithAnchor),
+            Editor(SelectToParentLine),
+            Expect(CurrentSelectedTexts(&["if x {\n        y"])),
+            Expect(CurrentCursorDirection(Direction::End)),
+            Expect(EditorCursorPosition(Position::new(1, 9))),
+        ])
+    })
+}
+
 #[test]
 fn test_delete_word_short_backward_from_end_of_file() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -475,6 +599,50 @@ fn exchange_sibling_2() -> anyhow::Result<()> {
     })
 }
 
+#[test]
+fn exchange_without_structural_validation() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn main(x: usize, y: Vec<A>) {}".to_string())),
+            Editor(MatchLiteral("x: usize".to_string())),
+            Editor(SetSelectionMode(SyntaxNodeFine)),
+            App(ToggleValidateStructuralEdits),
+            Editor(EnterExchangeMode),
+            // Expect the comma to be exchanged, although it breaks the syntax tree
+            Editor(MoveSelection(Next)),
+            Expect(CurrentComponentContent("fn main(,x: usize y: Vec<A>) {}")),
+        ])
+    })
+}
+
+#[test]
+fn exchange_deeply_nested_sibling() -> anyhow::Result<()> {
+    let depth = 100;
+    let content = format!(
+        "fn main() {{ {}x, y{} }}",
+        "f(".repeat(depth),
+        ")".repeat(depth)
+    );
+    let expected: &'static str = format!(
+        "fn main() {{ {}y, x{} }}",
+        "f(".repeat(depth),
+        ")".repeat(depth)
+    )
+    .leak();
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(content.clone())),
+            Editor(MatchLiteral("x".to_string())),
+            Editor(SetSelectionMode(SyntaxNodeCoarse)),
+            Editor(EnterExchangeMode),
+            Editor(MoveSelection(Next)),
+            Expect(CurrentComponentContent(expected)),
+        ])
+    })
+}
+
 #[test]
 fn select_character() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -739,6 +907,105 @@ fn multi_insert() -> anyhow::Result<()> {
     })
 }
 
+#[test]
+fn selection_to_clipboard_as_markdown_code_block() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn main() {\n    foo();\n}\n".to_string())),
+            Editor(SelectAll),
+            Editor(SelectionToClipboardAsMarkdownCodeBlock {
+                with_location: false,
+                use_system_clipboard: false,
+            }),
+            Editor(ReplaceWithCopiedText {
+                cut: false,
+                use_system_clipboard: false,
+            }),
+            Expect(CurrentComponentContent(
+                "```rust\nfn main() {\n    foo();\n}\n```",
+            )),
+            Editor(SetContent("fn main() {\n    foo();\n}\n".to_string())),
+            Editor(MatchLiteral("foo();\n}".to_string())),
+            Editor(SelectionToClipboardAsMarkdownCodeBlock {
+                with_location: true,
+                use_system_clipboard: false,
+            }),
+            Editor(SelectAll),
+            Editor(ReplaceWithCopiedText {
+                cut: false,
+                use_system_clipboard: false,
+            }),
+            Expect(CurrentComponentContent(
+                "src/main.rs:2-3\n```rust\nfoo();\n}\n```",
+            )),
+        ])
+    })
+}
+
+#[test]
+fn default_cursor_direction_of_selection_mode() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("hello world".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Expect(CurrentCursorDirection(Direction::Start)),
+            App(ToggleDefaultCursorDirection(WordShort)),
+            Editor(SetSelectionMode(LineTrimmed)),
+            Expect(CurrentCursorDirection(Direction::Start)),
+            Editor(SetSelectionMode(WordShort)),
+            Expect(CurrentCursorDirection(Direction::End)),
+            Expect(CurrentSelectedTexts(&["hello"])),
+        ])
+    })
+}
+
+#[test]
+fn search_clipboard_content() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("a.b a.bc axb a.b".to_string())),
+            App(SetClipboardContent {
+                copied_texts: CopiedTexts::one("a.b".to_string()),
+                use_system_clipboard: false,
+            }),
+            Editor(RepeatSelectionModeSearchFromClipboard {
+                whole_word: false,
+                use_system_clipboard: false,
+            }),
+            Editor(CursorAddToAllSelections),
+            // The clipboard content is escaped, so `.` does not match `x`
+            Expect(CurrentSelectedTexts(&["a.b", "a.b", "a.b"])),
+            Editor(CursorKeepPrimaryOnly),
+            Editor(RepeatSelectionModeSearchFromClipboard {
+                whole_word: true,
+                use_system_clipboard: false,
+            }),
+            Editor(CursorAddToAllSelections),
+            Expect(CurrentSelectedTexts(&["a.b", "a.b"])),
+        ])
+    })
+}
+
+#[test]
+fn select_and_replace_invisible_characters() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("let\u{00A0}x = \u{201C}a\u{201D};".to_string())),
+            Editor(SelectInvisibleCharacters),
+            Editor(CursorAddToAllSelections),
+            Expect(CurrentSelectedTexts(&["\u{00A0}", "\u{201C}", "\u{201D}"])),
+            Editor(CursorKeepPrimaryOnly),
+            Editor(SelectAll),
+            Editor(ReplaceInvisibleCharacters),
+            Expect(CurrentComponentContent("let x = \"a\";")),
+        ])
+    })
+}
+
 #[test]
 fn paste_in_insert_mode_1() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -762,6 +1029,88 @@ fn paste_in_insert_mode_1() -> anyhow::Result<()> {
     })
 }
 
+#[test]
+fn reindent_on_paste() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn main() {\n    x;\n}".to_string())),
+            App(SetClipboardContent {
+                copied_texts: CopiedTexts::one("if a {\n            b();\n        }".to_string()),
+                use_system_clipboard: false,
+            }),
+            App(ToggleReindentOnPaste),
+            Editor(MatchLiteral("x;".to_string())),
+            Editor(Change),
+            Editor(Paste {
+                direction: Direction::End,
+                use_system_clipboard: false,
+            }),
+            Expect(CurrentComponentContent(
+                "fn main() {\n    if a {\n        b();\n    }\n}",
+            )),
+        ])
+    })
+}
+
+#[test]
+fn reindent_on_paste_at_column_0() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn main() {\n    x;\n}".to_string())),
+            App(SetClipboardContent {
+                copied_texts: CopiedTexts::one(
+                    "        if a {\n            b();\n        }\n".to_string(),
+                ),
+                use_system_clipboard: false,
+            }),
+            App(ToggleReindentOnPaste),
+            Editor(MatchLiteral("x;".to_string())),
+            Editor(SetSelectionMode(LineFull)),
+            Editor(Paste {
+                direction: Direction::Start,
+                use_system_clipboard: false,
+            }),
+            // The first line is reindented too
+            Expect(CurrentComponentContent(
+                "fn main() {\n    if a {\n        b();\n    }\n    x;\n}",
+            )),
+        ])
+    })
+}
+
+#[test]
+fn reindent_on_paste_should_format_only_the_pasted_lines() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "fn main() {\n    x;\n}\nfn  f( ) {}\n".to_string(),
+            )),
+            App(SetClipboardContent {
+                copied_texts: CopiedTexts::one("let y=1;\nlet z  =  2;".to_string()),
+                use_system_clipboard: false,
+            }),
+            App(ToggleReindentOnPaste),
+            Editor(MatchLiteral("x;".to_string())),
+            Editor(Change),
+            Editor(Paste {
+                direction: Direction::End,
+                use_system_clipboard: false,
+            }),
+            // The formatter runs in the background
+            Expect(CurrentComponentContent(
+                "fn main() {\n    let y=1;\n    let z  =  2;\n}\nfn  f( ) {}\n",
+            )),
+            App(HandleNextAppMessage),
+            Expect(CurrentComponentContent(
+                "fn main() {\n    let y = 1;\n    let z = 2;\n}\nfn  f( ) {}\n",
+            )),
+        ])
+    })
+}
+
 #[test]
 fn paste_in_insert_mode_2() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -1027,41 +1376,119 @@ fn scroll_page() -> anyhow::Result<()> {
 }
 
 #[test]
-fn scroll_offset() -> anyhow::Result<()> {
+fn smooth_scroll_does_not_delay_cursor_movement() -> anyhow::Result<()> {
     execute_test(|s| {
         Box::new([
             App(OpenFile(s.main_rs())),
-            Editor(SetContent("alpha\nbeta\ngamma\nlok".to_string())),
+            App(ToggleSmoothScroll),
+            Editor(SetContent("1\n2 hey\n3".to_string())),
             Editor(SetRectangle(Rectangle {
                 origin: Position::default(),
                 width: 100,
                 height: 3,
             })),
-            Editor(SetScrollOffset(2)),
-            Expect(EditorGrid("🦀  src/main.rs\n3│█amma\n4│lok")),
+            Editor(ScrollPageDown),
+            Expect(CurrentLine("2 hey")),
+            Editor(ScrollPageUp),
+            Expect(CurrentLine("1")),
         ])
     })
 }
 
 #[test]
-fn jump() -> anyhow::Result<()> {
+fn smooth_mouse_scroll() -> anyhow::Result<()> {
+    use crate::components::component::Component;
+    let mut editor = crate::components::editor::Editor::from_text(None, "1\n2\n3\n4\n5\n6");
+    editor.set_rectangle(Rectangle {
+        origin: Position::default(),
+        width: 100,
+        height: 3,
+    });
+    let mut context = crate::context::Context::default();
+    context.toggle_smooth_scroll();
+    let scroll_down = || {
+        event::event::Event::Mouse(crossterm::event::MouseEvent {
+            kind: crossterm::event::MouseEventKind::ScrollDown,
+            column: 0,
+            row: 0,
+            modifiers: crossterm::event::KeyModifiers::NONE,
+        })
+    };
+
+    // The view is only moved by the animation ticks, which target this editor
+    let dispatches = editor.handle_event(&context, scroll_down())?;
+    assert_eq!(
+        dispatches.into_vec(),
+        [crate::app::Dispatch::StartScrollAnimation(editor.id())].to_vec()
+    );
+    assert_eq!(editor.scroll_offset(), 0);
+
+    // Scrolling during the animation shifts its target without starting another one
+    let dispatches = editor.handle_event(&context, scroll_down())?;
+    assert!(dispatches.into_vec().is_empty());
+    while editor.advance_scroll_animation() {}
+    assert_eq!(editor.scroll_offset(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn indent_guides() -> anyhow::Result<()> {
     execute_test(|s| {
         Box::new([
             App(OpenFile(s.main_rs())),
-            Editor(SetContent(
-                "Who lives on sea shore?\n yonky donkey".to_string(),
-            )),
+            App(ToggleIndentGuides),
+            Editor(SetContent("fn f() {\n    x\n}".to_string())),
             Editor(SetRectangle(Rectangle {
                 origin: Position::default(),
-                width: 100,
-                height: 1,
+                width: 20,
+                height: 4,
             })),
-            // In jump mode, the first stage labels each selection using their starting character,
-            // On subsequent stages, the labels are random alphabets
-            Expect(JumpChars(&[])),
-            Editor(SetSelectionMode(WordShort)),
-            Editor(ShowJumps {
-                use_current_selection_mode: false,
+            Expect(EditorGrid("🦀  src/main.rs\n1│█n f() {\n2││   x\n3│}")),
+            Expect(GridCellStyleKey(
+                Position::new(2, 2),
+                Some(StyleKey::IndentGuide),
+            )),
+        ])
+    })
+}
+
+#[test]
+fn scroll_offset() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("alpha\nbeta\ngamma\nlok".to_string())),
+            Editor(SetRectangle(Rectangle {
+                origin: Position::default(),
+                width: 100,
+                height: 3,
+            })),
+            Editor(SetScrollOffset(2)),
+            Expect(EditorGrid("🦀  src/main.rs\n3│█amma\n4│lok")),
+        ])
+    })
+}
+
+#[test]
+fn jump() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "Who lives on sea shore?\n yonky donkey".to_string(),
+            )),
+            Editor(SetRectangle(Rectangle {
+                origin: Position::default(),
+                width: 100,
+                height: 1,
+            })),
+            // In jump mode, the first stage labels each selection using their starting character,
+            // On subsequent stages, the labels are random alphabets
+            Expect(JumpChars(&[])),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(ShowJumps {
+                use_current_selection_mode: false,
             }),
             // Expect the jump to be the first character of each word
             // Note 'y' and 'd' are excluded because they are out of view,
@@ -1563,6 +1990,150 @@ fn main() { // too long
     })
 }
 
+#[test]
+fn yank_flash() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(SetYankFlashDuration(std::time::Duration::from_secs(60))),
+            Editor(SetContent("fn main() {}".to_string())),
+            Editor(SetRectangle(Rectangle {
+                origin: Position::default(),
+                width: 20,
+                height: 3,
+            })),
+            Editor(MatchLiteral("main".to_string())),
+            Editor(Copy {
+                use_system_clipboard: false,
+            }),
+            // Move the selection away, otherwise the selection style overrides the yank flash
+            Editor(MatchLiteral("fn".to_string())),
+            ExpectMulti(
+                (5..9)
+                    .map(|column| {
+                        ExpectKind::GridCellStyleKey(
+                            Position::new(1, column),
+                            Some(StyleKey::YankFlash),
+                        )
+                    })
+                    .collect(),
+            ),
+            // An edit clears the flash, instead of leaving it on the shifted text
+            Editor(EnterInsertMode(Direction::Start)),
+            Editor(Insert("x".to_string())),
+            Editor(EnterNormalMode),
+            ExpectMulti(
+                (5..10)
+                    .map(|column| ExpectKind::GridCellStyleKey(Position::new(1, column), None))
+                    .collect(),
+            ),
+            App(ToggleYankFlash),
+            Editor(MatchLiteral("main".to_string())),
+            Editor(Copy {
+                use_system_clipboard: false,
+            }),
+            Editor(MatchLiteral("fn".to_string())),
+            // The new copy replaces the previous flash, which has not expired, without flashing
+            Expect(GridCellStyleKey(Position::new(1, 5), None)),
+        ])
+    })
+}
+
+#[test]
+fn document_highlight() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let range = |start: usize, end: usize| Position::new(0, start)..Position::new(0, end);
+        let context = |path| crate::lsp::process::ResponseContext {
+            path: Some(path),
+            ..Default::default()
+        };
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn f(x: u8) { x }".to_string())),
+            Editor(SetRectangle(Rectangle {
+                origin: Position::default(),
+                width: 20,
+                height: 3,
+            })),
+            Editor(MatchLiteral("x".to_string())),
+            // A response for another file is ignored
+            App(HandleLspNotification(LspNotification::DocumentHighlight(
+                context(s.foo_rs()),
+                [range(5, 6), range(14, 15)].to_vec(),
+            ))),
+            Expect(GridCellStyleKey(Position::new(1, 16), None)),
+            App(HandleLspNotification(LspNotification::DocumentHighlight(
+                context(s.main_rs()),
+                [range(5, 6), range(14, 15)].to_vec(),
+            ))),
+            Expect(GridCellStyleKey(
+                Position::new(1, 16),
+                Some(StyleKey::DocumentHighlight),
+            )),
+            // The highlights are kept while the cursor is on one of the occurrences
+            App(HandleKeyEvent(key!("esc"))),
+            Expect(GridCellStyleKey(
+                Position::new(1, 16),
+                Some(StyleKey::DocumentHighlight),
+            )),
+            // The highlights are cleared once the cursor moves off the symbol
+            Editor(MatchLiteral("fn".to_string())),
+            App(HandleKeyEvent(key!("esc"))),
+            Expect(GridCellStyleKey(Position::new(1, 16), None)),
+        ])
+    })
+}
+
+#[test]
+fn insert_date_time() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo bar".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(CursorAddToAllSelections),
+            // A format without specifiers, so that the output does not depend on the current time
+            App(SetDateTimeFormat("100%% ".to_string())),
+            Editor(InsertDateTime),
+            Expect(CurrentComponentContent("100% foo 100% bar")),
+            Expect(CurrentSelectedTexts(&["foo", "bar"])),
+        ])
+    })
+}
+
+#[test]
+fn generate_uuid() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let path = s.gitignore();
+        Box::new([
+            App(OpenFile(s.gitignore())),
+            Editor(SetContent("a\nb".to_string())),
+            Editor(SetSelectionMode(LineTrimmed)),
+            Editor(CursorAddToAllSelections),
+            Editor(GenerateUuid { uppercase: true }),
+            Expect(CurrentSelectedTexts(&["a", "b"])),
+            App(SaveAll),
+            ExpectCustom(Box::new(move || {
+                let content = path.read().unwrap();
+                let uuids = content
+                    .lines()
+                    .map(|line| line[..line.len() - 1].to_string())
+                    .collect_vec();
+                let uuid_regex = regex::Regex::new(
+                    "^[0-9A-F]{8}-[0-9A-F]{4}-4[0-9A-F]{3}-[89AB][0-9A-F]{3}-[0-9A-F]{12}$",
+                )
+                .unwrap();
+                assert!(
+                    uuids.iter().all(|uuid| uuid_regex.is_match(uuid)),
+                    "{content}"
+                );
+                // Each cursor gets a distinct UUID
+                assert_ne!(uuids[0], uuids[1]);
+            })),
+        ])
+    })
+}
+
 #[test]
 fn empty_content_should_have_one_line() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -1709,6 +2280,20 @@ fn omit() -> Result<(), anyhow::Error> {
             Regex(R::new(r"^he")?),
             &["wehello"],
         ),
+        (
+            "foo bar\nspam TODO",
+            Keep,
+            LineContent,
+            Literal("todo".to_string()),
+            &["spam", "TODO"],
+        ),
+        (
+            "foo bar\nspam TODO",
+            Remove,
+            LineContent,
+            Literal("todo".to_string()),
+            &["foo", "bar"],
+        ),
     ];
     for case in cases.iter().cloned() {
         run_test(case)?;
@@ -1747,6 +2332,25 @@ fn swap_cursor_with_anchor() -> anyhow::Result<()> {
     })
 }
 
+#[test]
+fn align_cursors_to_anchor() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo bar spam".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(CursorAddToAllSelections),
+            Editor(AlignCursorsToAnchor(Direction::End)),
+            Expect(CurrentCursorDirection(Direction::End)),
+            // Aligning is idempotent, unlike swapping
+            Editor(AlignCursorsToAnchor(Direction::End)),
+            Expect(CurrentCursorDirection(Direction::End)),
+            Editor(AlignCursorsToAnchor(Direction::Start)),
+            Expect(CurrentCursorDirection(Direction::Start)),
+        ])
+    })
+}
+
 #[test]
 /// Line with emoji: not wrapped
 fn consider_unicode_width() -> anyhow::Result<()> {
@@ -1821,6 +2425,7 @@ fn next_prev_after_current_selection_is_deleted() -> anyhow::Result<()> {
                             escaped: false,
                             case_sensitive: false,
                             match_whole_word: false,
+                            multiline: false,
                         }),
                         search: r"\d+".to_string(),
                     },
@@ -1900,6 +2505,7 @@ fn main() {
                     escaped: true,
                     case_sensitive: false,
                     match_whole_word: false,
+                    multiline: false,
                 }),
             },
         },
@@ -1964,102 +2570,559 @@ fn select_surround_inside_with_multiwidth_character() -> Result<(), anyhow::Erro
 }
 
 #[test]
-fn select_surround_inside() -> Result<(), anyhow::Error> {
+fn delete_to_matching() -> Result<(), anyhow::Error> {
+    let run_test = |kind: SurroundKind, expected: &'static str| {
+        execute_test(|s| {
+            Box::new([
+                App(OpenFile(s.main_rs())),
+                Editor(SetContent("(hello (world)) (x (y))".to_string())),
+                Editor(MatchLiteral("rl".to_string())),
+                Editor(DeleteToMatching {
+                    enclosure: crate::surround::EnclosureKind::Parentheses,
+                    kind: kind.clone(),
+                }),
+                Expect(CurrentComponentContent(expected)),
+            ])
+        })
+    };
+    run_test(SurroundKind::Inside, "(hello ()) (x (y))")?;
+    run_test(SurroundKind::Around, "(hello ) (x (y))")
+}
+
+#[test]
+fn replace_inside_pair() -> Result<(), anyhow::Error> {
     execute_test(|s| {
         Box::new([
             App(OpenFile(s.main_rs())),
-            Editor(SetContent("(hello (world))".to_string())),
-            Editor(MatchLiteral("rl".to_string())),
-            Editor(SelectSurround {
-                enclosure: crate::surround::EnclosureKind::Parentheses,
-                kind: SurroundKind::Inside,
-            }),
-            Expect(CurrentSelectedTexts(&["world"])),
-            Expect(CurrentSelectionMode(SelectionMode::Custom)),
+            Editor(SetContent("f(\"hello world\")".to_string())),
+            Editor(MatchLiteral("world".to_string())),
+            Editor(ReplaceInsidePair(
+                crate::surround::EnclosureKind::DoubleQuotes,
+            )),
+            Expect(CurrentMode(Mode::Insert)),
+            Editor(Insert("bye".to_string())),
+            Expect(CurrentComponentContent("f(\"bye\")")),
         ])
     })
 }
 
 #[test]
-fn select_surround_around() -> Result<(), anyhow::Error> {
+fn wrap_selection_in_tag() -> Result<(), anyhow::Error> {
     execute_test(|s| {
         Box::new([
             App(OpenFile(s.main_rs())),
-            Editor(SetContent("(hello (world))".to_string())),
-            Editor(MatchLiteral("rl".to_string())),
-            Editor(SelectSurround {
-                enclosure: crate::surround::EnclosureKind::Parentheses,
-                kind: SurroundKind::Around,
-            }),
-            Expect(CurrentSelectedTexts(&["(world)"])),
-            Expect(CurrentSelectionMode(SelectionMode::Custom)),
+            Editor(SetContent("hello world; hello world".to_string())),
+            Editor(MatchLiteral("world".to_string())),
+            Editor(CursorAddToAllSelections),
+            Editor(WrapSelectionInTag("b".to_string())),
+            Expect(CurrentMode(Mode::Insert)),
+            Expect(CurrentComponentContent(
+                "hello <b>world</b>; hello <b>world</b>",
+            )),
+            Editor(Insert(" id".to_string())),
+            Expect(CurrentComponentContent(
+                "hello <b id>world</b>; hello <b id>world</b>",
+            )),
         ])
     })
 }
 
 #[test]
-fn select_surround_inside_same_symbols() -> Result<(), anyhow::Error> {
+fn rename_paired_tags() -> Result<(), anyhow::Error> {
     execute_test(|s| {
-        {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetLanguage(
+                shared::language::from_extension("tsx").unwrap(),
+            )),
+            Editor(SetContent("const x = <div>hello</div>".to_string())),
+            Editor(MatchLiteral("div".to_string())),
+            Editor(EnterInsertMode(Direction::End)),
+            Editor(Insert("x".to_string())),
+            Expect(CurrentComponentContent("const x = <divx>hello</divx>")),
+            Editor(Backspace),
+            Editor(Backspace),
+            Expect(CurrentComponentContent("const x = <di>hello</di>")),
+            Editor(EnterNormalMode),
+            Editor(MatchLiteral("/di".to_string())),
+            Editor(EnterInsertMode(Direction::End)),
+            Editor(Insert("v".to_string())),
+            Expect(CurrentComponentContent("const x = <div>hello</div>")),
+            Editor(Insert("x".to_string())),
+            Expect(CurrentComponentContent("const x = <divx>hello</divx>")),
+            // The paired rename is part of the same undo entry as the edit
+            Editor(EnterNormalMode),
+            Editor(Undo),
+            Expect(CurrentComponentContent("const x = <div>hello</div>")),
+        ])
+    })
+}
+
+#[test]
+fn rename_paired_html_and_xml_tags() -> Result<(), anyhow::Error> {
+    let run_test = |extension: &'static str| {
+        execute_test(|s| {
             Box::new([
                 App(OpenFile(s.main_rs())),
-                Editor(SetContent("hello 'world'".to_string())),
-                Editor(MatchLiteral("rl".to_string())),
-                Editor(DeleteSurround(crate::surround::EnclosureKind::SingleQuotes)),
-                Expect(CurrentSelectedTexts(&["world"])),
-                Expect(CurrentSelectionMode(SelectionMode::Custom)),
+                Editor(SetLanguage(
+                    shared::language::from_extension(extension).unwrap(),
+                )),
+                Editor(SetContent("<p>hello <b>world</b></p>".to_string())),
+                Editor(MatchLiteral("</b".to_string())),
+                Editor(EnterInsertMode(Direction::End)),
+                Editor(Insert("r".to_string())),
+                Expect(CurrentComponentContent("<p>hello <br>world</br></p>")),
+                Editor(Backspace),
+                Expect(CurrentComponentContent("<p>hello <b>world</b></p>")),
             ])
-        }
+        })
+    };
+    run_test("html")?;
+    run_test("xml")
+}
+
+#[test]
+fn evaluate_selection() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("x = 1 + 2 * 3; y = (1 + 2) * 3;".to_string())),
+            Editor(MatchLiteral("1 + 2 * 3".to_string())),
+            Editor(EvaluateSelection),
+            Expect(CurrentComponentContent("x = 7; y = (1 + 2) * 3;")),
+            Expect(CurrentSelectedTexts(&["7"])),
+            // Nothing is replaced if the selection is not an arithmetic expression
+            Editor(MatchLiteral("y = (1".to_string())),
+            Editor(EvaluateSelection),
+            Expect(EditorInfoOpen(true)),
+            Expect(CurrentComponentContent("x = 7; y = (1 + 2) * 3;")),
+        ])
     })
 }
 
 #[test]
-fn delete_surround() -> Result<(), anyhow::Error> {
+fn sum_selections() -> Result<(), anyhow::Error> {
     execute_test(|s| {
-        {
-            Box::new([
-                App(OpenFile(s.main_rs())),
-                Editor(SetContent("(hello (world))".to_string())),
-                Editor(MatchLiteral("rl".to_string())),
-                Editor(DeleteSurround(crate::surround::EnclosureKind::Parentheses)),
-                Expect(CurrentSelectedTexts(&["world"])),
-                Expect(CurrentSelectionMode(SelectionMode::Custom)),
-                Expect(CurrentComponentContent("(hello world)")),
-            ])
-        }
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("1 22 x 4".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(CursorAddToAllSelections),
+            Editor(SumSelections),
+            Expect(EditorInfoContent(
+                "Sum: 27\nAverage: 9\nCount: 3\nSkipped: \"x\"",
+            )),
+            // Words that parse as non-finite numbers are skipped
+            Editor(CursorKeepPrimaryOnly),
+            Editor(SetContent("1 inf nan 2".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(CursorAddToAllSelections),
+            Editor(SumSelections),
+            Expect(EditorInfoContent(
+                "Sum: 3\nAverage: 1.5\nCount: 2\nSkipped: \"inf\", \"nan\"",
+            )),
+        ])
     })
 }
 
 #[test]
-fn change_surround() -> Result<(), anyhow::Error> {
+fn sort_selections_by_content() -> Result<(), anyhow::Error> {
     execute_test(|s| {
-        {
-            Box::new([
-                App(OpenFile(s.main_rs())),
-                Editor(SetContent("(hello (world))".to_string())),
-                Editor(MatchLiteral("rl".to_string())),
-                Editor(ChangeSurround {
-                    from: crate::surround::EnclosureKind::Parentheses,
-                    to: crate::surround::EnclosureKind::CurlyBraces,
-                }),
-                Expect(CurrentSelectedTexts(&["{world}"])),
-                Expect(CurrentSelectionMode(SelectionMode::Custom)),
-                Expect(CurrentComponentContent("(hello {world})")),
-            ])
-        }
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("pear, fig, apple".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(CursorAddToAllSelections),
+            Editor(SortSelectionsByContent),
+            Expect(CurrentComponentContent("apple, fig, pear")),
+            Expect(CurrentSelectedTexts(&["apple", "fig", "pear"])),
+        ])
     })
 }
 
 #[test]
-fn replace_with_pattern() -> Result<(), anyhow::Error> {
-    fn run_test(
-        mode: LocalSearchConfigMode,
-        content: &str,
-        search_pattern: &str,
-        replace_pattern: &str,
-        expected_content: &'static str,
-        expected_selected_text: &'static [&'static str],
-    ) -> anyhow::Result<()> {
+fn replace_with_register_from_history() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo bar spam".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(Copy {
+                use_system_clipboard: false,
+            }),
+            Editor(MoveSelection(Next)),
+            Editor(Copy {
+                use_system_clipboard: false,
+            }),
+            Editor(MoveSelection(Next)),
+            Editor(ReplaceWithRegisterFromHistory),
+            App(HandleKeyEvents(keys!("f o o enter").to_vec())),
+            Expect(CurrentComponentContent("foo bar foo")),
+        ])
+    })
+}
+
+#[test]
+fn select_word_under_cursor_occurrences() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "fn f() { let x = 1; x + xs } fn g() { x }".to_string(),
+            )),
+            Editor(MatchLiteral("x = 1".to_string())),
+            Editor(SelectWordUnderCursorOccurrences),
+            // `xs` and the `x` of another function are not selected
+            Expect(CurrentSelectedTexts(&["x", "x"])),
+            Editor(Change),
+            Editor(Insert("y".to_string())),
+            Expect(CurrentComponentContent(
+                "fn f() { let y = 1; y + xs } fn g() { x }",
+            )),
+            // The word chars of the language are respected
+            Editor(SetLanguage(shared::language::from_extension("js").unwrap())),
+            Editor(SetContent("$foo + foo + $foo".to_string())),
+            Editor(MatchLiteral("$foo".to_string())),
+            Editor(SelectWordUnderCursorOccurrences),
+            Expect(CurrentSelectedTexts(&["$foo", "$foo"])),
+        ])
+    })
+}
+
+#[test]
+fn select_to_bookmark() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("a b c d e".to_string())),
+            Editor(MatchLiteral("d".to_string())),
+            Editor(ToggleBookmark),
+            Editor(MatchLiteral("b".to_string())),
+            Editor(SelectToBookmark(Direction::End)),
+            Expect(CurrentSelectedTexts(&["b c d"])),
+            Expect(CurrentSelectionMode(Custom)),
+            Expect(EditorCursorPosition(Position::new(0, 6))),
+            // The nearest bookmark is used
+            Editor(MatchLiteral("b".to_string())),
+            Editor(ToggleBookmark),
+            Editor(MatchLiteral("e".to_string())),
+            Editor(SelectToBookmark(Direction::Start)),
+            Expect(CurrentSelectedTexts(&["d e"])),
+            Expect(EditorCursorPosition(Position::new(0, 6))),
+            // No bookmark after the cursor
+            Editor(SelectToBookmark(Direction::End)),
+            Expect(CurrentSelectedTexts(&["d e"])),
+        ])
+    })
+}
+
+#[test]
+fn toggle_line_ending() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("a\nb\n".to_string())),
+            Editor(ToggleLineEnding),
+            Expect(CurrentComponentContent("a\r\nb\r\n")),
+            Editor(ToggleLineEnding),
+            Expect(CurrentComponentContent("a\nb\n")),
+        ])
+    })
+}
+
+#[test]
+fn go_to_matching_tag() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetLanguage(
+                shared::language::from_extension("tsx").unwrap(),
+            )),
+            Editor(SetContent(
+                "const x = <div><span>hi</span></div>".to_string(),
+            )),
+            // The innermost enclosing element is used
+            Editor(MatchLiteral("hi".to_string())),
+            Editor(GoToMatchingTag),
+            Expect(CurrentSelectedTexts(&["span"])),
+            Expect(EditorCursorPosition(Position::new(0, 25))),
+            Editor(GoToMatchingTag),
+            Expect(EditorCursorPosition(Position::new(0, 16))),
+            Editor(GoToMatchingTag),
+            Expect(EditorCursorPosition(Position::new(0, 25))),
+            Editor(MatchLiteral("<div".to_string())),
+            Editor(GoToMatchingTag),
+            Expect(CurrentSelectedTexts(&["div"])),
+            Expect(EditorCursorPosition(Position::new(0, 32))),
+            // Outside of any element
+            Editor(MatchLiteral("const".to_string())),
+            Editor(GoToMatchingTag),
+            Expect(EditorInfoOpen(true)),
+            Expect(CurrentSelectedTexts(&["const"])),
+        ])
+    })
+}
+
+#[test]
+fn select_next_occurrence_in_selection() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("b1 (b2 b3) b4".to_string())),
+            App(UpdateLocalSearchConfig {
+                update: LocalSearchConfigUpdate::Search("b".to_string()),
+                scope: Scope::Local,
+                show_config_after_enter: false,
+            }),
+            Editor(MatchLiteral("(b2 b3)".to_string())),
+            Editor(SelectNextOccurrenceInSelection),
+            Expect(CurrentSelectedTexts(&["b"])),
+            Expect(EditorCursorPosition(Position::new(0, 4))),
+            Editor(MoveSelection(Next)),
+            Expect(EditorCursorPosition(Position::new(0, 7))),
+            // Should stop at the boundary of the selection
+            Editor(MoveSelection(Next)),
+            Expect(EditorCursorPosition(Position::new(0, 7))),
+            Editor(FilterClear),
+            Editor(MoveSelection(Next)),
+            Expect(EditorCursorPosition(Position::new(0, 12))),
+        ])
+    })
+}
+
+#[test]
+fn pipe_selection() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("hello world; hello world".to_string())),
+            Editor(MatchLiteral("world".to_string())),
+            Editor(CursorAddToAllSelections),
+            Editor(PipeSelection("tr a-z A-Z".to_string())),
+            Expect(CurrentComponentContent("hello WORLD; hello WORLD")),
+            Expect(CurrentSelectedTexts(&["WORLD", "WORLD"])),
+            // Failing commands should not modify the selections
+            Editor(PipeSelection("echo oops >&2; exit 1".to_string())),
+            Expect(CurrentComponentContent("hello WORLD; hello WORLD")),
+            Expect(EditorInfoContent("oops")),
+        ])
+    })
+}
+
+#[test]
+fn insert_shell_output() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("hello world".to_string())),
+            Editor(MatchLiteral("world".to_string())),
+            Editor(InsertShellOutput("echo big".to_string())),
+            App(HandleNextAppMessage),
+            Expect(CurrentComponentContent("hello bigworld")),
+            Expect(CurrentSelectedTexts(&["world"])),
+            Editor(SwapCursorWithAnchor),
+            Editor(InsertShellOutput("printf '!'".to_string())),
+            App(HandleNextAppMessage),
+            Expect(CurrentComponentContent("hello bigworld!")),
+            Expect(CurrentSelectedTexts(&["world"])),
+            // Failing commands should not modify the content
+            Editor(InsertShellOutput("exit 1".to_string())),
+            App(HandleNextAppMessage),
+            Expect(CurrentComponentContent("hello bigworld!")),
+            // Via the prompt
+            Editor(MatchLiteral("hello".to_string())),
+            App(OpenInsertShellOutputPrompt),
+            Editor(Insert("printf '?'".to_string())),
+            App(HandleKeyEvents(keys!("enter").to_vec())),
+            App(HandleNextAppMessage),
+            Expect(CurrentComponentContent("?hello bigworld!")),
+        ])
+    })
+}
+
+#[test]
+fn toggle_case_of_char() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("a1B c".to_string())),
+            Editor(SetSelectionMode(Column)),
+            Editor(ToggleCaseOfChar),
+            Expect(CurrentComponentContent("A1B c")),
+            Expect(CurrentSelectedTexts(&["1"])),
+            // Non-alphabetic characters are skipped over
+            Editor(ToggleCaseOfChar),
+            Expect(CurrentComponentContent("A1B c")),
+            Editor(ToggleCaseOfChar),
+            Expect(CurrentComponentContent("A1b c")),
+            // Works with multiple cursors
+            Editor(SetContent("ab cd".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(CursorAddToAllSelections),
+            Editor(ToggleCaseOfChar),
+            Expect(CurrentComponentContent("Ab Cd")),
+            Expect(CurrentSelectedTexts(&["b", "d"])),
+            Editor(ToggleCaseOfChar),
+            Expect(CurrentComponentContent("AB CD")),
+            // The last character stays selected
+            Expect(CurrentSelectedTexts(&[" ", "D"])),
+        ])
+    })
+}
+
+#[test]
+fn expand_snippet() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(SetSnippets(crate::snippet::Snippets::new(&[(
+                "rust",
+                "fn",
+                "fn ${1:name}($2) {\n    $0\n}",
+            )]))),
+            Editor(SetContent("".to_string())),
+            Editor(EnterInsertMode(Direction::Start)),
+            Editor(Insert("fn".to_string())),
+            Editor(ExpandSnippet),
+            Expect(CurrentComponentContent("fn name() {\n    \n}")),
+            Expect(CurrentSelectedTexts(&["name"])),
+            Expect(CurrentMode(Mode::Normal)),
+            Editor(Change),
+            Editor(Insert("main".to_string())),
+            // "main" is not the prefix of any snippet, so go to the next tabstop
+            Editor(ExpandSnippet),
+            Expect(CurrentMode(Mode::Insert)),
+            Editor(Insert("x: X".to_string())),
+            Editor(ExpandSnippet),
+            Editor(Insert("todo!()".to_string())),
+            Expect(CurrentComponentContent("fn main(x: X) {\n    todo!()\n}")),
+            // There is no more tabstops, so insert a tab
+            Editor(ExpandSnippet),
+            Expect(CurrentComponentContent("fn main(x: X) {\n    todo!()\t\n}")),
+        ])
+    })
+}
+
+#[test]
+fn leaving_insert_mode_should_forget_the_tabstops() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(SetSnippets(crate::snippet::Snippets::new(&[(
+                "rust", "pair", "($1, $2)",
+            )]))),
+            Editor(SetContent("".to_string())),
+            Editor(EnterInsertMode(Direction::Start)),
+            Editor(Insert("pair".to_string())),
+            Editor(ExpandSnippet),
+            Expect(CurrentComponentContent("(, )")),
+            Expect(CurrentMode(Mode::Insert)),
+            Editor(EnterNormalMode),
+            Editor(EnterInsertMode(Direction::Start)),
+            // The remaining tabstop is forgotten, so a tab is inserted instead of going to it
+            Editor(ExpandSnippet),
+            Expect(CurrentComponentContent("\t(, )")),
+        ])
+    })
+}
+
+#[test]
+fn select_surround_inside() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("(hello (world))".to_string())),
+            Editor(MatchLiteral("rl".to_string())),
+            Editor(SelectSurround {
+                enclosure: crate::surround::EnclosureKind::Parentheses,
+                kind: SurroundKind::Inside,
+            }),
+            Expect(CurrentSelectedTexts(&["world"])),
+            Expect(CurrentSelectionMode(SelectionMode::Custom)),
+        ])
+    })
+}
+
+#[test]
+fn select_surround_around() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("(hello (world))".to_string())),
+            Editor(MatchLiteral("rl".to_string())),
+            Editor(SelectSurround {
+                enclosure: crate::surround::EnclosureKind::Parentheses,
+                kind: SurroundKind::Around,
+            }),
+            Expect(CurrentSelectedTexts(&["(world)"])),
+            Expect(CurrentSelectionMode(SelectionMode::Custom)),
+        ])
+    })
+}
+
+#[test]
+fn select_surround_inside_same_symbols() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        {
+            Box::new([
+                App(OpenFile(s.main_rs())),
+                Editor(SetContent("hello 'world'".to_string())),
+                Editor(MatchLiteral("rl".to_string())),
+                Editor(DeleteSurround(crate::surround::EnclosureKind::SingleQuotes)),
+                Expect(CurrentSelectedTexts(&["world"])),
+                Expect(CurrentSelectionMode(SelectionMode::Custom)),
+            ])
+        }
+    })
+}
+
+#[test]
+fn delete_surround() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        {
+            Box::new([
+                App(OpenFile(s.main_rs())),
+                Editor(SetContent("(hello (world))".to_string())),
+                Editor(MatchLiteral("rl".to_string())),
+                Editor(DeleteSurround(crate::surround::EnclosureKind::Parentheses)),
+                Expect(CurrentSelectedTexts(&["world"])),
+                Expect(CurrentSelectionMode(SelectionMode::Custom)),
+                Expect(CurrentComponentContent("(hello world)")),
+            ])
+        }
+    })
+}
+
+#[test]
+fn change_surround() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        {
+            Box::new([
+                App(OpenFile(s.main_rs())),
+                Editor(SetContent("(hello (world))".to_string())),
+                Editor(MatchLiteral("rl".to_string())),
+                Editor(ChangeSurround {
+                    from: crate::surround::EnclosureKind::Parentheses,
+                    to: crate::surround::EnclosureKind::CurlyBraces,
+                }),
+                Expect(CurrentSelectedTexts(&["{world}"])),
+                Expect(CurrentSelectionMode(SelectionMode::Custom)),
+                Expect(CurrentComponentContent("(hello {world})")),
+            ])
+        }
+    })
+}
+
+#[test]
+fn replace_with_pattern() -> Result<(), anyhow::Error> {
+    fn run_test(
+        mode: LocalSearchConfigMode,
+        content: &str,
+        search_pattern: &str,
+        replace_pattern: &str,
+        expected_content: &'static str,
+        expected_selected_text: &'static [&'static str],
+    ) -> anyhow::Result<()> {
         execute_test(|s| {
             {
                 Box::new([
@@ -2100,6 +3163,7 @@ fn replace_with_pattern() -> Result<(), anyhow::Error> {
             escaped: false,
             case_sensitive: false,
             match_whole_word: false,
+            multiline: false,
         }),
         "ali_123 abu_456 adam_99",
         r"abu_(\d+)",
@@ -2244,3 +3308,400 @@ fn multi_cursor_insert() -> Result<(), anyhow::Error> {
         }
     })
 }
+
+#[test]
+fn go_to_first_non_whitespace() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("  foo bar\n    qux".to_string())),
+            Editor(MatchLiteral("bar".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Expect(CurrentSelectedTexts(&["bar"])),
+            Editor(GoToFirstNonWhitespace),
+            // Reselected using the current selection mode
+            Expect(CurrentSelectedTexts(&["foo"])),
+            Expect(CurrentSelectionMode(WordShort)),
+            // A multi-line selection is respected
+            Editor(MatchLiteral("bar\n    qux".to_string())),
+            Editor(GoToFirstNonWhitespace),
+            Expect(CurrentSelectedTexts(&["b"])),
+            Editor(MatchLiteral("bar\n    qux".to_string())),
+            Editor(SwapCursorWithAnchor),
+            Editor(GoToFirstNonWhitespace),
+            Expect(CurrentSelectedTexts(&["q"])),
+        ])
+    })
+}
+
+#[test]
+fn select_lines_matching() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo 1\nbar\nfoo 2\nbaz\nfoo 3\n".to_string())),
+            Editor(SelectLinesMatching("^foo".to_string())),
+            Expect(CurrentSelectedTexts(&["foo 1\n", "foo 2\n", "foo 3\n"])),
+            Expect(CurrentSelectionMode(LineFull)),
+            Editor(Delete { backward: false }),
+            Expect(CurrentComponentContent("bar\nbaz\n")),
+            // Only the lines of a multi-line selection are considered
+            Editor(SetContent("foo 1\nbar\nfoo 2\nfoo 3".to_string())),
+            Editor(MatchLiteral("bar\nfoo 2".to_string())),
+            Editor(SelectLinesMatching("foo".to_string())),
+            Expect(CurrentSelectedTexts(&["foo 2\n"])),
+        ])
+    })
+}
+
+#[test]
+fn select_lines_not_matching() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo 1\nbar\nfoo 2\nbaz\n".to_string())),
+            Editor(SelectLinesNotMatching("^foo".to_string())),
+            Expect(CurrentSelectedTexts(&["bar\n", "baz\n"])),
+            Editor(Delete { backward: false }),
+            Expect(CurrentComponentContent("foo 1\nfoo 2\n")),
+            // Only the lines of a multi-line selection are considered
+            Editor(SetContent("bar\nfoo 1\nbaz\nqux".to_string())),
+            Editor(MatchLiteral("foo 1\nbaz".to_string())),
+            Editor(SelectLinesNotMatching("foo".to_string())),
+            Expect(CurrentSelectedTexts(&["baz\n"])),
+        ])
+    })
+}
+
+#[test]
+fn select_by_query() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "fn foo() {}\nfn bar() { let x = 1; }".to_string(),
+            )),
+            Editor(SelectByQuery(
+                "(function_item name: (identifier) @function.name)".to_string(),
+            )),
+            Expect(CurrentSelectedTexts(&["foo", "bar"])),
+            // Invalid query is reported
+            Editor(SelectByQuery("(function_item".to_string())),
+            Expect(EditorInfoOpen(true)),
+            Expect(CurrentSelectedTexts(&["foo", "bar"])),
+        ])
+    })
+}
+
+#[test]
+fn select_enclosing_string_and_comment() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                r#"let x = "hello world"; // a comment"#.to_string(),
+            )),
+            Editor(MatchLiteral("world".to_string())),
+            Editor(SelectEnclosingString {
+                include_delimiters: true,
+            }),
+            Expect(CurrentSelectedTexts(&[r#""hello world""#])),
+            Editor(MatchLiteral("world".to_string())),
+            Editor(SelectEnclosingString {
+                include_delimiters: false,
+            }),
+            Expect(CurrentSelectedTexts(&["hello world"])),
+            Editor(MatchLiteral("comment".to_string())),
+            Editor(SelectEnclosingComment {
+                include_delimiters: false,
+            }),
+            Expect(CurrentSelectedTexts(&["a comment"])),
+            Editor(SelectEnclosingComment {
+                include_delimiters: true,
+            }),
+            Expect(CurrentSelectedTexts(&["// a comment"])),
+            // Not inside a string
+            Editor(MatchLiteral("let".to_string())),
+            Editor(SelectEnclosingString {
+                include_delimiters: true,
+            }),
+            Expect(EditorInfoOpen(true)),
+            Expect(CurrentSelectedTexts(&["let"])),
+        ])
+    })
+}
+
+#[test]
+fn navigate_to_import_block() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "//! Doc\nuse a::b;\n// Comment\nuse c::d;\n\nfn main() {}\nuse e::f;".to_string(),
+            )),
+            Editor(MatchLiteral("main".to_string())),
+            Editor(NavigateToImportBlock),
+            Expect(CurrentSelectedTexts(&["use a::b;\n// Comment\nuse c::d;"])),
+            Editor(SetContent("fn main() {}".to_string())),
+            Editor(NavigateToImportBlock),
+            Expect(EditorInfoOpen(true)),
+        ])
+    })
+}
+
+#[test]
+fn sort_imports() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "use crate::foo;\nuse std::io;\n// Comment\nuse anyhow::Result;\nuse std::fmt;\n\nfn main() {}"
+                    .to_string(),
+            )),
+            Editor(SortImports),
+            Expect(CurrentComponentContent(
+                "use std::fmt;\nuse std::io;\n\n// Comment\nuse anyhow::Result;\n\nuse crate::foo;\n\nfn main() {}",
+            )),
+            App(ToggleGroupImports),
+            Editor(SortImports),
+            Expect(CurrentComponentContent(
+                "// Comment\nuse anyhow::Result;\nuse crate::foo;\nuse std::fmt;\nuse std::io;\n\nfn main() {}",
+            )),
+        ])
+    })
+}
+
+#[test]
+fn toggle_line_comment_keeps_cursor_on_the_same_character() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "fn main() {\n    foo();\n\n      bar();\n}".to_string(),
+            )),
+            Editor(MatchLiteral("foo".to_string())),
+            Editor(ToggleLineComment),
+            Expect(CurrentComponentContent(
+                "fn main() {\n    // foo();\n\n      bar();\n}",
+            )),
+            Expect(CurrentSelectedTexts(&["foo"])),
+            Editor(ToggleLineComment),
+            Expect(CurrentComponentContent(
+                "fn main() {\n    foo();\n\n      bar();\n}",
+            )),
+            Expect(CurrentSelectedTexts(&["foo"])),
+            // Lines are commented at the smallest indentation, and blank lines are skipped
+            Editor(MatchLiteral("foo();\n\n      bar".to_string())),
+            Editor(ToggleLineComment),
+            Expect(CurrentComponentContent(
+                "fn main() {\n    // foo();\n\n    //   bar();\n}",
+            )),
+            Expect(CurrentSelectedTexts(&["foo();\n\n    //   bar"])),
+            Editor(ToggleLineComment),
+            Expect(CurrentComponentContent(
+                "fn main() {\n    foo();\n\n      bar();\n}",
+            )),
+            Expect(CurrentSelectedTexts(&["foo();\n\n      bar"])),
+        ])
+    })
+}
+
+#[test]
+fn toggle_line_comment_does_not_mistake_doc_comments_for_comments() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("/// doc\nfn f() {}".to_string())),
+            Editor(MatchLiteral("doc".to_string())),
+            Editor(ToggleLineComment),
+            Expect(CurrentComponentContent("// /// doc\nfn f() {}")),
+            Editor(ToggleLineComment),
+            Expect(CurrentComponentContent("/// doc\nfn f() {}")),
+        ])
+    })
+}
+
+#[test]
+fn select_next_modified_region() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.gitignore())),
+            Editor(SetContent("a\nb\nc\nd\ne\n".to_string())),
+            Editor(Save),
+            Editor(SetContent("a\nB\nc\nd\nE\n".to_string())),
+            Editor(SelectNextModifiedRegion),
+            Expect(CurrentSelectedTexts(&["B\n"])),
+            Editor(SelectNextModifiedRegion),
+            Expect(CurrentSelectedTexts(&["E\n"])),
+            // Wrap around
+            Editor(SelectNextModifiedRegion),
+            Expect(CurrentSelectedTexts(&["B\n"])),
+            // The byte order mark of the saved file is not a modification
+            Editor(ConvertEncoding(crate::encoding::Encoding::Utf8Bom)),
+            Editor(SetContent("a\nB\nc\nd\nx\n".to_string())),
+            Editor(SelectNextModifiedRegion),
+            Expect(CurrentSelectedTexts(&["x\n"])),
+            Editor(SelectNextModifiedRegion),
+            Expect(CurrentSelectedTexts(&["x\n"])),
+        ])
+    })
+}
+
+#[test]
+fn expand_and_shrink_selection() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        let range = |start: usize, end: usize| Position::new(0, start)..Position::new(0, end);
+        let context = |path| crate::lsp::process::ResponseContext {
+            path: Some(path),
+            ..Default::default()
+        };
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("fn main() { let x = 1 + 2; }".to_string())),
+            // Without a language server, the tree-sitter nodes are used
+            Editor(MatchLiteral("1".to_string())),
+            Editor(ExpandSelection),
+            Expect(CurrentSelectedTexts(&["1 + 2"])),
+            Editor(ExpandSelection),
+            Expect(CurrentSelectedTexts(&["let x = 1 + 2;"])),
+            Editor(ShrinkSelection),
+            Expect(CurrentSelectedTexts(&["1 + 2"])),
+            Editor(ShrinkSelection),
+            Expect(CurrentSelectedTexts(&["1"])),
+            // The ranges of the language server are used when they enclose the selection
+            Editor(MatchLiteral("x".to_string())),
+            // A response for another file is ignored
+            App(HandleLspNotification(LspNotification::SelectionRange(
+                context(s.foo_rs()),
+                [range(16, 17), range(0, 26)].to_vec(),
+            ))),
+            Expect(CurrentSelectedTexts(&["x"])),
+            App(HandleLspNotification(LspNotification::SelectionRange(
+                context(s.main_rs()),
+                [range(16, 17), range(12, 26)].to_vec(),
+            ))),
+            Expect(CurrentSelectedTexts(&["let x = 1 + 2;"])),
+            Editor(ShrinkSelection),
+            Expect(CurrentSelectedTexts(&["x"])),
+            // The kept ranges are forgotten once the selection moves away from them,
+            // even if one of them encloses the new selection
+            Editor(MatchLiteral("2".to_string())),
+            Editor(ExpandSelection),
+            Expect(CurrentSelectedTexts(&["1 + 2"])),
+        ])
+    })
+}
+
+#[test]
+fn select_function_body() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "fn main() {\n    let x = 1;\n    let f = |y| y + 1;\n}".to_string(),
+            )),
+            Editor(MatchLiteral("x".to_string())),
+            Editor(SelectFunctionBody),
+            Expect(CurrentSelectedTexts(&[
+                "let x = 1;\n    let f = |y| y + 1;",
+            ])),
+            // Expression body
+            Editor(MatchLiteral("y +".to_string())),
+            Editor(SelectFunctionBody),
+            Expect(CurrentSelectedTexts(&["y + 1"])),
+            // Not inside a function
+            Editor(SetContent("struct A;".to_string())),
+            Editor(MatchLiteral("A".to_string())),
+            Editor(SelectFunctionBody),
+            Expect(EditorInfoOpen(true)),
+            Expect(CurrentSelectedTexts(&["A"])),
+        ])
+    })
+}
+
+#[test]
+fn move_selection_to_column() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("\tfoo bar\nabc".to_string())),
+            Editor(MatchLiteral("bar".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            // The tab occupies the first 4 columns
+            Editor(MoveSelectionToColumn(4)),
+            Expect(CurrentSelectedTexts(&["foo"])),
+            Expect(CurrentSelectionMode(WordShort)),
+            // Clamped to the end of the line
+            Editor(MoveSelectionToColumn(100)),
+            Expect(CurrentSelectedTexts(&["bar"])),
+        ])
+    })
+}
+
+#[test]
+fn go_to_byte_offset() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("héllo\nwörld".to_string())),
+            Editor(SetSelectionMode(Column)),
+            // "é" is 2 bytes, so byte 3 is the first "l"
+            Editor(GoToByteOffset(3)),
+            Expect(CurrentSelectedTexts(&["l"])),
+            // A byte offset within a multi-byte character goes to the character
+            Editor(GoToByteOffset(9)),
+            Expect(CurrentSelectedTexts(&["ö"])),
+            // Clamped to the length of the buffer
+            Editor(GoToByteOffset(100)),
+            Expect(EditorCursorPosition(Position { line: 1, column: 5 })),
+        ])
+    })
+}
+
+#[test]
+fn go_to_paragraph_boundary() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("a\nb\n\n  \nc\nd\n\ne".to_string())),
+            Editor(SetSelectionMode(Column)),
+            Editor(GoToParagraphBoundary(Direction::End)),
+            Expect(EditorCursorPosition(Position { line: 2, column: 0 })),
+            // Consecutive blank lines, including whitespace-only lines, are skipped
+            Editor(GoToParagraphBoundary(Direction::End)),
+            Expect(EditorCursorPosition(Position { line: 6, column: 0 })),
+            // The last line is the boundary if there is no more blank line
+            Editor(GoToParagraphBoundary(Direction::End)),
+            Expect(EditorCursorPosition(Position { line: 7, column: 0 })),
+            Editor(GoToParagraphBoundary(Direction::Start)),
+            Expect(EditorCursorPosition(Position { line: 6, column: 0 })),
+            // Consecutive blank lines are skipped backward as well
+            Editor(GoToParagraphBoundary(Direction::Start)),
+            Expect(EditorCursorPosition(Position { line: 3, column: 0 })),
+            Editor(GoToParagraphBoundary(Direction::Start)),
+            Expect(EditorCursorPosition(Position { line: 0, column: 0 })),
+            // The blank line right after the paragraph of the cursor is the boundary
+            Editor(MatchLiteral("d".to_string())),
+            Editor(GoToParagraphBoundary(Direction::End)),
+            Expect(EditorCursorPosition(Position { line: 6, column: 0 })),
+        ])
+    })
+}
+
+#[test]
+fn jump_to_percent_of_line() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("0123456789\nabc".to_string())),
+            Editor(MatchLiteral("0".to_string())),
+            Editor(SetSelectionMode(Column)),
+            Editor(JumpToPercentOfLine(50)),
+            Expect(CurrentSelectedTexts(&["5"])),
+            Editor(JumpToPercentOfLine(100)),
+            Expect(CurrentSelectedTexts(&["9"])),
+            Editor(JumpToPercentOfLine(0)),
+            Expect(CurrentSelectedTexts(&["0"])),
+        ])
+    })
+}
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, path::PathBuf};
 
 use globset::Glob;
 
@@ -8,10 +8,15 @@ use shared::canonicalized_path::CanonicalizedPath;
 
 use crate::{
     app::{GlobalSearchConfigUpdate, GlobalSearchFilterGlob, LocalSearchConfigUpdate, Scope},
+    char_info::CharacterReplacement,
     clipboard::{Clipboard, CopiedTexts},
-    components::{keymap_legend::KeymapLegendSection, prompt::PromptHistoryKey},
-    list::grep::RegexConfig,
+    components::{editor::Direction, keymap_legend::KeymapLegendSection, prompt::PromptHistoryKey},
+    list::{grep::RegexConfig, todo::TodoTag},
     quickfix_list::DiagnosticSeverityRange,
+    recent_files::RecentFiles,
+    search_preset::{SearchPreset, SearchPresets},
+    selection::SelectionMode,
+    snippet::Snippets,
     themes::Theme,
 };
 
@@ -20,7 +25,6 @@ pub(crate) struct Context {
     mode: Option<GlobalMode>,
     theme: Theme,
 
-    #[cfg(test)]
     highlight_configs: crate::syntax_highlight::HighlightConfigs,
     current_working_directory: CanonicalizedPath,
     local_search_config: LocalSearchConfig,
@@ -28,6 +32,43 @@ pub(crate) struct Context {
     quickfix_list_state: Option<QuickfixListState>,
     contextual_keymaps: Vec<KeymapLegendSection>,
     prompt_histories: HashMap<PromptHistoryKey, IndexSet<String>>,
+    smooth_scroll: bool,
+    /// Whether the file explorer is kept open as a sidebar, see [`crate::layout::Layout::recalculate_layout`].
+    file_explorer_sidebar: bool,
+    show_indent_guides: bool,
+    /// Whether sorting imports also groups them, see [`crate::buffer::Buffer::get_edit_transaction_of_sort_imports`].
+    group_imports: bool,
+    /// Whether exchange and replace (raise) skip candidates that break the syntax tree,
+    /// turning it off allows deliberately creating an intermediate invalid state.
+    validate_structural_edits: bool,
+    /// Whether multi-line text pasted into a buffer of a known language
+    /// is reindented to the line it is pasted into, see [`crate::transformation::reindent`].
+    /// The pasted lines are then formatted in the background with the formatter of the language,
+    /// or with the language server if the language has no formatter.
+    reindent_on_paste: bool,
+    /// Whether the copied ranges are highlighted briefly, for `yank_flash_duration`.
+    yank_flash: bool,
+    yank_flash_duration: std::time::Duration,
+    /// The strftime-style format of [`crate::components::editor::DispatchEditor::InsertDateTime`].
+    date_time_format: String,
+    /// Whether the inserted date/time is in UTC instead of the local timezone.
+    date_time_utc: bool,
+    /// Whether the other occurrences of the symbol under the cursor are highlighted,
+    /// using `textDocument/documentHighlight` of the language server.
+    document_highlight: bool,
+    /// The cursor direction applied when switching to a selection mode,
+    /// which is [`Direction::Start`] for selection modes that are not in this map.
+    default_cursor_directions: HashMap<std::mem::Discriminant<SelectionMode>, Direction>,
+    /// See [`crate::buffer::DEFAULT_INCREMENTAL_REPARSE_THRESHOLD`].
+    incremental_reparse_threshold: usize,
+    /// See [`crate::buffer::DEFAULT_LARGE_FILE_THRESHOLD`].
+    large_file_threshold: usize,
+    snippets: Snippets,
+    search_presets: SearchPresets,
+    recent_files: RecentFiles,
+    todo_tags: Vec<TodoTag>,
+    character_replacements: Vec<CharacterReplacement>,
+    final_newline: FinalNewline,
 }
 
 pub(crate) struct QuickfixListState {
@@ -53,6 +94,54 @@ impl GlobalMode {
     }
 }
 
+/// How the trailing newlines of a file are normalized when it is saved.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub(crate) enum FinalNewline {
+    /// End the file with exactly one newline, as per the POSIX convention.
+    #[default]
+    Ensure,
+    /// Remove every trailing newline.
+    Trim,
+    /// Leave the trailing newlines untouched.
+    Preserve,
+}
+
+impl FinalNewline {
+    pub(crate) fn display(&self) -> &'static str {
+        match self {
+            FinalNewline::Ensure => "ensure one",
+            FinalNewline::Trim => "trim",
+            FinalNewline::Preserve => "preserve",
+        }
+    }
+
+    fn next(self) -> FinalNewline {
+        match self {
+            FinalNewline::Ensure => FinalNewline::Trim,
+            FinalNewline::Trim => FinalNewline::Preserve,
+            FinalNewline::Preserve => FinalNewline::Ensure,
+        }
+    }
+
+    /// The newline of the file is kept, that is a file ending with `\r\n` stays so.
+    pub(crate) fn normalize(self, content: String) -> String {
+        let trimmed = content.trim_end_matches(['\r', '\n']);
+        match self {
+            FinalNewline::Preserve => content,
+            FinalNewline::Trim => trimmed.to_string(),
+            FinalNewline::Ensure if content.is_empty() => content,
+            FinalNewline::Ensure => {
+                let newline = if content.ends_with("\r\n") {
+                    "\r\n"
+                } else {
+                    "\n"
+                };
+                format!("{}{}", trimmed, newline)
+            }
+        }
+    }
+}
+
 #[derive(Clone, PartialEq, Eq, Debug)]
 pub(crate) struct Search {
     pub(crate) mode: LocalSearchConfigMode,
@@ -65,7 +154,6 @@ impl Default for Context {
             clipboard: Clipboard::new(),
             theme: Theme::default(),
             mode: None,
-            #[cfg(test)]
             highlight_configs: crate::syntax_highlight::HighlightConfigs::new(),
             current_working_directory: CanonicalizedPath::try_from(".").unwrap(),
             local_search_config: LocalSearchConfig::default(),
@@ -73,6 +161,26 @@ impl Default for Context {
             quickfix_list_state: Default::default(),
             contextual_keymaps: Default::default(),
             prompt_histories: Default::default(),
+            smooth_scroll: false,
+            file_explorer_sidebar: false,
+            show_indent_guides: false,
+            group_imports: true,
+            validate_structural_edits: true,
+            reindent_on_paste: false,
+            yank_flash: true,
+            yank_flash_duration: std::time::Duration::from_millis(150),
+            date_time_format: crate::date_time::DEFAULT_FORMAT.to_string(),
+            date_time_utc: false,
+            document_highlight: true,
+            default_cursor_directions: Default::default(),
+            incremental_reparse_threshold: crate::buffer::DEFAULT_INCREMENTAL_REPARSE_THRESHOLD,
+            large_file_threshold: crate::buffer::DEFAULT_LARGE_FILE_THRESHOLD,
+            snippets: Default::default(),
+            search_presets: Default::default(),
+            recent_files: Default::default(),
+            todo_tags: TodoTag::defaults(),
+            character_replacements: CharacterReplacement::defaults(),
+            final_newline: FinalNewline::default(),
         }
     }
 }
@@ -122,7 +230,6 @@ impl Context {
         Self { theme, ..self }
     }
 
-    #[cfg(test)]
     pub(crate) fn highlight(
         &mut self,
         language: shared::language::Language,
@@ -176,6 +283,9 @@ impl Context {
                     }
                 };
             }
+            GlobalSearchConfigUpdate::SetOpenBuffersOnly(open_buffers_only) => {
+                self.global_search_config.open_buffers_only = open_buffers_only
+            }
         };
         Ok(())
     }
@@ -215,6 +325,222 @@ impl Context {
         self.contextual_keymaps = contextual_keymaps
     }
 
+    pub(crate) fn smooth_scroll(&self) -> bool {
+        self.smooth_scroll
+    }
+
+    pub(crate) fn toggle_smooth_scroll(&mut self) {
+        self.smooth_scroll = !self.smooth_scroll
+    }
+
+    pub(crate) fn file_explorer_sidebar(&self) -> bool {
+        self.file_explorer_sidebar
+    }
+
+    pub(crate) fn toggle_file_explorer_sidebar(&mut self) {
+        self.file_explorer_sidebar = !self.file_explorer_sidebar
+    }
+
+    pub(crate) fn show_indent_guides(&self) -> bool {
+        self.show_indent_guides
+    }
+
+    pub(crate) fn toggle_indent_guides(&mut self) {
+        self.show_indent_guides = !self.show_indent_guides
+    }
+
+    pub(crate) fn group_imports(&self) -> bool {
+        self.group_imports
+    }
+
+    pub(crate) fn toggle_group_imports(&mut self) {
+        self.group_imports = !self.group_imports
+    }
+
+    pub(crate) fn validate_structural_edits(&self) -> bool {
+        self.validate_structural_edits
+    }
+
+    pub(crate) fn toggle_validate_structural_edits(&mut self) {
+        self.validate_structural_edits = !self.validate_structural_edits
+    }
+
+    pub(crate) fn reindent_on_paste(&self) -> bool {
+        self.reindent_on_paste
+    }
+
+    pub(crate) fn toggle_reindent_on_paste(&mut self) {
+        self.reindent_on_paste = !self.reindent_on_paste
+    }
+
+    pub(crate) fn yank_flash(&self) -> bool {
+        self.yank_flash
+    }
+
+    pub(crate) fn toggle_yank_flash(&mut self) {
+        self.yank_flash = !self.yank_flash
+    }
+
+    pub(crate) fn yank_flash_duration(&self) -> std::time::Duration {
+        self.yank_flash_duration
+    }
+
+    pub(crate) fn set_yank_flash_duration(&mut self, duration: std::time::Duration) {
+        self.yank_flash_duration = duration
+    }
+
+    pub(crate) fn date_time_format(&self) -> &str {
+        &self.date_time_format
+    }
+
+    pub(crate) fn set_date_time_format(&mut self, format: String) {
+        self.date_time_format = format
+    }
+
+    pub(crate) fn date_time_utc(&self) -> bool {
+        self.date_time_utc
+    }
+
+    pub(crate) fn toggle_date_time_utc(&mut self) {
+        self.date_time_utc = !self.date_time_utc
+    }
+
+    pub(crate) fn document_highlight(&self) -> bool {
+        self.document_highlight
+    }
+
+    pub(crate) fn toggle_document_highlight(&mut self) {
+        self.document_highlight = !self.document_highlight
+    }
+
+    pub(crate) fn default_cursor_direction(&self, selection_mode: &SelectionMode) -> Direction {
+        self.default_cursor_directions
+            .get(&std::mem::discriminant(selection_mode))
+            .cloned()
+            .unwrap_or(Direction::Start)
+    }
+
+    pub(crate) fn toggle_default_cursor_direction(&mut self, selection_mode: &SelectionMode) {
+        let direction = self.default_cursor_direction(selection_mode).reverse();
+        self.default_cursor_directions
+            .insert(std::mem::discriminant(selection_mode), direction);
+    }
+
+    pub(crate) fn incremental_reparse_threshold(&self) -> usize {
+        self.incremental_reparse_threshold
+    }
+
+    pub(crate) fn set_incremental_reparse_threshold(
+        &mut self,
+        incremental_reparse_threshold: usize,
+    ) {
+        self.incremental_reparse_threshold = incremental_reparse_threshold
+    }
+
+    pub(crate) fn large_file_threshold(&self) -> usize {
+        self.large_file_threshold
+    }
+
+    pub(crate) fn set_large_file_threshold(&mut self, large_file_threshold: usize) {
+        self.large_file_threshold = large_file_threshold
+    }
+
+    pub(crate) fn snippets(&self) -> &Snippets {
+        &self.snippets
+    }
+
+    pub(crate) fn set_snippets(&mut self, snippets: Snippets) {
+        self.snippets = snippets
+    }
+
+    pub(crate) fn search_presets(&self) -> &[SearchPreset] {
+        self.search_presets.presets()
+    }
+
+    pub(crate) fn set_search_presets(&mut self, search_presets: SearchPresets) {
+        self.search_presets = search_presets
+    }
+
+    /// The most recently opened file comes first.
+    pub(crate) fn recent_files(&self) -> &[PathBuf] {
+        self.recent_files.files()
+    }
+
+    pub(crate) fn set_recent_files(&mut self, recent_files: RecentFiles) {
+        self.recent_files = recent_files
+    }
+
+    pub(crate) fn push_recent_file(&mut self, path: PathBuf) -> anyhow::Result<()> {
+        self.recent_files.push(path)
+    }
+
+    pub(crate) fn todo_tags(&self) -> &[TodoTag] {
+        &self.todo_tags
+    }
+
+    pub(crate) fn set_todo_tags(&mut self, todo_tags: Vec<TodoTag>) {
+        self.todo_tags = todo_tags
+    }
+
+    pub(crate) fn character_replacements(&self) -> &[CharacterReplacement] {
+        &self.character_replacements
+    }
+
+    pub(crate) fn set_character_replacements(
+        &mut self,
+        character_replacements: Vec<CharacterReplacement>,
+    ) {
+        self.character_replacements = character_replacements
+    }
+
+    pub(crate) fn final_newline(&self) -> FinalNewline {
+        self.final_newline
+    }
+
+    pub(crate) fn cycle_final_newline(&mut self) {
+        self.final_newline = self.final_newline.next()
+    }
+
+    /// Save the current search config of the given scope as a preset of the given name.
+    pub(crate) fn save_search_preset(&mut self, scope: Scope, name: String) -> anyhow::Result<()> {
+        let global_config = match scope {
+            Scope::Local => None,
+            Scope::Global => Some(&self.global_search_config),
+        };
+        let preset = SearchPreset::new(name, self.get_local_search_config(scope), global_config);
+        self.search_presets.save(preset)
+    }
+
+    /// Populate the search config of the given scope with the given preset.
+    pub(crate) fn apply_search_preset(
+        &mut self,
+        scope: Scope,
+        preset: SearchPreset,
+    ) -> anyhow::Result<()> {
+        if scope == Scope::Global {
+            self.global_search_config.clear_globs();
+            for (filter_glob, glob) in [
+                (GlobalSearchFilterGlob::Include, preset.include_glob),
+                (GlobalSearchFilterGlob::Exclude, preset.exclude_glob),
+            ] {
+                if let Some(glob) = glob {
+                    self.update_global_search_config(GlobalSearchConfigUpdate::SetGlob(
+                        filter_glob,
+                        glob,
+                    ))?
+                }
+            }
+        }
+        for update in [
+            LocalSearchConfigUpdate::Mode(preset.mode),
+            LocalSearchConfigUpdate::Replacement(preset.replacement),
+            LocalSearchConfigUpdate::Search(preset.search),
+        ] {
+            self.update_local_search_config(update, scope)
+        }
+        Ok(())
+    }
+
     pub(crate) fn push_history_prompt(&mut self, key: PromptHistoryKey, line: String) {
         if let Some(map) = self.prompt_histories.get_mut(&key) {
             map.shift_remove(&line);
@@ -229,6 +555,20 @@ impl Context {
         }
     }
 
+    /// Texts that were copied or searched, from the latest to the earliest, without duplicates.
+    pub(crate) fn register_history(&self) -> Vec<String> {
+        let searches = [Scope::Local, Scope::Global]
+            .into_iter()
+            .filter_map(|scope| self.prompt_histories.get(&PromptHistoryKey::Search(scope)))
+            .flat_map(|history| history.iter().rev().cloned());
+        self.clipboard
+            .history()
+            .map(|copied_texts| copied_texts.join("\n"))
+            .chain(searches)
+            .unique()
+            .collect_vec()
+    }
+
     pub(crate) fn get_prompt_history(
         &mut self,
         key: PromptHistoryKey,
@@ -251,6 +591,8 @@ pub(crate) struct GlobalSearchConfig {
     include_glob: Option<Glob>,
     exclude_glob: Option<Glob>,
     local_config: LocalSearchConfig,
+    /// Search and replace only the opened buffers instead of every file in the working directory.
+    open_buffers_only: bool,
 }
 impl GlobalSearchConfig {
     pub(crate) fn local_config(&self) -> &LocalSearchConfig {
@@ -265,6 +607,11 @@ impl GlobalSearchConfig {
         let _ = self.include_glob.insert(glob);
     }
 
+    fn clear_globs(&mut self) {
+        self.include_glob = None;
+        self.exclude_glob = None;
+    }
+
     pub(crate) fn include_glob(&self) -> Option<Glob> {
         self.include_glob.clone()
     }
@@ -272,9 +619,13 @@ impl GlobalSearchConfig {
     pub(crate) fn exclude_glob(&self) -> Option<Glob> {
         self.exclude_glob.clone()
     }
+
+    pub(crate) fn open_buffers_only(&self) -> bool {
+        self.open_buffers_only
+    }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Copy)]
+#[derive(Clone, Debug, PartialEq, Eq, Copy, serde::Serialize, serde::Deserialize)]
 pub(crate) enum LocalSearchConfigMode {
     Regex(RegexConfig),
     AstGrep,
@@ -307,6 +658,7 @@ impl RegexConfig {
                     self.case_sensitive.then_some("Case-sensitive".to_string()),
                     self.match_whole_word
                         .then_some("Match whole word".to_string()),
+                    self.multiline.then_some("Multi-line".to_string()),
                 ]
                 .into_iter()
                 .flatten()
@@ -329,6 +681,9 @@ pub(crate) struct LocalSearchConfig {
     pub(crate) mode: LocalSearchConfigMode,
     search: Option<String>,
     replacement: Option<String>,
+    /// If true, the replaced regions are selected after a local replace,
+    /// so that further actions can be chained on them.
+    select_replacements: bool,
 }
 
 impl LocalSearchConfig {
@@ -338,6 +693,7 @@ impl LocalSearchConfig {
             mode,
             search: Default::default(),
             replacement: Default::default(),
+            select_replacements: false,
         }
     }
 
@@ -350,6 +706,9 @@ impl LocalSearchConfig {
             LocalSearchConfigUpdate::Search(search) => {
                 self.set_search(search);
             }
+            LocalSearchConfigUpdate::SelectReplacements(select_replacements) => {
+                self.select_replacements = select_replacements
+            }
         }
     }
 
@@ -378,6 +737,10 @@ impl LocalSearchConfig {
         self.replacement.clone().unwrap_or_default()
     }
 
+    pub(crate) fn select_replacements(&self) -> bool {
+        self.select_replacements
+    }
+
     pub(crate) fn require_tree_sitter(&self) -> bool {
         self.mode == LocalSearchConfigMode::AstGrep
     }
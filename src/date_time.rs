@@ -0,0 +1,50 @@
+//! Formatting of the current date/time with strftime-style formats, such as `%Y-%m-%d %H:%M`.
+//!
+//! See <https://docs.rs/chrono/latest/chrono/format/strftime/index.html> for the supported specifiers.
+
+use chrono::{
+    format::{Item, StrftimeItems},
+    DateTime, Local, TimeZone, Utc,
+};
+
+pub(crate) const DEFAULT_FORMAT: &str = "%Y-%m-%d";
+
+/// Format the current date/time in UTC if `utc` is true, otherwise in the local timezone.
+pub(crate) fn format_now(format: &str, utc: bool) -> anyhow::Result<String> {
+    if utc {
+        self::format(Utc::now(), format)
+    } else {
+        self::format(Local::now(), format)
+    }
+}
+
+/// Returns an error instead of panicking if `format` contains an invalid specifier.
+fn format<Tz: TimeZone>(date_time: DateTime<Tz>, format: &str) -> anyhow::Result<String>
+where
+    Tz::Offset: std::fmt::Display,
+{
+    let items = StrftimeItems::new(format).collect::<Vec<_>>();
+    if items.contains(&Item::Error) {
+        anyhow::bail!("Invalid date/time format {:?}", format)
+    }
+    Ok(date_time.format_with_items(items.iter()).to_string())
+}
+
+#[cfg(test)]
+mod test_date_time {
+    use chrono::{TimeZone, Utc};
+
+    use super::format;
+
+    #[test]
+    fn strftime_format() -> anyhow::Result<()> {
+        let date_time = Utc.with_ymd_and_hms(2024, 3, 9, 14, 5, 0).unwrap();
+        assert_eq!(format(date_time, "%Y-%m-%d")?, "2024-03-09");
+        assert_eq!(
+            format(date_time, "## %a %d %b, %H:%M")?,
+            "## Sat 09 Mar, 14:05"
+        );
+        assert!(format(date_time, "%Q").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,141 @@
+//! Detection and conversion of the text encoding of files.
+//!
+//! Files are decoded into a `String` when they are opened,
+//! and encoded back into their original encoding when they are saved.
+
+const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: [u8; 2] = [0xFF, 0xFE];
+const UTF16_BE_BOM: [u8; 2] = [0xFE, 0xFF];
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub(crate) enum Encoding {
+    #[default]
+    Utf8,
+    /// UTF-8 prefixed with a byte order mark, which is commonly written by Windows tooling.
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    /// Assumed for files that are neither UTF-8 nor prefixed with a byte order mark.
+    Latin1,
+}
+
+impl Encoding {
+    pub(crate) fn all() -> [Encoding; 5] {
+        [
+            Encoding::Utf8,
+            Encoding::Utf8Bom,
+            Encoding::Utf16Le,
+            Encoding::Utf16Be,
+            Encoding::Latin1,
+        ]
+    }
+
+    pub(crate) fn display(&self) -> &'static str {
+        match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf8Bom => "UTF-8 BOM",
+            Encoding::Utf16Le => "UTF-16 LE",
+            Encoding::Utf16Be => "UTF-16 BE",
+            Encoding::Latin1 => "Latin-1",
+        }
+    }
+
+    /// Detect the encoding of `bytes` by its byte order mark, then decode it without the byte order mark.
+    ///
+    /// Invalid sequences of UTF-8 and UTF-16 are replaced with `U+FFFD`.
+    /// Returns an error for binary content, i.e. content without a UTF-16 byte order mark that contains NUL bytes.
+    pub(crate) fn decode(bytes: &[u8]) -> anyhow::Result<(Encoding, String)> {
+        Ok(if let Some(rest) = bytes.strip_prefix(&UTF16_LE_BOM) {
+            (Encoding::Utf16Le, decode_utf16(rest, u16::from_le_bytes))
+        } else if let Some(rest) = bytes.strip_prefix(&UTF16_BE_BOM) {
+            (Encoding::Utf16Be, decode_utf16(rest, u16::from_be_bytes))
+        } else if bytes.contains(&0) {
+            anyhow::bail!("The content is binary")
+        } else if let Some(rest) = bytes.strip_prefix(&UTF8_BOM) {
+            (
+                Encoding::Utf8Bom,
+                String::from_utf8_lossy(rest).into_owned(),
+            )
+        } else {
+            match std::str::from_utf8(bytes) {
+                Ok(content) => (Encoding::Utf8, content.to_string()),
+                Err(_) => (
+                    Encoding::Latin1,
+                    bytes.iter().map(|&byte| byte as char).collect(),
+                ),
+            }
+        })
+    }
+
+    /// Encode `content` in this encoding, including its byte order mark, if any.
+    pub(crate) fn encode(&self, content: &str) -> anyhow::Result<Vec<u8>> {
+        Ok(match self {
+            Encoding::Utf8 => content.as_bytes().to_vec(),
+            Encoding::Utf8Bom => UTF8_BOM.iter().copied().chain(content.bytes()).collect(),
+            Encoding::Utf16Le => UTF16_LE_BOM
+                .into_iter()
+                .chain(content.encode_utf16().flat_map(u16::to_le_bytes))
+                .collect(),
+            Encoding::Utf16Be => UTF16_BE_BOM
+                .into_iter()
+                .chain(content.encode_utf16().flat_map(u16::to_be_bytes))
+                .collect(),
+            Encoding::Latin1 => content
+                .chars()
+                .map(|char| {
+                    u8::try_from(char).map_err(|_| {
+                        anyhow::anyhow!("The character {:?} cannot be encoded in Latin-1", char)
+                    })
+                })
+                .collect::<anyhow::Result<_>>()?,
+        })
+    }
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    char::decode_utf16(
+        bytes
+            .chunks_exact(2)
+            .map(|chunk| from_bytes([chunk[0], chunk[1]])),
+    )
+    .map(|char| char.unwrap_or(char::REPLACEMENT_CHARACTER))
+    .collect()
+}
+
+#[cfg(test)]
+mod test_encoding {
+    use super::Encoding;
+
+    #[test]
+    fn round_trip() -> anyhow::Result<()> {
+        for encoding in Encoding::all() {
+            let bytes = encoding.encode("héllo\n")?;
+            assert_eq!(
+                Encoding::decode(&bytes)?,
+                (encoding, "héllo\n".to_string()),
+                "{:?}",
+                encoding
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn strip_utf8_bom() -> anyhow::Result<()> {
+        assert_eq!(
+            Encoding::decode(b"\xEF\xBB\xBFhello")?,
+            (Encoding::Utf8Bom, "hello".to_string())
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn binary_content_cannot_be_decoded() {
+        assert!(Encoding::decode(b"\x7FELF\x02\x01\x00\x00").is_err())
+    }
+
+    #[test]
+    fn latin1_cannot_encode_characters_beyond_u_plus_ff() {
+        assert!(Encoding::Latin1.encode("🦀").is_err())
+    }
+}
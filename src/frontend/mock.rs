@@ -1,5 +1,3 @@
-#! #[cfg(test)]
-
 use crate::{components::component::Cursor, screen::Screen};
 #[derive(Clone, Default)]
 pub(crate) struct MockFrontend {
@@ -1,5 +1,4 @@
 pub(crate) mod crossterm;
-#[cfg(test)]
 pub(crate) mod mock;
 
 use crate::{app::Dimension, components::component::Cursor, screen::Screen};
@@ -591,6 +591,10 @@ pub(crate) enum StyleKey {
     KeymapKey,
     UiFuzzyMatchedChar,
     ParentLine,
+    IndentGuide,
+    IndentGuideActive,
+    YankFlash,
+    DocumentHighlight,
 }
 
 /// TODO: in the future, tab size should be configurable
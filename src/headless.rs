@@ -0,0 +1,53 @@
+//! Run a sequence of key events against a file without the TUI,
+//! so that edits can be scripted, for example batch refactors or benchmarks.
+
+use std::sync::{Arc, Mutex};
+
+use shared::canonicalized_path::CanonicalizedPath;
+
+use crate::{
+    app::{App, Dispatch},
+    frontend::{mock::MockFrontend, Frontend},
+};
+
+pub(crate) struct HeadlessConfig {
+    pub(crate) path: CanonicalizedPath,
+    /// Space-separated key events, for example `"i h e l l o esc"`.
+    pub(crate) keys: String,
+    pub(crate) working_directory: CanonicalizedPath,
+}
+
+/// Open `config.path`, feed it with `config.keys`, and return the final content of its buffer.
+///
+/// Unlike the interactive mode, the first error aborts the run instead of being shown as an info.
+/// Note that the file is only written if the given keys save it.
+pub(crate) fn run(config: HeadlessConfig) -> anyhow::Result<String> {
+    let mut app = App::new(
+        Arc::new(Mutex::new(MockFrontend::default())),
+        config.working_directory,
+    )?;
+    app.disable_lsp();
+    run_keys(&mut app, &config.path, &config.keys)
+}
+
+pub(crate) fn run_keys<T: Frontend>(
+    app: &mut App<T>,
+    path: &CanonicalizedPath,
+    keys: &str,
+) -> anyhow::Result<String> {
+    let key_events = event::parse_key_events(keys.trim())
+        .map_err(|error| anyhow::anyhow!("Failed to parse keys {:?}: {}", keys, error))?;
+
+    app.handle_dispatch(Dispatch::OpenFile(path.clone()))?;
+    for key_event in key_events {
+        app.handle_event_strictly(event::event::Event::Key(key_event))?;
+        if app.should_quit() {
+            break;
+        }
+    }
+
+    match app.get_buffer_content(path) {
+        Some(content) => Ok(content),
+        None => Ok(path.read()?),
+    }
+}
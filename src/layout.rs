@@ -11,7 +11,8 @@ use crate::{
         prompt::Prompt,
         suggestive_editor::{Info, SuggestiveEditor},
     },
-    context::QuickfixListSource,
+    context::{Context, QuickfixListSource},
+    position::Position,
     quickfix_list::{Location, QuickfixListItem},
     rectangle::{Border, LayoutKind, Rectangle},
 };
@@ -20,7 +21,7 @@ use indexmap::IndexMap;
 use itertools::Itertools;
 use nary_tree::NodeId;
 use shared::canonicalized_path::CanonicalizedPath;
-use std::{cell::RefCell, rc::Rc};
+use std::{cell::RefCell, ops::Range, rc::Rc};
 
 /// The layout of the app is split into multiple sections: the main panel, info panel, quickfix
 /// lists, prompts, and etc.
@@ -28,7 +29,12 @@ use std::{cell::RefCell, rc::Rc};
 /// hover text, diagnostics, etc.
 pub(crate) struct Layout {
     background_suggestive_editors: IndexMap<CanonicalizedPath, Rc<RefCell<SuggestiveEditor>>>,
+    /// Editors of unnamed in-memory buffers, see [`crate::app::Dispatch::OpenScratch`].
+    scratch_editors: Vec<Rc<RefCell<SuggestiveEditor>>>,
     background_file_explorer: Rc<RefCell<FileExplorer>>,
+    /// Whether the file explorer is docked to the left of the other components,
+    /// see [`Context::file_explorer_sidebar`].
+    file_explorer_sidebar: bool,
     background_quickfix_list: Option<Rc<RefCell<Editor>>>,
 
     rectangles: Vec<Rectangle>,
@@ -49,7 +55,9 @@ impl Layout {
         Ok(Layout {
             background_quickfix_list: None,
             background_suggestive_editors: IndexMap::new(),
+            scratch_editors: Vec::new(),
             background_file_explorer: Rc::new(RefCell::new(FileExplorer::new(working_directory)?)),
+            file_explorer_sidebar: false,
             rectangles,
             borders,
             terminal_dimension,
@@ -88,6 +96,10 @@ impl Layout {
                 self.tree.remove(node.node_id(), true);
             }
         } else {
+            // Closing a scratch editor discards it
+            let id = node.data().component().borrow().id();
+            self.scratch_editors
+                .retain(|editor| editor.borrow().id() != id);
             self.tree.remove(node.node_id(), true);
         };
 
@@ -113,18 +125,67 @@ impl Layout {
     }
 
     pub(crate) fn recalculate_layout(&mut self) {
-        let (layout_kind, ratio) = layout_kind(&self.terminal_dimension);
+        if self.file_explorer_sidebar
+            && self
+                .tree
+                .get_node_child_id(self.tree.root_id(), ComponentKind::FileExplorer)
+                .is_none()
+        {
+            self.tree.append_component(
+                self.tree.root_id(),
+                KindedComponent::new(
+                    ComponentKind::FileExplorer,
+                    self.background_file_explorer.clone(),
+                ),
+                false,
+            );
+        }
 
-        let (rectangles, borders) = Rectangle::generate(
-            layout_kind,
-            self.components().len(),
-            ratio,
-            self.terminal_dimension,
-        );
-        self.rectangles = rectangles;
-        self.borders = borders;
+        // The sidebar is only docked if there are other components beside it
+        let (sidebar, components): (Vec<_>, Vec<_>) =
+            self.components().into_iter().partition(|component| {
+                self.file_explorer_sidebar && component.kind() == ComponentKind::FileExplorer
+            });
+        let (sidebar, components) = if components.is_empty() {
+            (Vec::new(), sidebar)
+        } else {
+            (sidebar, components)
+        };
+        let sidebar_width = if sidebar.is_empty() {
+            0
+        } else {
+            FILE_EXPLORER_SIDEBAR_WIDTH.min(self.terminal_dimension.width / 3)
+        };
+        // The sidebar is separated from the other components by a border
+        let offset = if sidebar.is_empty() {
+            0
+        } else {
+            sidebar_width + 1
+        };
+        let dimension = Dimension {
+            width: self.terminal_dimension.width.saturating_sub(offset),
+            height: self.terminal_dimension.height,
+        };
 
-        self.components()
+        let (layout_kind, ratio) = layout_kind(&dimension);
+        let (rectangles, borders) =
+            Rectangle::generate(layout_kind, components.len(), ratio, dimension);
+        self.rectangles = rectangles
+            .into_iter()
+            .map(|rectangle| Rectangle {
+                origin: rectangle.origin.move_right(offset),
+                ..rectangle
+            })
+            .collect_vec();
+        self.borders = borders
+            .into_iter()
+            .map(|border| Border {
+                start: border.start.move_right(offset),
+                ..border
+            })
+            .collect_vec();
+
+        components
             .into_iter()
             .zip(self.rectangles.iter())
             .for_each(|(component, rectangle)| {
@@ -133,6 +194,35 @@ impl Layout {
                     .borrow_mut()
                     .set_rectangle(rectangle.clone())
             });
+
+        if let Some(sidebar) = sidebar.first() {
+            sidebar.component().borrow_mut().set_rectangle(Rectangle {
+                origin: Position::default(),
+                width: sidebar_width,
+                height: self.terminal_dimension.height,
+            });
+            self.borders.push(Border::new_vertical(
+                Position::new(0, sidebar_width as usize),
+                self.terminal_dimension.height,
+            ));
+        }
+    }
+
+    pub(crate) fn set_file_explorer_sidebar(&mut self, file_explorer_sidebar: bool) {
+        self.file_explorer_sidebar = file_explorer_sidebar;
+        if !file_explorer_sidebar {
+            let root_id = self.tree.root_id();
+            if let Some(node_id) = self
+                .tree
+                .get_node_child_id(root_id, ComponentKind::FileExplorer)
+            {
+                // Keep the explorer as a normal window if it is being used
+                if node_id != self.tree.focused_component_id() {
+                    self.tree.remove(node_id, false);
+                }
+            }
+        }
+        self.recalculate_layout()
     }
 
     pub(crate) fn get_existing_editor(
@@ -179,12 +269,40 @@ impl Layout {
         suggestive_editor: Rc<RefCell<SuggestiveEditor>>,
     ) {
         let path = suggestive_editor.borrow().path();
+        let id = suggestive_editor.borrow().id();
+        let is_scratch = self
+            .scratch_editors
+            .iter()
+            .any(|editor| editor.borrow().id() == id);
         if let Some(path) = path {
+            // A scratch editor is no longer one once its buffer is saved to a path
+            self.scratch_editors
+                .retain(|editor| editor.borrow().id() != id);
             self.background_suggestive_editors
                 .insert(path, suggestive_editor);
+        } else if !is_scratch {
+            self.scratch_editors.push(suggestive_editor);
         }
     }
 
+    /// Returns the id and title of every scratch editor, in the order of creation.
+    pub(crate) fn scratch_editors(&self, context: &Context) -> Vec<(ComponentId, String)> {
+        self.scratch_editors
+            .iter()
+            .map(|editor| (editor.borrow().id(), editor.borrow().title(context)))
+            .collect_vec()
+    }
+
+    pub(crate) fn get_scratch_editor(
+        &self,
+        id: ComponentId,
+    ) -> Option<Rc<RefCell<SuggestiveEditor>>> {
+        self.scratch_editors
+            .iter()
+            .find(|editor| editor.borrow().id() == id)
+            .cloned()
+    }
+
     fn show_info_on(
         &mut self,
         node_id: NodeId,
@@ -223,10 +341,10 @@ impl Layout {
             .collect()
     }
 
-    pub(crate) fn save_all(&self) -> Result<(), anyhow::Error> {
+    pub(crate) fn save_all(&self, context: &Context) -> Result<(), anyhow::Error> {
         self.background_suggestive_editors
             .iter()
-            .map(|(_, editor)| editor.borrow_mut().editor_mut().save())
+            .map(|(_, editor)| editor.borrow_mut().editor_mut().save(context))
             .collect::<Result<Vec<_>, _>>()?;
         Ok(())
     }
@@ -255,6 +373,17 @@ impl Layout {
     }
 
     pub(crate) fn open_file_explorer(&mut self) {
+        if self.file_explorer_sidebar {
+            // Focus the sidebar without closing the other components
+            self.recalculate_layout();
+            if let Some(node_id) = self
+                .tree
+                .get_node_child_id(self.tree.root_id(), ComponentKind::FileExplorer)
+            {
+                self.tree.set_focus_component_id(node_id);
+            }
+            return;
+        }
         self.tree.remove_all_root_children();
         self.tree.replace_root_node_child(
             ComponentKind::FileExplorer,
@@ -264,23 +393,46 @@ impl Layout {
         debug_assert_eq!(self.tree.root().children().count(), 1);
     }
 
+    /// Searches the shown components, then the hidden editors.
+    pub(crate) fn get_component_by_id(
+        &self,
+        component_id: ComponentId,
+    ) -> Option<Rc<RefCell<dyn Component>>> {
+        self.components()
+            .into_iter()
+            .map(|component| component.component())
+            .find(|component| component.borrow().id() == component_id)
+            .or_else(|| {
+                self.get_suggestive_editor_by_id(component_id)
+                    .ok()
+                    .map(|editor| editor as Rc<RefCell<dyn Component>>)
+            })
+    }
+
+    pub(crate) fn get_suggestive_editor_by_id(
+        &self,
+        component_id: ComponentId,
+    ) -> anyhow::Result<Rc<RefCell<SuggestiveEditor>>> {
+        self.background_suggestive_editors
+            .iter()
+            .find(|(_, component)| component.borrow().id() == component_id)
+            .map(|(_, component)| component.clone())
+            .ok_or_else(|| anyhow!("Couldn't find component with id {:?}", component_id))
+    }
+
     pub(crate) fn update_highlighted_spans(
         &self,
         component_id: ComponentId,
+        byte_range: Range<usize>,
         highlighted_spans: crate::syntax_highlight::HighlighedSpans,
     ) -> Result<(), anyhow::Error> {
-        let component = self
-            .background_suggestive_editors
-            .iter()
-            .find(|(_, component)| component.borrow().id() == component_id)
-            .map(|(_, component)| component)
-            .ok_or_else(|| anyhow!("Couldn't find component with id {:?}", component_id))?;
+        let component = self.get_suggestive_editor_by_id(component_id)?;
 
         let mut component = component.borrow_mut();
         component
             .editor_mut()
             .buffer_mut()
-            .update_highlighted_spans(highlighted_spans);
+            .update_highlighted_spans(byte_range, highlighted_spans);
 
         Ok(())
     }
@@ -545,6 +697,8 @@ impl Layout {
         self.tree.remove_current_child(ComponentKind::EditorInfo);
     }
 }
+const FILE_EXPLORER_SIDEBAR_WIDTH: u16 = 30;
+
 fn layout_kind(terminal_dimension: &Dimension) -> (LayoutKind, f32) {
     const MAIN_PANEL_MIN_WIDTH: u16 = 100;
     const RIGHT_PANEL_MIN_WIDTH: u16 = 50;
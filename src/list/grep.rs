@@ -11,11 +11,14 @@ use shared::canonicalized_path::CanonicalizedPath;
 
 use super::WalkBuilderConfig;
 
-#[derive(Clone, Debug, PartialEq, Eq, Copy)]
+#[derive(Clone, Debug, PartialEq, Eq, Copy, serde::Serialize, serde::Deserialize)]
 pub(crate) struct RegexConfig {
     pub(crate) escaped: bool,
     pub(crate) case_sensitive: bool,
     pub(crate) match_whole_word: bool,
+    /// Allow `.` to match newlines, so that a match can span multiple lines.
+    #[serde(default)]
+    pub(crate) multiline: bool,
 }
 impl RegexConfig {
     pub(crate) fn to_regex(self, pattern: &str) -> Result<Regex, anyhow::Error> {
@@ -29,6 +32,7 @@ impl Default for RegexConfig {
             escaped: true,
             case_sensitive: false,
             match_whole_word: false,
+            multiline: false,
         }
     }
 }
@@ -62,7 +66,11 @@ pub(crate) fn run(
     grep_config: RegexConfig,
 ) -> anyhow::Result<Vec<Location>> {
     let pattern = get_regex(pattern, grep_config)?.as_str().to_string();
-    let matcher = RegexMatcher::new_line_matcher(&pattern)?;
+    let matcher = if grep_config.multiline {
+        RegexMatcher::new(&pattern)?
+    } else {
+        RegexMatcher::new_line_matcher(&pattern)?
+    };
     let regex = Regex::new(&pattern)?;
 
     Ok(walk_builder_config
@@ -72,7 +80,10 @@ pub(crate) fn run(
             // Tree-sitter should be disabled whenever possible during
             // global search, because it will slow down the operation tremendously
             debug_assert!(buffer.tree().is_none());
-            let mut searcher = SearcherBuilder::new().build();
+            // In multi-line mode, `line` contains every line spanned by the match
+            let mut searcher = SearcherBuilder::new()
+                .multi_line(grep_config.multiline)
+                .build();
             searcher.search_path(
                 &matcher,
                 path.clone(),
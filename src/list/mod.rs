@@ -11,6 +11,8 @@ pub(crate) mod ast_grep;
 
 pub(crate) mod case_agnostic;
 pub(crate) mod grep;
+pub(crate) mod open_buffers;
+pub(crate) mod todo;
 
 pub(crate) struct WalkBuilderConfig {
     pub(crate) root: PathBuf,
@@ -0,0 +1,59 @@
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    buffer::Buffer,
+    context::{LocalSearchConfig, LocalSearchConfigMode},
+    quickfix_list::Location,
+    selection_mode::{regex::get_regex, AstGrep, ByteRange, CaseAgnostic},
+};
+
+/// Search the given buffers instead of walking the working directory,
+/// which is faster, and includes the modifications that are not saved yet.
+///
+/// Buffers without a path are skipped.
+pub(crate) fn run(
+    buffers: Vec<Rc<RefCell<Buffer>>>,
+    config: &LocalSearchConfig,
+) -> anyhow::Result<Vec<Location>> {
+    let search = config.search();
+    let find: Box<dyn Fn(&Buffer) -> anyhow::Result<Vec<ByteRange>>> = match config.mode {
+        LocalSearchConfigMode::Regex(regex_config) => {
+            // Same as the line matcher of grep, `^` and `$` match at the start and end of every line
+            let regex = regex::RegexBuilder::new(get_regex(&search, regex_config)?.as_str())
+                .multi_line(true)
+                .build()?;
+            Box::new(move |buffer| {
+                Ok(regex
+                    .find_iter(&buffer.content())
+                    .map(|match_| ByteRange::new(match_.range()))
+                    .collect())
+            })
+        }
+        LocalSearchConfigMode::AstGrep => Box::new(move |buffer| {
+            Ok(AstGrep::new(buffer, &search)?
+                .find_all()
+                .map(|node_match| ByteRange::new(node_match.range()))
+                .collect())
+        }),
+        LocalSearchConfigMode::CaseAgnostic => Box::new(move |buffer| {
+            Ok(CaseAgnostic::new(search.clone())
+                .find_all(&buffer.content())
+                .into_iter()
+                .map(|(range, _)| range)
+                .collect())
+        }),
+    };
+    let mut locations = Vec::new();
+    for buffer in buffers {
+        let buffer = buffer.borrow();
+        let Some(path) = buffer.path() else { continue };
+        for byte_range in find(&buffer)? {
+            let range = byte_range.range();
+            locations.push(Location {
+                path: path.clone(),
+                range: buffer.byte_to_position(range.start)?..buffer.byte_to_position(range.end)?,
+            })
+        }
+    }
+    Ok(locations)
+}
@@ -0,0 +1,133 @@
+//! Find the comments tagged with `TODO`, `FIXME`, etc. throughout the project.
+//!
+//! The tags can be configured in `todo_tags.json` in the config directory, for example:
+//!
+//! ```json
+//! [{ "name": "TODO", "style": "information" }, { "name": "SAFETY", "style": "hint" }]
+//! ```
+
+use itertools::Itertools;
+use regex::Regex;
+use shared::canonicalized_path::CanonicalizedPath;
+
+use anyhow::Context;
+
+use crate::{
+    components::suggestive_editor::{Decoration, Info},
+    grid::StyleKey,
+    position::Position,
+    quickfix_list::{Location, QuickfixListItem},
+    selection_range::SelectionRange,
+};
+
+use super::WalkBuilderConfig;
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Deserialize)]
+pub(crate) struct TodoTag {
+    pub(crate) name: String,
+    #[serde(default)]
+    pub(crate) style: TodoStyle,
+}
+
+/// How a tag is highlighted in the info of its quickfix item.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum TodoStyle {
+    Error,
+    Warning,
+    #[default]
+    Information,
+    Hint,
+}
+
+impl TodoStyle {
+    fn style_key(self) -> StyleKey {
+        match self {
+            TodoStyle::Error => StyleKey::DiagnosticsError,
+            TodoStyle::Warning => StyleKey::DiagnosticsWarning,
+            TodoStyle::Information => StyleKey::DiagnosticsInformation,
+            TodoStyle::Hint => StyleKey::DiagnosticsHint,
+        }
+    }
+}
+
+impl TodoTag {
+    fn new(name: &str, style: TodoStyle) -> TodoTag {
+        TodoTag {
+            name: name.to_string(),
+            style,
+        }
+    }
+
+    pub(crate) fn defaults() -> Vec<TodoTag> {
+        [
+            TodoTag::new("TODO", TodoStyle::Information),
+            TodoTag::new("FIXME", TodoStyle::Error),
+            TodoTag::new("HACK", TodoStyle::Warning),
+        ]
+        .to_vec()
+    }
+
+    /// Returns the default tags if `todo_tags.json` does not exist.
+    pub(crate) fn load() -> anyhow::Result<Vec<TodoTag>> {
+        let path = grammar::config_dir().join("todo_tags.json");
+        if !path.exists() {
+            return Ok(TodoTag::defaults());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse TODO tags from {}", path.display()))
+    }
+}
+
+/// Each item spans the tag, and its info is the text following the tag.
+pub(crate) fn run(
+    tags: Vec<TodoTag>,
+    walk_builder_config: WalkBuilderConfig,
+) -> anyhow::Result<Vec<QuickfixListItem>> {
+    if tags.is_empty() {
+        return Ok(Vec::new());
+    }
+    let regex = Regex::new(&format!(
+        r"\b({})\b:?\s*(.*)",
+        tags.iter().map(|tag| regex::escape(&tag.name)).join("|")
+    ))?;
+    walk_builder_config.run(Box::new(move |path, sender| {
+        // Binary files are skipped
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            return Ok(());
+        };
+        if !regex.is_match(&content) {
+            return Ok(());
+        }
+        let path: CanonicalizedPath = path.try_into()?;
+        for (line, text) in content.lines().enumerate() {
+            let Some(captures) = regex.captures(text) else {
+                continue;
+            };
+            let (Some(tag), Some(comment)) = (captures.get(1), captures.get(2)) else {
+                continue;
+            };
+            let style = tags
+                .iter()
+                .find(|todo_tag| todo_tag.name == tag.as_str())
+                .map(|todo_tag| todo_tag.style)
+                .unwrap_or_default();
+            let column = text[..tag.start()].chars().count();
+            let info_content = format!("{}: {}", tag.as_str(), comment.as_str().trim_end());
+            let info = Info::new(tag.as_str().to_string(), info_content).set_decorations(vec![
+                Decoration::new(SelectionRange::Byte(0..tag.len()), style.style_key()),
+            ]);
+            let location = Location {
+                path: path.clone(),
+                range: Position::new(line, column)
+                    ..Position::new(line, column + tag.as_str().chars().count()),
+            };
+            sender
+                .send(QuickfixListItem::new(location, Some(info)))
+                .map_err(|error| log::error!("sender.send {:?}", error))
+                .unwrap_or_default();
+        }
+        Ok(())
+    }))
+}
@@ -1,6 +1,9 @@
 use itertools::Itertools;
 
-use crate::{app::Dispatch, components::dropdown::DropdownItem};
+use crate::{
+    app::{Dispatch, Dispatches},
+    components::{dropdown::DropdownItem, suggestive_editor::Info},
+};
 
 use super::workspace_edit::WorkspaceEdit;
 
@@ -11,8 +14,53 @@ pub(crate) struct CodeAction {
     pub(crate) kind: Option<String>,
     pub(crate) edit: Option<WorkspaceEdit>,
     pub(crate) command: Option<Command>,
+    /// Defined when this code action has neither an edit nor a command,
+    /// which means it has to be resolved before it can be applied.
+    pub(crate) unresolved: Option<UnresolvedCodeAction>,
 }
 
+impl CodeAction {
+    /// Refer https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#codeAction_resolve
+    pub(crate) fn dispatches(self) -> Dispatches {
+        if let (None, None, Some(unresolved)) = (&self.edit, &self.command, self.unresolved) {
+            return Dispatches::one(Dispatch::ResolveCodeAction(unresolved));
+        }
+        self.edit
+            .map(Dispatch::ApplyWorkspaceEdit)
+            .into_iter()
+            // A command this code action executes. If a code action
+            // provides an edit and a command, first the edit is
+            // executed and then the command.
+            // Refer https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#codeAction
+            .chain(
+                self.command
+                    .map(|command| Dispatch::LspExecuteCommand { command }),
+            )
+            .collect_vec()
+            .into()
+    }
+
+    fn kind(&self) -> Option<String> {
+        self.kind.clone().filter(|kind| !kind.is_empty())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct UnresolvedCodeAction(lsp_types::CodeAction);
+impl UnresolvedCodeAction {
+    pub(crate) fn into_inner(self) -> lsp_types::CodeAction {
+        self.0
+    }
+}
+
+impl PartialEq for UnresolvedCodeAction {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.title.eq(&other.0.title)
+    }
+}
+
+impl Eq for UnresolvedCodeAction {}
+
 #[derive(Debug, Clone)]
 pub(crate) struct Command(lsp_types::Command);
 impl Command {
@@ -41,30 +89,11 @@ impl Eq for Command {}
 
 impl From<CodeAction> for DropdownItem {
     fn from(value: CodeAction) -> DropdownItem {
-        DropdownItem::new(value.title)
-            .set_group(Some(
-                value
-                    .kind
-                    .and_then(|kind| if kind.is_empty() { None } else { Some(kind) })
-                    .unwrap_or("Misc.".to_string()),
-            ))
-            .set_dispatches(
-                value
-                    .edit
-                    .map(Dispatch::ApplyWorkspaceEdit)
-                    .into_iter()
-                    // A command this code action executes. If a code action
-                    // provides an edit and a command, first the edit is
-                    // executed and then the command.
-                    // Refer https://microsoft.github.io/language-server-protocol/specifications/lsp/3.17/specification/#codeAction
-                    .chain(
-                        value
-                            .command
-                            .map(|command| Dispatch::LspExecuteCommand { command }),
-                    )
-                    .collect_vec()
-                    .into(),
-            )
+        let kind = value.kind();
+        DropdownItem::new(value.title.clone())
+            .set_group(Some(kind.clone().unwrap_or("Misc.".to_string())))
+            .set_info(kind.map(|kind| Info::new("Code Action".to_string(), format!("[{}]", kind))))
+            .set_dispatches(value.dispatches())
     }
 }
 
@@ -86,12 +115,27 @@ impl TryFrom<lsp_types::CodeAction> for CodeAction {
     fn try_from(value: lsp_types::CodeAction) -> Result<Self, Self::Error> {
         log::info!("CodeAction: {:#?}", value);
 
+        let unresolved = (value.edit.is_none() && value.command.is_none())
+            .then(|| UnresolvedCodeAction(value.clone()));
         let title = value.title;
         Ok(CodeAction {
             title,
             kind: value.kind.map(|kind| kind.as_str().to_string()),
             edit: value.edit.map(WorkspaceEdit::try_from).transpose()?,
             command: value.command.map(Command),
+            unresolved,
         })
     }
 }
+
+impl From<lsp_types::Command> for CodeAction {
+    fn from(value: lsp_types::Command) -> Self {
+        CodeAction {
+            title: value.title.clone(),
+            kind: None,
+            edit: None,
+            command: Some(Command(value)),
+            unresolved: None,
+        }
+    }
+}
@@ -119,6 +119,14 @@ impl LspManager {
             });
     }
 
+    /// Returns true if the language server of `path` is running and initialized.
+    pub(crate) fn is_initialized(&self, path: &CanonicalizedPath) -> bool {
+        language::from_path(path)
+            .and_then(|language| self.lsp_server_process_channels.get(&language.id()?))
+            .map(|channel| channel.is_initialized())
+            .unwrap_or(false)
+    }
+
     pub(crate) fn shutdown(&mut self) {
         for (_, channel) in self.lsp_server_process_channels.drain() {
             channel
@@ -20,14 +20,14 @@ use std::time::Duration;
 use crate::app::AppMessage;
 use crate::utils::consolidate_errors;
 
-use super::code_action::CodeAction;
+use super::code_action::{CodeAction, UnresolvedCodeAction};
 use super::completion::{Completion, CompletionItem};
 use super::goto_definition_response::GotoDefinitionResponse;
 use super::hover::Hover;
 use super::prepare_rename_response::PrepareRenameResponse;
 use super::signature_help::SignatureHelp;
 use super::symbols::Symbols;
-use super::workspace_edit::WorkspaceEdit;
+use super::workspace_edit::{TextDocumentEdit, WorkspaceEdit};
 use crate::quickfix_list::Location;
 
 struct LspServerProcess {
@@ -67,15 +67,37 @@ pub(crate) enum LspNotification {
     Error(String),
     WorkspaceEdit(WorkspaceEdit),
     CodeAction(Vec<CodeAction>),
+    CodeActionResolve(CodeAction),
+    /// The code actions of kind `source.organizeImports`.
+    /// This is empty if the language server does not support code actions.
+    OrganizeImports(Vec<CodeAction>),
     SignatureHelp(Option<SignatureHelp>),
     Symbols(Symbols),
     CompletionItemResolve(lsp_types::CompletionItem),
+    /// The ranges of the occurrences of the symbol under the cursor of the current editor.
+    DocumentHighlight(
+        ResponseContext,
+        Vec<std::ops::Range<crate::position::Position>>,
+    ),
+    /// The ranges enclosing the cursor of the current editor, from the innermost to the outermost.
+    ///
+    /// This is empty if the language server does not support `textDocument/selectionRange`.
+    SelectionRange(
+        ResponseContext,
+        Vec<std::ops::Range<crate::position::Position>>,
+    ),
 }
 
+/// Used as the description of the response context of `textDocument/codeAction`
+/// to tell apart the responses of [`FromEditor::TextDocumentOrganizeImports`].
+const ORGANIZE_IMPORTS: &str = "Organize imports";
+
 #[derive(Debug, Clone, PartialEq, Eq, Default)]
 pub(crate) struct ResponseContext {
     pub(crate) scope: Option<Scope>,
     pub(crate) description: Option<String>,
+    /// The file of the request, if the request is about a file.
+    pub(crate) path: Option<CanonicalizedPath>,
 }
 impl ResponseContext {
     pub(crate) fn set_description(self, descrption: &str) -> Self {
@@ -133,6 +155,13 @@ pub(crate) enum FromEditor {
     TextDocumentImplementation(RequestParams),
     TextDocumentTypeDefinition(RequestParams),
     TextDocumentDocumentSymbol(RequestParams),
+    TextDocumentDocumentHighlight(RequestParams),
+    TextDocumentSelectionRange(RequestParams),
+    TextDocumentRangeFormatting {
+        params: RequestParams,
+        range: std::ops::Range<crate::position::Position>,
+    },
+    TextDocumentOrganizeImports(RequestParams),
     WorkspaceDidRenameFiles {
         old: CanonicalizedPath,
         new: CanonicalizedPath,
@@ -145,6 +174,10 @@ pub(crate) enum FromEditor {
         completion_item: lsp_types::CompletionItem,
         params: RequestParams,
     },
+    CodeActionResolve {
+        code_action: UnresolvedCodeAction,
+        params: RequestParams,
+    },
 }
 
 impl FromEditor {
@@ -361,6 +394,10 @@ impl LspServerProcess {
                                     .collect(),
                                 },
                             }),
+                            data_support: Some(true),
+                            resolve_support: Some(CodeActionCapabilityResolveSupport {
+                                properties: vec!["edit".to_string()],
+                            }),
                             ..Default::default()
                         }),
                         rename: Some(RenameClientCapabilities {
@@ -505,6 +542,10 @@ impl LspServerProcess {
                         completion_item,
                         params,
                     })),
+                    // Requested after every keypress, so only the position the cursor rests on is requested
+                    FromEditor::TextDocumentDocumentHighlight(params) => {
+                        debounce.put(Event(FromEditor::TextDocumentDocumentHighlight(params)))
+                    }
                     // Other requests should not be throttled, and hanlded immediately
                     _ => self.handle_from_editor(from_editor),
                 },
@@ -545,6 +586,17 @@ impl LspServerProcess {
                 .map_err(|e| anyhow::anyhow!("Serde error = {:?}", e))?
                 .payload
                 .map_err(|e| {
+                    if pending_response_request.method == "textDocument/selectionRange" {
+                        // So that the editor falls back to tree-sitter
+                        self.app_message_sender
+                            .send(AppMessage::LspNotification(
+                                LspNotification::SelectionRange(
+                                    pending_response_request.context.clone(),
+                                    Vec::new(),
+                                ),
+                            ))
+                            .unwrap();
+                    }
                     self.app_message_sender
                         .send(AppMessage::LspNotification(LspNotification::Error(
                             format!("LSP JSON-RPC Error: {:?}: {}", e.code, e.message),
@@ -683,6 +735,42 @@ impl LspServerProcess {
                                 .unwrap();
                         }
                     }
+                    "textDocument/documentHighlight" => {
+                        let payload: <lsp_request!("textDocument/documentHighlight") as Request>::Result =
+                            serde_json::from_value(response)?;
+
+                        self.app_message_sender
+                            .send(AppMessage::LspNotification(
+                                LspNotification::DocumentHighlight(
+                                    response_context,
+                                    payload
+                                        .unwrap_or_default()
+                                        .into_iter()
+                                        .map(|highlight| {
+                                            highlight.range.start.into()..highlight.range.end.into()
+                                        })
+                                        .collect(),
+                                ),
+                            ))
+                            .unwrap();
+                    }
+                    "textDocument/selectionRange" => {
+                        let payload: <lsp_request!("textDocument/selectionRange") as Request>::Result =
+                            serde_json::from_value(response)?;
+
+                        let mut ranges = Vec::new();
+                        let mut selection_range = payload
+                            .and_then(|selection_ranges| selection_ranges.into_iter().next());
+                        while let Some(SelectionRange { range, parent }) = selection_range {
+                            ranges.push(range.start.into()..range.end.into());
+                            selection_range = parent.map(|parent| *parent);
+                        }
+                        self.app_message_sender
+                            .send(AppMessage::LspNotification(
+                                LspNotification::SelectionRange(response_context, ranges),
+                            ))
+                            .unwrap();
+                    }
                     "textDocument/prepareRename" => {
                         let payload: <lsp_request!("textDocument/prepareRename") as Request>::Result =
                             serde_json::from_value(response)?;
@@ -695,6 +783,28 @@ impl LspServerProcess {
                                 .unwrap();
                         }
                     }
+                    "textDocument/rangeFormatting" => {
+                        let payload: <lsp_request!("textDocument/rangeFormatting") as Request>::Result =
+                            serde_json::from_value(response)?;
+
+                        if let (Some(edits), Some(path)) = (payload, response_context.path) {
+                            self.app_message_sender
+                                .send(AppMessage::LspNotification(LspNotification::WorkspaceEdit(
+                                    WorkspaceEdit {
+                                        edits: [TextDocumentEdit {
+                                            path,
+                                            edits: edits
+                                                .into_iter()
+                                                .map(|edit| edit.try_into())
+                                                .collect::<Result<Vec<_>, _>>()?,
+                                        }]
+                                        .to_vec(),
+                                        resource_operations: Vec::new(),
+                                    },
+                                )))
+                                .unwrap();
+                        }
+                    }
                     "textDocument/rename" => {
                         let payload: <lsp_request!("textDocument/rename") as Request>::Result =
                             serde_json::from_value(response)?;
@@ -711,22 +821,46 @@ impl LspServerProcess {
                         let payload: <lsp_request!("textDocument/codeAction") as Request>::Result =
                             serde_json::from_value(response)?;
 
-                        if let Some(payload) = payload {
+                        let code_actions = payload
+                            .map(|payload| {
+                                payload
+                                    .into_iter()
+                                    .map(|r| match r {
+                                        CodeActionOrCommand::Command(command) => Ok(command.into()),
+                                        CodeActionOrCommand::CodeAction(code_action) => {
+                                            code_action.try_into()
+                                        }
+                                    })
+                                    .collect::<Result<Vec<_>, _>>()
+                            })
+                            .transpose()?;
+
+                        if response_context.description.as_deref() == Some(ORGANIZE_IMPORTS) {
+                            self.app_message_sender
+                                .send(AppMessage::LspNotification(
+                                    LspNotification::OrganizeImports(
+                                        code_actions.unwrap_or_default(),
+                                    ),
+                                ))
+                                .unwrap();
+                        } else if let Some(code_actions) = code_actions {
                             self.app_message_sender
                                 .send(AppMessage::LspNotification(LspNotification::CodeAction(
-                                    payload
-                                        .into_iter()
-                                        .map(|r| match r {
-                                            CodeActionOrCommand::Command(_) => todo!(),
-                                            CodeActionOrCommand::CodeAction(code_action) => {
-                                                code_action.try_into()
-                                            }
-                                        })
-                                        .collect::<Result<Vec<_>, _>>()?,
+                                    code_actions,
                                 )))
                                 .unwrap();
                         }
                     }
+                    "codeAction/resolve" => {
+                        let payload: <lsp_request!("codeAction/resolve") as Request>::Result =
+                            serde_json::from_value(response)?;
+
+                        self.app_message_sender
+                            .send(AppMessage::LspNotification(
+                                LspNotification::CodeActionResolve(payload.try_into()?),
+                            ))
+                            .unwrap();
+                    }
                     "textDocument/signatureHelp" => {
                         let payload: <lsp_request!("textDocument/signatureHelp") as Request>::Result =
                             serde_json::from_value(response)?;
@@ -1152,6 +1286,81 @@ impl LspServerProcess {
         )
     }
 
+    fn text_document_document_highlight(
+        &mut self,
+        params: RequestParams,
+    ) -> Result<(), anyhow::Error> {
+        if !self.has_capability(|c| c.document_highlight_provider.is_some()) {
+            return Ok(());
+        }
+        self.send_request::<lsp_request!("textDocument/documentHighlight")>(
+            params.context,
+            DocumentHighlightParams {
+                partial_result_params: Default::default(),
+                text_document_position_params: TextDocumentPositionParams {
+                    position: params.position.into(),
+                    text_document: path_buf_to_text_document_identifier(params.path)?,
+                },
+                work_done_progress_params: Default::default(),
+            },
+        )
+    }
+
+    fn text_document_selection_range(
+        &mut self,
+        params: RequestParams,
+    ) -> Result<(), anyhow::Error> {
+        if !self.has_capability(|c| c.selection_range_provider.is_some()) {
+            // So that the editor falls back to tree-sitter
+            self.app_message_sender.send(AppMessage::LspNotification(
+                LspNotification::SelectionRange(params.context, Vec::new()),
+            ))?;
+            return Ok(());
+        }
+        let result = self.send_request::<lsp_request!("textDocument/selectionRange")>(
+            params.context.clone(),
+            SelectionRangeParams {
+                partial_result_params: Default::default(),
+                text_document: path_buf_to_text_document_identifier(params.path)?,
+                positions: [params.position.into()].to_vec(),
+                work_done_progress_params: Default::default(),
+            },
+        );
+        if result.is_err() {
+            // So that the editor falls back to tree-sitter
+            self.app_message_sender.send(AppMessage::LspNotification(
+                LspNotification::SelectionRange(params.context, Vec::new()),
+            ))?;
+        }
+        result
+    }
+
+    fn text_document_range_formatting(
+        &mut self,
+        params: RequestParams,
+        range: std::ops::Range<crate::position::Position>,
+    ) -> Result<(), anyhow::Error> {
+        if !self.has_capability(|c| c.document_range_formatting_provider.is_some()) {
+            return Ok(());
+        }
+        self.send_request::<lsp_request!("textDocument/rangeFormatting")>(
+            params.context,
+            DocumentRangeFormattingParams {
+                text_document: path_buf_to_text_document_identifier(params.path)?,
+                range: Range {
+                    start: range.start.into(),
+                    end: range.end.into(),
+                },
+                options: FormattingOptions {
+                    tab_size: 4,
+                    insert_spaces: true,
+                    ..Default::default()
+                },
+                work_done_progress_params: Default::default(),
+            },
+        )
+    }
+
     fn text_document_prepare_rename(&mut self, params: RequestParams) -> Result<(), anyhow::Error> {
         if !self.has_capability(|c| c.rename_provider.is_some()) {
             return Ok(());
@@ -1190,6 +1399,7 @@ impl LspServerProcess {
         &mut self,
         params: RequestParams,
         diagnostics: Vec<Diagnostic>,
+        only: Option<Vec<CodeActionKind>>,
     ) -> Result<(), anyhow::Error> {
         if !self.has_capability(|c| c.code_action_provider.is_some()) {
             return Ok(());
@@ -1200,7 +1410,7 @@ impl LspServerProcess {
                 context: CodeActionContext {
                     diagnostics,
                     trigger_kind: None,
-                    only: None,
+                    only,
                 },
                 partial_result_params: Default::default(),
                 range: Range {
@@ -1213,6 +1423,24 @@ impl LspServerProcess {
         )
     }
 
+    fn text_document_organize_imports(
+        &mut self,
+        params: RequestParams,
+    ) -> Result<(), anyhow::Error> {
+        if !self.has_capability(|c| c.code_action_provider.is_some()) {
+            // So that the editor falls back to sorting imports
+            self.app_message_sender.send(AppMessage::LspNotification(
+                LspNotification::OrganizeImports(Vec::new()),
+            ))?;
+            return Ok(());
+        }
+        self.text_document_code_action(
+            params.set_description(ORGANIZE_IMPORTS),
+            Vec::new(),
+            Some([CodeActionKind::SOURCE_ORGANIZE_IMPORTS].to_vec()),
+        )
+    }
+
     pub(crate) fn text_document_signature_help(
         &mut self,
         params: RequestParams,
@@ -1286,6 +1514,34 @@ impl LspServerProcess {
         self.send_request::<lsp_request!("completionItem/resolve")>(params.context, completion_item)
     }
 
+    fn code_action_resolve(
+        &mut self,
+        params: RequestParams,
+        code_action: UnresolvedCodeAction,
+    ) -> Result<(), anyhow::Error> {
+        if !self.has_capability(|c| {
+            matches!(
+                c.code_action_provider,
+                Some(CodeActionProviderCapability::Options(CodeActionOptions {
+                    resolve_provider: Some(true),
+                    ..
+                }))
+            )
+        }) {
+            // Otherwise choosing the code action silently does nothing
+            self.app_message_sender
+                .send(AppMessage::LspNotification(LspNotification::Error(format!(
+                    "The code action {:?} cannot be applied, because it has no edit, and the language server does not support resolving code actions.",
+                    code_action.into_inner().title
+                ))))?;
+            return Ok(());
+        }
+        self.send_request::<lsp_request!("codeAction/resolve")>(
+            params.context,
+            code_action.into_inner(),
+        )
+    }
+
     fn handle_from_editor(&mut self, from_editor: &FromEditor) {
         log::info!(
             "LspServerProcess::handle_from_editor = {}",
@@ -1306,6 +1562,15 @@ impl LspServerProcess {
             FromEditor::TextDocumentTypeDefinition(params) => {
                 self.text_document_type_definition(params)
             }
+            FromEditor::TextDocumentDocumentHighlight(params) => {
+                self.text_document_document_highlight(params)
+            }
+            FromEditor::TextDocumentRangeFormatting { params, range } => {
+                self.text_document_range_formatting(params, range)
+            }
+            FromEditor::TextDocumentSelectionRange(params) => {
+                self.text_document_selection_range(params)
+            }
             FromEditor::TextDocumentRename { params, new_name } => {
                 self.text_document_rename(params, new_name)
             }
@@ -1315,7 +1580,10 @@ impl LspServerProcess {
             FromEditor::TextDocumentCodeAction {
                 params,
                 diagnostics,
-            } => self.text_document_code_action(params, diagnostics),
+            } => self.text_document_code_action(params, diagnostics, None),
+            FromEditor::TextDocumentOrganizeImports(params) => {
+                self.text_document_organize_imports(params)
+            }
             FromEditor::TextDocumentDocumentSymbol(params) => {
                 self.text_document_document_symbol(params)
             }
@@ -1346,6 +1614,10 @@ impl LspServerProcess {
                 completion_item,
                 params,
             } => self.completion_item_resolve(params, completion_item),
+            FromEditor::CodeActionResolve {
+                code_action,
+                params,
+            } => self.code_action_resolve(params, code_action),
         }
         .unwrap_or_else(|error| {
             log::info!("LspServerProcess::handle_from_editor | error={:?}", error);
@@ -2,14 +2,18 @@ mod buffer;
 mod git;
 
 pub(crate) mod char_index_range;
+mod char_info;
 mod cli;
 mod clipboard;
 pub(crate) mod command;
 mod components;
 mod context;
+mod date_time;
 mod edit;
+mod encoding;
 pub(crate) mod frontend;
 mod grid;
+mod headless;
 #[cfg(test)]
 mod integration_test;
 
@@ -19,18 +23,23 @@ mod lsp;
 mod position;
 
 mod app;
+mod arithmetic;
 pub(crate) mod history;
 mod non_empty_extensions;
 mod quickfix_list;
+mod recent_files;
 mod rectangle;
 mod screen;
+mod search_preset;
 mod selection;
 pub(crate) mod selection_mode;
 pub(crate) mod selection_range;
+mod snippet;
 pub(crate) mod soft_wrap;
 pub(crate) mod style;
 pub(crate) mod surround;
 pub(crate) mod syntax_highlight;
+mod task;
 mod terminal;
 #[cfg(test)]
 mod test_app;
@@ -74,6 +83,26 @@ pub(crate) fn run(config: RunConfig) -> anyhow::Result<()> {
         receiver,
     )?;
     app.set_syntax_highlight_request_sender(syntax_highlighter_sender);
+    match snippet::Snippets::load() {
+        Ok(snippets) => app.set_snippets(snippets),
+        Err(error) => log::error!("Failed to load snippets: {:?}", error),
+    }
+    match search_preset::SearchPresets::load() {
+        Ok(search_presets) => app.set_search_presets(search_presets),
+        Err(error) => log::error!("Failed to load search presets: {:?}", error),
+    }
+    match recent_files::RecentFiles::load() {
+        Ok(recent_files) => app.set_recent_files(recent_files),
+        Err(error) => log::error!("Failed to load recent files: {:?}", error),
+    }
+    match list::todo::TodoTag::load() {
+        Ok(todo_tags) => app.set_todo_tags(todo_tags),
+        Err(error) => log::error!("Failed to load TODO tags: {:?}", error),
+    }
+    match char_info::CharacterReplacement::load() {
+        Ok(character_replacements) => app.set_character_replacements(character_replacements),
+        Err(error) => log::error!("Failed to load character replacements: {:?}", error),
+    }
 
     let sender = app.sender();
 
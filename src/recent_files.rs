@@ -0,0 +1,119 @@
+//! Recently opened files, persisted in `recent_files.json` in the config directory,
+//! so that they can be reopened across sessions.
+
+use std::{path::PathBuf, sync::mpsc::Sender};
+
+use anyhow::Context;
+
+/// The maximum number of files to remember.
+const CAPACITY: usize = 100;
+
+#[derive(Default)]
+pub(crate) struct RecentFiles {
+    /// Sends the serialized files to the thread that persists them,
+    /// `None` means they only live in memory.
+    persister: Option<Sender<String>>,
+    /// The most recently opened file comes first.
+    files: Vec<PathBuf>,
+}
+
+impl RecentFiles {
+    pub(crate) fn load() -> anyhow::Result<RecentFiles> {
+        let path = grammar::config_dir().join("recent_files.json");
+        let files = if path.exists() {
+            let content = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&content)
+                .with_context(|| format!("Failed to parse recent files from {}", path.display()))?
+        } else {
+            Vec::new()
+        };
+        Ok(RecentFiles {
+            persister: Some(Self::spawn_persister(path)),
+            files,
+        })
+    }
+
+    /// Persist the files in another thread, so that opening a file is not blocked by the disk.
+    fn spawn_persister(path: PathBuf) -> Sender<String> {
+        let (sender, receiver) = std::sync::mpsc::channel::<String>();
+        std::thread::spawn(move || {
+            while let Ok(content) = receiver.recv() {
+                // Only the latest content is written when files are opened in quick succession
+                let content = receiver.try_iter().last().unwrap_or(content);
+                if let Err(error) = std::fs::write(&path, content) {
+                    log::error!(
+                        "Failed to save recent files to {}: {:?}",
+                        path.display(),
+                        error
+                    )
+                }
+            }
+        });
+        sender
+    }
+
+    pub(crate) fn files(&self) -> &[PathBuf] {
+        &self.files
+    }
+
+    /// Move `file` to the front, forget the least recent files beyond [`CAPACITY`],
+    /// then persist all files in the background.
+    pub(crate) fn push(&mut self, file: PathBuf) -> anyhow::Result<()> {
+        self.files.retain(|existing| existing != &file);
+        self.files.insert(0, file);
+        self.files.truncate(CAPACITY);
+        if let Some(persister) = &self.persister {
+            persister
+                .send(serde_json::to_string_pretty(&self.files)?)
+                .context("Failed to save recent files")?
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test_recent_files {
+    use std::path::PathBuf;
+
+    use super::{RecentFiles, CAPACITY};
+
+    #[test]
+    fn push_should_deduplicate_and_cap() -> anyhow::Result<()> {
+        let mut recent_files = RecentFiles::default();
+        for i in 0..CAPACITY + 1 {
+            recent_files.push(PathBuf::from(format!("{i}.rs")))?;
+        }
+        recent_files.push(PathBuf::from("50.rs"))?;
+        let files = recent_files.files();
+        assert_eq!(files.len(), CAPACITY);
+        assert_eq!(files[0], PathBuf::from("50.rs"));
+        assert_eq!(files[1], PathBuf::from(format!("{}.rs", CAPACITY)));
+        assert_eq!(
+            files.iter().filter(|file| file.ends_with("50.rs")).count(),
+            1
+        );
+        // The least recent file is forgotten
+        assert!(!files.contains(&PathBuf::from("0.rs")));
+        Ok(())
+    }
+
+    #[test]
+    fn push_should_persist_in_the_background() -> anyhow::Result<()> {
+        let directory = tempfile::tempdir()?;
+        let path = directory.path().join("recent_files.json");
+        let mut recent_files = RecentFiles {
+            persister: Some(RecentFiles::spawn_persister(path.clone())),
+            files: Vec::new(),
+        };
+        recent_files.push(PathBuf::from("a.rs"))?;
+        recent_files.push(PathBuf::from("b.rs"))?;
+
+        let expected = serde_json::to_string_pretty(recent_files.files())?;
+        let started_at = std::time::Instant::now();
+        while std::fs::read_to_string(&path).ok().as_ref() != Some(&expected) {
+            assert!(started_at.elapsed() < std::time::Duration::from_secs(5));
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        Ok(())
+    }
+}
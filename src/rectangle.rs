@@ -82,7 +82,7 @@ impl Border {
             .collect()
     }
 
-    fn new_vertical(start: Position, length: u16) -> Border {
+    pub(crate) fn new_vertical(start: Position, length: u16) -> Border {
         Border {
             direction: BorderDirection::Vertical,
             start,
@@ -0,0 +1,118 @@
+//! Named search/replace presets, persisted in `search_presets.json` in the config directory,
+//! so that frequently used patterns can be recalled instead of being retyped.
+
+use std::path::PathBuf;
+
+use anyhow::Context;
+use itertools::Itertools;
+
+use crate::{
+    app::Scope,
+    context::{GlobalSearchConfig, LocalSearchConfig, LocalSearchConfigMode},
+};
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct SearchPreset {
+    pub(crate) name: String,
+    pub(crate) search: String,
+    #[serde(default)]
+    pub(crate) replacement: String,
+    #[serde(default)]
+    pub(crate) mode: LocalSearchConfigMode,
+    /// Only applicable to global search.
+    #[serde(default)]
+    pub(crate) include_glob: Option<String>,
+    /// Only applicable to global search.
+    #[serde(default)]
+    pub(crate) exclude_glob: Option<String>,
+}
+
+impl SearchPreset {
+    pub(crate) fn new(
+        name: String,
+        local_config: &LocalSearchConfig,
+        global_config: Option<&GlobalSearchConfig>,
+    ) -> SearchPreset {
+        SearchPreset {
+            name,
+            search: local_config.search(),
+            replacement: local_config.replacement(),
+            mode: local_config.mode,
+            include_glob: global_config
+                .and_then(|config| config.include_glob())
+                .map(|glob| glob.to_string()),
+            exclude_glob: global_config
+                .and_then(|config| config.exclude_glob())
+                .map(|glob| glob.to_string()),
+        }
+    }
+
+    pub(crate) fn display(&self, scope: Scope) -> String {
+        let globs = [
+            self.include_glob.as_ref().map(|glob| format!("+{glob}")),
+            self.exclude_glob.as_ref().map(|glob| format!("-{glob}")),
+        ]
+        .into_iter()
+        .flatten()
+        .join(" ");
+        format!(
+            "{}: {} -> {} ({}){}",
+            self.name,
+            self.search,
+            self.replacement,
+            self.mode.display(),
+            if scope == Scope::Global && !globs.is_empty() {
+                format!(" [{globs}]")
+            } else {
+                String::new()
+            }
+        )
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct SearchPresets {
+    /// Where the presets are persisted, `None` means they only live in memory.
+    path: Option<PathBuf>,
+    presets: Vec<SearchPreset>,
+}
+
+impl SearchPresets {
+    pub(crate) fn load() -> anyhow::Result<SearchPresets> {
+        let path = grammar::config_dir().join("search_presets.json");
+        let presets = if path.exists() {
+            let content = std::fs::read_to_string(&path)?;
+            serde_json::from_str(&content).with_context(|| {
+                format!("Failed to parse search presets from {}", path.display())
+            })?
+        } else {
+            Vec::new()
+        };
+        Ok(SearchPresets {
+            path: Some(path),
+            presets,
+        })
+    }
+
+    pub(crate) fn presets(&self) -> &[SearchPreset] {
+        &self.presets
+    }
+
+    /// Replace the preset of the same name, otherwise add a new one,
+    /// then persist all presets.
+    pub(crate) fn save(&mut self, preset: SearchPreset) -> anyhow::Result<()> {
+        match self
+            .presets
+            .iter_mut()
+            .find(|existing| existing.name == preset.name)
+        {
+            Some(existing) => *existing = preset,
+            None => self.presets.push(preset),
+        }
+        if let Some(path) = &self.path {
+            std::fs::write(path, serde_json::to_string_pretty(&self.presets)?)
+                .with_context(|| format!("Failed to save search presets to {}", path.display()))?
+        }
+        Ok(())
+    }
+}
@@ -32,7 +32,11 @@ pub(crate) struct SelectionSet {
 /// 2. Pop latest filter
 /// 3. Clear all filters
 #[derive(Clone, Default, Debug, PartialEq, Eq)]
-pub(crate) struct Filters(Vec<Filter>);
+pub(crate) struct Filters {
+    filters: Vec<Filter>,
+    /// If defined, only items within this range are retained.
+    bound: Option<CharIndexRange>,
+}
 impl Filters {
     /// Returns `Some(item)` if it satisfy this `Filters`.
     pub(crate) fn retain(
@@ -40,27 +44,40 @@ impl Filters {
         buffer: &Buffer,
         item: selection_mode::ByteRange,
     ) -> Option<selection_mode::ByteRange> {
-        self.0
+        let item = match self.bound {
+            Some(bound) => {
+                let range = item.to_char_index_range(buffer).ok()?;
+                (bound.start <= range.start && range.end <= bound.end).then_some(item)?
+            }
+            None => item,
+        };
+        self.filters
             .iter()
             .try_fold(item, |item, filter| filter.retain(buffer, item))
     }
 
     fn push(self, filter: Filter) -> Filters {
-        let mut result = self.0;
-        result.push(filter);
-        Filters(result)
+        let mut filters = self.filters;
+        filters.push(filter);
+        Filters { filters, ..self }
     }
 
     pub(crate) fn display(&self) -> Option<String> {
-        if self.0.is_empty() {
+        if self.is_empty() {
             None
         } else {
-            Some(self.0.iter().map(|filter| filter.display()).join(", "))
+            Some(
+                self.bound
+                    .map(|bound| format!("Within[{}..{}]", bound.start.0, bound.end.0))
+                    .into_iter()
+                    .chain(self.filters.iter().map(|filter| filter.display()))
+                    .join(", "),
+            )
         }
     }
 
     pub(crate) fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.filters.is_empty() && self.bound.is_none()
     }
 }
 
@@ -82,6 +99,11 @@ impl Filter {
                 .ok()
                 .map(|rope| rope.to_string()),
             FilterTarget::Info => item.info().as_ref().map(|info| info.content().clone()),
+            FilterTarget::LineContent => buffer
+                .byte_to_char(item.range().start)
+                .and_then(|char_index| buffer.get_line_by_char_index(char_index))
+                .ok()
+                .map(|rope| rope.to_string()),
         }?;
         let matched: bool = match &self.mechanism {
             FilterMechanism::Literal(literal) => {
@@ -122,6 +144,8 @@ impl Filter {
 pub(crate) enum FilterTarget {
     Info,
     Content,
+    /// The content of the line where the selection starts.
+    LineContent,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq, Copy)]
@@ -177,6 +201,29 @@ impl SelectionSet {
         self.cursor_index = 0;
     }
 
+    /// Keep only the selection with the minimum `key`.
+    pub(crate) fn only_min_by_key<K: Ord, F: Fn(&Selection) -> K>(&mut self, key: F) {
+        if let Some((index, _)) = self
+            .selections
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, selection)| key(selection))
+        {
+            self.cursor_index = index;
+        }
+        self.only()
+    }
+
+    /// Make the next (or previous, if `backward` is true) selection the primary selection.
+    pub(crate) fn cycle_primary(&mut self, backward: bool) {
+        let len = self.selections.len();
+        self.cursor_index = if backward {
+            (self.cursor_index + len - 1) % len
+        } else {
+            (self.cursor_index + 1) % len
+        };
+    }
+
     pub(crate) fn apply<F>(&self, mode: SelectionMode, f: F) -> anyhow::Result<SelectionSet>
     where
         F: Fn(&Selection) -> anyhow::Result<Selection>,
@@ -377,6 +424,17 @@ impl SelectionSet {
         }
     }
 
+    /// Only retain selections within the given range, until the filters are cleared.
+    pub(crate) fn set_bound(self, bound: CharIndexRange) -> Self {
+        Self {
+            filters: Filters {
+                bound: Some(bound),
+                ..self.filters
+            },
+            ..self
+        }
+    }
+
     pub(crate) fn filter_clear(self) -> Self {
         Self {
             filters: Filters::default(),
@@ -438,7 +496,9 @@ pub(crate) enum SelectionMode {
     LineTrimmed,
     Column,
     Custom,
-    Find { search: Search },
+    Find {
+        search: Search,
+    },
 
     // Syntax-tree
     Token,
@@ -451,8 +511,17 @@ pub(crate) enum SelectionMode {
     // Git
     GitHunk(crate::git::DiffMode),
 
+    // Git & LSP
+    Attention {
+        diff_mode: crate::git::DiffMode,
+        severity_range: DiagnosticSeverityRange,
+        composition: selection_mode::AttentionComposition,
+    },
+
     // Local quickfix
-    LocalQuickfix { title: String },
+    LocalQuickfix {
+        title: String,
+    },
 
     // Bookmark
     Bookmark,
@@ -486,6 +555,19 @@ impl SelectionMode {
             SelectionMode::GitHunk(diff_mode) => {
                 format!("GIT HUNK ({})", diff_mode.display()).to_string()
             }
+            SelectionMode::Attention {
+                diff_mode,
+                severity_range,
+                composition,
+            } => {
+                let severity = format!("{:?}", severity_range).to_uppercase();
+                format!(
+                    "GIT HUNK ({}) {} DIAGNOSTIC:{}",
+                    diff_mode.display(),
+                    composition.display(),
+                    severity
+                )
+            }
             SelectionMode::Bookmark => "BOOKMARK".to_string(),
             SelectionMode::LocalQuickfix { title } => title.to_string(),
         }
@@ -540,6 +622,16 @@ impl SelectionMode {
             SelectionMode::GitHunk(diff_mode) => {
                 Box::new(selection_mode::GitHunk::new(diff_mode, buffer)?)
             }
+            SelectionMode::Attention {
+                diff_mode,
+                severity_range,
+                composition,
+            } => Box::new(selection_mode::Attention::new(
+                diff_mode,
+                *severity_range,
+                *composition,
+                params,
+            )?),
             SelectionMode::Bookmark => Box::new(selection_mode::Bookmark),
             SelectionMode::EmptyLine => Box::new(selection_mode::Regex::new(buffer, r"(?m)^\s*$")?),
             SelectionMode::LocalQuickfix { .. } => {
@@ -0,0 +1,66 @@
+use itertools::Itertools;
+
+use crate::{edit::is_overlapping, quickfix_list::DiagnosticSeverityRange};
+
+use super::{ByteRange, Diagnostic, GitHunk, SelectionMode, SelectionModeParams};
+
+/// How the git hunks and the diagnostics of `Attention` are composed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum AttentionComposition {
+    /// Only diagnostics that overlap with a git hunk.
+    Intersection,
+    /// Both git hunks and diagnostics.
+    Union,
+}
+
+impl AttentionComposition {
+    pub(crate) fn display(&self) -> &'static str {
+        match self {
+            AttentionComposition::Intersection => "∩",
+            AttentionComposition::Union => "∪",
+        }
+    }
+}
+
+pub(crate) struct Attention {
+    git_hunk: GitHunk,
+    diagnostic: Diagnostic,
+    composition: AttentionComposition,
+}
+
+impl Attention {
+    pub(crate) fn new(
+        diff_mode: &crate::git::DiffMode,
+        severity_range: DiagnosticSeverityRange,
+        composition: AttentionComposition,
+        params: SelectionModeParams<'_>,
+    ) -> anyhow::Result<Self> {
+        Ok(Self {
+            git_hunk: GitHunk::new(diff_mode, params.buffer)?,
+            diagnostic: Diagnostic::new(severity_range, params),
+            composition,
+        })
+    }
+}
+
+impl SelectionMode for Attention {
+    fn iter<'a>(
+        &'a self,
+        params: SelectionModeParams<'a>,
+    ) -> anyhow::Result<Box<dyn Iterator<Item = ByteRange> + 'a>> {
+        let hunks = self.git_hunk.iter(params.clone())?.collect_vec();
+        let diagnostics = self.diagnostic.iter(params)?;
+        Ok(match self.composition {
+            AttentionComposition::Intersection => Box::new(
+                diagnostics
+                    .filter(move |diagnostic| {
+                        hunks
+                            .iter()
+                            .any(|hunk| is_overlapping(hunk.range(), diagnostic.range()))
+                    })
+                    .sorted(),
+            ),
+            AttentionComposition::Union => Box::new(hunks.into_iter().chain(diagnostics).sorted()),
+        })
+    }
+}
@@ -1,4 +1,5 @@
 pub(crate) mod ast_grep;
+pub(crate) mod attention;
 pub(crate) mod bookmark;
 pub(crate) mod case_agnostic;
 pub(crate) mod column;
@@ -17,6 +18,7 @@ pub(crate) mod word_long;
 pub(crate) mod word_short;
 pub(crate) use self::regex::Regex;
 pub(crate) use ast_grep::AstGrep;
+pub(crate) use attention::{Attention, AttentionComposition};
 pub(crate) use bookmark::Bookmark;
 pub(crate) use case_agnostic::CaseAgnostic;
 pub(crate) use column::Column;
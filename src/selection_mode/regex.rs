@@ -8,25 +8,56 @@ pub(crate) struct Regex {
 }
 
 pub(crate) fn get_regex(pattern: &str, config: RegexConfig) -> anyhow::Result<regex::Regex> {
-    let pattern = if config.escaped {
+    let pattern = if config.match_whole_word {
+        whole_word(pattern, config.escaped)
+    } else if config.escaped {
         regex::escape(pattern)
     } else {
         pattern.to_string()
     };
-    let pattern = if config.match_whole_word {
-        format!("\\b{}\\b", pattern)
-    } else {
-        pattern
-    };
     let pattern = if config.case_sensitive {
         pattern
     } else {
         format!("(?i){}", pattern)
     };
+    let pattern = if config.multiline {
+        format!("(?s){}", pattern)
+    } else {
+        pattern
+    };
     let pattern = format!("(?m){}", pattern);
     Ok(regex::Regex::new(&pattern)?)
 }
 
+/// `\b` only holds between a word character and a non-word character,
+/// so it is only placed on the side of a literal pattern that begins/ends with a word character.
+/// Otherwise, searching for `$foo` would never match after a space.
+///
+/// Both `\b` and `is_word_char` are Unicode-aware, so identifiers like `café` are respected.
+fn whole_word(pattern: &str, escaped: bool) -> String {
+    fn is_word_char(c: char) -> bool {
+        c.is_alphanumeric() || c == '_'
+    }
+    if escaped {
+        let boundary = |c: Option<char>| {
+            if c.map(is_word_char).unwrap_or(false) {
+                "\\b"
+            } else {
+                ""
+            }
+        };
+        format!(
+            "{}{}{}",
+            boundary(pattern.chars().next()),
+            regex::escape(pattern),
+            boundary(pattern.chars().last())
+        )
+    } else {
+        // The group is necessary so that the boundaries apply to every alternative
+        format!("\\b(?:{})\\b", pattern)
+    }
+}
+
 impl Regex {
     pub(crate) fn from_config(
         buffer: &Buffer,
@@ -47,6 +78,7 @@ impl Regex {
                 escaped: false,
                 case_sensitive: false,
                 match_whole_word: false,
+                multiline: false,
             },
         )?;
         Ok(Self {
@@ -84,6 +116,7 @@ mod test_regex {
                 escaped: true,
                 case_sensitive: false,
                 match_whole_word: false,
+                multiline: false,
             },
         )
         .unwrap()
@@ -100,6 +133,7 @@ mod test_regex {
                 escaped: false,
                 case_sensitive: false,
                 match_whole_word: false,
+                multiline: false,
             },
         )
         .unwrap()
@@ -120,6 +154,7 @@ mod test_regex {
                 escaped: false,
                 case_sensitive: false,
                 match_whole_word: false,
+                multiline: false,
             },
         )
         .unwrap()
@@ -140,6 +175,7 @@ mod test_regex {
                 escaped: false,
                 case_sensitive: false,
                 match_whole_word: true,
+                multiline: false,
             },
         )
         .unwrap()
@@ -164,6 +200,7 @@ mod test_regex {
                 escaped: false,
                 case_sensitive: false,
                 match_whole_word: false,
+                multiline: false,
             },
         )
         .unwrap()
@@ -173,4 +210,70 @@ mod test_regex {
             &[(1..8, "- [ ] a"), (9..16, "- [ ] b"), (28..35, "- [ ] d")],
         );
     }
+
+    #[test]
+    fn multiline() {
+        let buffer = Buffer::new(None, "a /* b\nc */ d /* e */");
+        let config = |multiline: bool| RegexConfig {
+            escaped: false,
+            case_sensitive: false,
+            match_whole_word: false,
+            multiline,
+        };
+        crate::selection_mode::Regex::from_config(&buffer, r"/\*.*?\*/", config(true))
+            .unwrap()
+            .assert_all_selections(
+                &buffer,
+                Selection::default(),
+                &[(2..11, "/* b\nc */"), (14..21, "/* e */")],
+            );
+        crate::selection_mode::Regex::from_config(&buffer, r"/\*.*?\*/", config(false))
+            .unwrap()
+            .assert_all_selections(&buffer, Selection::default(), &[(14..21, "/* e */")]);
+    }
+
+    #[test]
+    fn match_whole_word_unicode() {
+        let buffer = Buffer::new(None, "café cafés xcafé naïve_ naïve");
+        let config = |escaped: bool| RegexConfig {
+            escaped,
+            case_sensitive: false,
+            match_whole_word: true,
+            multiline: false,
+        };
+        crate::selection_mode::Regex::from_config(&buffer, "café", config(true))
+            .unwrap()
+            .assert_all_selections(&buffer, Selection::default(), &[(0..5, "café")]);
+        crate::selection_mode::Regex::from_config(&buffer, "naïve", config(true))
+            .unwrap()
+            .assert_all_selections(&buffer, Selection::default(), &[(28..34, "naïve")]);
+        crate::selection_mode::Regex::from_config(&buffer, "café|naïve", config(false))
+            .unwrap()
+            .assert_all_selections(
+                &buffer,
+                Selection::default(),
+                &[(0..5, "café"), (28..34, "naïve")],
+            );
+    }
+
+    #[test]
+    fn match_whole_word_literal_with_non_word_edges() {
+        let buffer = Buffer::new(None, "$foo $foobar a$foo");
+        crate::selection_mode::Regex::from_config(
+            &buffer,
+            "$foo",
+            RegexConfig {
+                escaped: true,
+                case_sensitive: false,
+                match_whole_word: true,
+                multiline: false,
+            },
+        )
+        .unwrap()
+        .assert_all_selections(
+            &buffer,
+            Selection::default(),
+            &[(0..4, "$foo"), (14..18, "$foo")],
+        );
+    }
 }
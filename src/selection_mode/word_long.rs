@@ -4,13 +4,15 @@ pub struct WordLong;
 
 impl WordLong {
     pub(crate) fn as_regex(buffer: &Buffer) -> anyhow::Result<super::Regex> {
+        let word_chars = regex::escape(buffer.word_chars());
         super::Regex::from_config(
             buffer,
-            r"((\w|-)+)|([^a-zA-Z\d\s])",
+            &format!(r"([\w{word_chars}]+)|([^a-zA-Z\d\s])"),
             crate::list::grep::RegexConfig {
                 escaped: false,
                 case_sensitive: false,
                 match_whole_word: false,
+                multiline: false,
             },
         )
     }
@@ -48,4 +50,17 @@ mod test_word_long {
             ],
         );
     }
+
+    #[test]
+    fn word_chars_of_language() {
+        let mut buffer = Buffer::new(None, "$foo a-b");
+        buffer
+            .set_language(shared::language::from_extension("js").unwrap())
+            .unwrap();
+        WordLong::as_regex(&buffer).unwrap().assert_all_selections(
+            &buffer,
+            Selection::default(),
+            &[(0..4, "$foo"), (5..8, "a-b")],
+        );
+    }
 }
@@ -3,14 +3,21 @@ use crate::buffer::Buffer;
 pub struct WordShort;
 
 impl WordShort {
+    /// Word characters other than `-` may prefix a segment, for example `$` in `$fooBar`,
+    /// while `-` stays a separator so that kebab-case is still broken into segments.
     pub(crate) fn as_regex(buffer: &Buffer) -> anyhow::Result<super::Regex> {
+        let prefix = match buffer.word_chars().replace('-', "") {
+            word_chars if word_chars.is_empty() => String::new(),
+            word_chars => format!("[{}]*", regex::escape(&word_chars)),
+        };
         super::Regex::from_config(
             buffer,
-            r"((([a-z]+)|(([A-Z]{2,})+)|([A-Z][a-z]*))_*)|([^\w\s]|_)|[0-9]+",
+            &format!(r"({prefix}(([a-z]+)|(([A-Z]{{2,}})+)|([A-Z][a-z]*))_*)|([^\w\s]|_)|[0-9]+"),
             crate::list::grep::RegexConfig {
                 escaped: false,
                 case_sensitive: true,
                 match_whole_word: false,
+                multiline: false,
             },
         )
     }
@@ -51,4 +58,23 @@ mod test_word_short {
             ],
         );
     }
+
+    #[test]
+    fn word_chars_of_language() {
+        let mut buffer = Buffer::new(None, "$fooBar a-b");
+        buffer
+            .set_language(shared::language::from_extension("js").unwrap())
+            .unwrap();
+        WordShort::as_regex(&buffer).unwrap().assert_all_selections(
+            &buffer,
+            Selection::default(),
+            &[
+                (0..4, "$foo"),
+                (4..7, "Bar"),
+                (8..9, "a"),
+                (9..10, "-"),
+                (10..11, "b"),
+            ],
+        );
+    }
 }
@@ -0,0 +1,165 @@
+//! User-defined snippets, loaded from `snippets.json` in the config directory.
+//!
+//! The file maps a language id to the snippets of that language, keyed by prefix, for example:
+//!
+//! ```json
+//! { "rust": { "fn": "fn ${1:name}($2) {\n    $0\n}" } }
+//! ```
+//!
+//! Snippet bodies support `$1`, `${1:placeholder}` and the final tabstop `$0`.
+//! A literal `$` or `}` can be escaped with a backslash.
+
+use std::collections::HashMap;
+
+use anyhow::Context;
+use itertools::Itertools;
+
+use crate::{char_index_range::CharIndexRange, selection::CharIndex};
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, serde::Deserialize)]
+pub(crate) struct Snippets(HashMap<String, HashMap<String, String>>);
+
+impl Snippets {
+    pub(crate) fn load() -> anyhow::Result<Snippets> {
+        let path = grammar::config_dir().join("snippets.json");
+        if !path.exists() {
+            return Ok(Default::default());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse snippets from {}", path.display()))
+    }
+
+    #[cfg(test)]
+    pub(crate) fn new(snippets: &[(&str, &str, &str)]) -> Snippets {
+        let mut result = Snippets::default();
+        for (language_id, prefix, body) in snippets {
+            result
+                .0
+                .entry(language_id.to_string())
+                .or_default()
+                .insert(prefix.to_string(), body.to_string());
+        }
+        result
+    }
+
+    pub(crate) fn get(&self, language_id: &str, prefix: &str) -> Option<Snippet> {
+        Some(Snippet::parse(self.0.get(language_id)?.get(prefix)?))
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct Tabstop {
+    pub(crate) number: usize,
+    pub(crate) range: CharIndexRange,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct Snippet {
+    pub(crate) text: String,
+    /// Relative to the start of `text`, sorted in the order of navigation,
+    /// that is `$1`, `$2`, ..., and finally `$0`.
+    pub(crate) tabstops: Vec<Tabstop>,
+}
+
+impl Snippet {
+    pub(crate) fn parse(body: &str) -> Snippet {
+        let mut text = String::new();
+        let mut tabstops = Vec::new();
+        let mut chars = body.chars().peekable();
+        let is_escapable = |c: Option<&char>| matches!(c, Some('$' | '}' | '\\'));
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' if is_escapable(chars.peek()) => text.extend(chars.next()),
+                '$' if chars.peek().map(char::is_ascii_digit).unwrap_or(false) => {
+                    let number = chars.peeking_take_while(char::is_ascii_digit).join("");
+                    let start = text.chars().count();
+                    tabstops.push(Tabstop {
+                        number: number.parse().unwrap_or_default(),
+                        range: (CharIndex(start)..CharIndex(start)).into(),
+                    });
+                }
+                '$' if chars.peek() == Some(&'{') => {
+                    chars.next();
+                    let number = chars.peeking_take_while(char::is_ascii_digit).join("");
+                    let start = text.chars().count();
+                    if chars.next_if_eq(&':').is_some() {
+                        while let Some(c) = chars.next() {
+                            match c {
+                                '}' => break,
+                                '\\' if is_escapable(chars.peek()) => text.extend(chars.next()),
+                                c => text.push(c),
+                            }
+                        }
+                    } else {
+                        chars.next_if_eq(&'}');
+                    }
+                    let end = text.chars().count();
+                    tabstops.push(Tabstop {
+                        number: number.parse().unwrap_or_default(),
+                        range: (CharIndex(start)..CharIndex(end)).into(),
+                    });
+                }
+                c => text.push(c),
+            }
+        }
+        if !tabstops.iter().any(|tabstop| tabstop.number == 0) {
+            let end = text.chars().count();
+            tabstops.push(Tabstop {
+                number: 0,
+                range: (CharIndex(end)..CharIndex(end)).into(),
+            })
+        }
+        let tabstops = tabstops
+            .into_iter()
+            .sorted_by_key(|tabstop| (tabstop.number == 0, tabstop.number))
+            .collect_vec();
+        Snippet { text, tabstops }
+    }
+}
+
+#[cfg(test)]
+mod test_snippet {
+    use super::{Snippet, Tabstop};
+    use crate::selection::CharIndex;
+
+    fn tabstop(number: usize, start: usize, end: usize) -> Tabstop {
+        Tabstop {
+            number,
+            range: (CharIndex(start)..CharIndex(end)).into(),
+        }
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            Snippet::parse("fn ${1:name}($2) {\n    $0\n}"),
+            Snippet {
+                text: "fn name() {\n    \n}".to_string(),
+                tabstops: vec![tabstop(1, 3, 7), tabstop(2, 8, 8), tabstop(0, 16, 16)]
+            }
+        );
+    }
+
+    #[test]
+    fn parse_should_add_final_tabstop_at_the_end_if_absent() {
+        assert_eq!(
+            Snippet::parse("x${1}y"),
+            Snippet {
+                text: "xy".to_string(),
+                tabstops: vec![tabstop(1, 1, 1), tabstop(0, 2, 2)]
+            }
+        );
+    }
+
+    #[test]
+    fn parse_escapes() {
+        assert_eq!(
+            Snippet::parse(r"\$1 ${1:a\}b} $"),
+            Snippet {
+                text: "$1 a}b $".to_string(),
+                tabstops: vec![tabstop(1, 3, 6), tabstop(0, 8, 8)]
+            }
+        );
+    }
+}
@@ -1,4 +1,9 @@
-use std::{collections::HashMap, ops::Range, sync::mpsc::Sender, time::Duration};
+use std::{
+    collections::HashMap,
+    ops::Range,
+    sync::{mpsc::Sender, Arc, Mutex},
+    time::Duration,
+};
 
 use tree_sitter_highlight::{HighlightConfiguration, HighlightEvent, Highlighter};
 
@@ -49,12 +54,40 @@ impl GetHighlightConfig for Language {
     }
 }
 
+/// The number of bytes of source code covered by each chunk streamed by
+/// `Highlight::highlight_in_chunks`.
+pub(crate) const HIGHLIGHT_CHUNK_SIZE: usize = 64 * 1024;
+
 pub trait Highlight {
-    fn highlight(&self, source_code: &str) -> anyhow::Result<HighlighedSpans>;
+    fn highlight(&self, source_code: &str) -> anyhow::Result<HighlighedSpans> {
+        let mut highlighted_spans = vec![];
+        self.highlight_in_chunks(source_code, usize::MAX, &mut |_, chunk| {
+            highlighted_spans.extend(chunk.0);
+            true
+        })?;
+        Ok(HighlighedSpans(highlighted_spans))
+    }
+
+    /// Highlight `source_code` progressively, calling `on_chunk` with the byte range
+    /// covered by each chunk and the spans within it.
+    /// The chunks are contiguous and together cover the whole `source_code`.
+    ///
+    /// Highlighting stops early if `on_chunk` returns `false`.
+    fn highlight_in_chunks(
+        &self,
+        source_code: &str,
+        chunk_size: usize,
+        on_chunk: &mut dyn FnMut(Range<usize>, HighlighedSpans) -> bool,
+    ) -> anyhow::Result<()>;
 }
 
 impl Highlight for HighlightConfiguration {
-    fn highlight(&self, source_code: &str) -> anyhow::Result<HighlighedSpans> {
+    fn highlight_in_chunks(
+        &self,
+        source_code: &str,
+        chunk_size: usize,
+        on_chunk: &mut dyn FnMut(Range<usize>, HighlighedSpans) -> bool,
+    ) -> anyhow::Result<()> {
         let mut highlighter = Highlighter::new();
 
         let highlights = highlighter.highlight(self, source_code.as_bytes(), None, |_| None)?;
@@ -62,6 +95,7 @@ impl Highlight for HighlightConfiguration {
         let mut highlight = None;
 
         let mut highlighted_spans = vec![];
+        let mut chunk_start = 0;
 
         for event in highlights {
             match event? {
@@ -81,10 +115,23 @@ impl Highlight for HighlightConfiguration {
                             });
                         }
                     }
+                    // Chunks are only cut at the end of a source event,
+                    // so that no span crosses the boundary of two chunks
+                    if end.saturating_sub(chunk_start) >= chunk_size {
+                        let spans = HighlighedSpans(std::mem::take(&mut highlighted_spans));
+                        if !on_chunk(chunk_start..end, spans) {
+                            return Ok(());
+                        }
+                        chunk_start = end;
+                    }
                 }
             }
         }
-        Ok(HighlighedSpans(highlighted_spans))
+        on_chunk(
+            chunk_start..source_code.len(),
+            HighlighedSpans(highlighted_spans),
+        );
+        Ok(())
     }
 }
 
@@ -103,6 +150,9 @@ impl HighlighedSpans {
 
 pub(crate) struct SyntaxHighlightRequest {
     pub(crate) component_id: ComponentId,
+    /// Identifies this request among the requests of the same component,
+    /// so that chunks of an outdated highlighting pass can be discarded.
+    pub(crate) request_id: usize,
     pub(crate) language: Language,
     pub(crate) source_code: String,
 }
@@ -117,23 +167,47 @@ pub(crate) fn start_thread(callback: Sender<AppMessage>) -> Sender<SyntaxHighlig
         }
     }
 
+    // The latest request of each component, used to abort outdated highlighting passes
+    // as soon as the content changes, so that edits stay responsive.
+    let latest_request_ids = Arc::new(Mutex::new(HashMap::<ComponentId, usize>::new()));
+
     std::thread::spawn(move || {
         let mut highlight_configs = HighlightConfigs::new();
+        let latest = latest_request_ids.clone();
         let debounce = EventDebouncer::new(Duration::from_millis(150), move |Event(request)| {
-            match highlight_configs.highlight(request.language, &request.source_code) {
-                Ok(highlighted_spans) => {
-                    let _ = callback.send(AppMessage::SyntaxHighlightResponse {
-                        component_id: request.component_id,
-                        highlighted_spans,
-                    });
-                }
-                Err(error) => {
-                    log::info!("syntax_highlight_error = {:#?}", error)
-                }
+            let is_outdated = || {
+                latest
+                    .lock()
+                    .map(|latest| latest.get(&request.component_id) != Some(&request.request_id))
+                    .unwrap_or(false)
+            };
+            let result = highlight_configs.highlight_in_chunks(
+                request.language,
+                &request.source_code,
+                HIGHLIGHT_CHUNK_SIZE,
+                &mut |byte_range, highlighted_spans| {
+                    if is_outdated() {
+                        return false;
+                    }
+                    callback
+                        .send(AppMessage::SyntaxHighlightResponse {
+                            component_id: request.component_id,
+                            request_id: request.request_id,
+                            byte_range,
+                            highlighted_spans,
+                        })
+                        .is_ok()
+                },
+            );
+            if let Err(error) = result {
+                log::info!("syntax_highlight_error = {:#?}", error)
             }
         });
 
         while let Ok(request) = receiver.recv() {
+            if let Ok(mut latest) = latest_request_ids.lock() {
+                latest.insert(request.component_id, request.request_id);
+            }
             debounce.put(Event(request))
         }
     });
@@ -156,23 +230,40 @@ impl HighlightConfigs {
         language: Language,
         source_code: &str,
     ) -> Result<HighlighedSpans, anyhow::Error> {
+        match self.get(language)? {
+            Some(config) => config.highlight(source_code),
+            None => Ok(Default::default()),
+        }
+    }
+
+    pub(crate) fn highlight_in_chunks(
+        &mut self,
+        language: Language,
+        source_code: &str,
+        chunk_size: usize,
+        on_chunk: &mut dyn FnMut(Range<usize>, HighlighedSpans) -> bool,
+    ) -> Result<(), anyhow::Error> {
+        match self.get(language)? {
+            Some(config) => config.highlight_in_chunks(source_code, chunk_size, on_chunk),
+            None => {
+                on_chunk(0..source_code.len(), Default::default());
+                Ok(())
+            }
+        }
+    }
+
+    fn get(&mut self, language: Language) -> anyhow::Result<Option<&HighlightConfiguration>> {
         let Some(grammar_id) = language.tree_sitter_grammar_id() else {
-            return Ok(Default::default());
+            return Ok(None);
         };
-        let config = match self.0.get(&grammar_id) {
-            Some(config) => config,
-            None => {
-                if let Some(highlight_config) = language.get_highlight_config()? {
+        if !self.0.contains_key(&grammar_id) {
+            match language.get_highlight_config()? {
+                Some(highlight_config) => {
                     self.0.insert(grammar_id.clone(), highlight_config);
-                    let get_error = || {
-                        anyhow::anyhow!("Unreachable: should be able to obtain a value that is inserted to the HashMap")
-                    };
-                    self.0.get(&grammar_id).ok_or_else(get_error)?
-                } else {
-                    return Ok(Default::default());
                 }
+                None => return Ok(None),
             }
-        };
-        config.highlight(source_code)
+        }
+        Ok(self.0.get(&grammar_id))
     }
 }
@@ -0,0 +1,162 @@
+//! Project tasks, such as build, test or lint, defined in `.ki/tasks.json` of the working directory,
+//! for example:
+//!
+//! ```json
+//! [{ "name": "check", "command": "cargo check --message-format short" }]
+//! ```
+//!
+//! The output of a task is parsed into a quickfix list, see [`parse_error_locations`].
+
+use anyhow::Context;
+use shared::canonicalized_path::CanonicalizedPath;
+
+use crate::{
+    components::suggestive_editor::Info,
+    position::Position,
+    quickfix_list::{Location, QuickfixListItem},
+};
+
+/// Matches `path:line`, `path:line:column` and `path:line:column: message`,
+/// including the `--> path:line:column` of rustc.
+pub(crate) const DEFAULT_ERROR_PATTERN: &str = r"(?m)^\s*(?:--> )?(?P<path>[^\s:]+):(?P<line>\d+)(?::(?P<column>\d+))?(?::\s*(?P<message>.*))?";
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub(crate) struct Task {
+    pub(crate) name: String,
+    /// Run with `sh -c` in the working directory.
+    pub(crate) command: String,
+    /// See [`parse_error_locations`], defaults to [`DEFAULT_ERROR_PATTERN`].
+    #[serde(default)]
+    pub(crate) error_pattern: Option<String>,
+}
+
+impl Task {
+    /// Returns no tasks if `.ki/tasks.json` does not exist.
+    pub(crate) fn load(working_directory: &CanonicalizedPath) -> anyhow::Result<Vec<Task>> {
+        let path = working_directory
+            .to_path_buf()
+            .join(".ki")
+            .join("tasks.json");
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse tasks from {}", path.display()))
+    }
+
+    pub(crate) fn error_pattern(&self) -> &str {
+        self.error_pattern
+            .as_deref()
+            .unwrap_or(DEFAULT_ERROR_PATTERN)
+    }
+
+    /// Returns whether the command succeeded, and its stdout followed by its stderr.
+    pub(crate) fn run(
+        &self,
+        working_directory: &CanonicalizedPath,
+    ) -> anyhow::Result<(bool, String)> {
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(&self.command)
+            .current_dir(working_directory.to_path_buf())
+            .output()?;
+        Ok((
+            output.status.success(),
+            format!(
+                "{}{}",
+                String::from_utf8_lossy(&output.stdout),
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        ))
+    }
+}
+
+/// Find the locations in `output` that match `pattern`, which must have the named groups
+/// `path` and `line`, and optionally `column` and `message`.
+///
+/// Lines and columns are 1-based, and relative paths are resolved against `working_directory`.
+/// Locations of files that do not exist are skipped.
+pub(crate) fn parse_error_locations(
+    output: &str,
+    pattern: &str,
+    working_directory: &CanonicalizedPath,
+) -> anyhow::Result<Vec<QuickfixListItem>> {
+    let regex = regex::Regex::new(pattern)?;
+    Ok(regex
+        .captures_iter(output)
+        .filter_map(|captures| {
+            let path = working_directory
+                .join(captures.name("path")?.as_str())
+                .ok()?;
+            let number = |name: &str| -> Option<usize> {
+                Some(captures.name(name)?.as_str().parse::<usize>().ok()?.max(1) - 1)
+            };
+            let position = Position {
+                line: number("line")?,
+                column: number("column").unwrap_or(0),
+            };
+            let info = captures
+                .name("message")
+                .map(|message| message.as_str().trim())
+                .filter(|message| !message.is_empty())
+                .map(|message| Info::new("Error".to_string(), message.to_string()));
+            Some(QuickfixListItem::new(
+                Location {
+                    path,
+                    range: position..position,
+                },
+                info,
+            ))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test_task {
+    use shared::canonicalized_path::CanonicalizedPath;
+
+    use super::{parse_error_locations, DEFAULT_ERROR_PATTERN};
+    use crate::position::Position;
+
+    #[test]
+    fn parse_compiler_output() -> anyhow::Result<()> {
+        let working_directory: CanonicalizedPath = env!("CARGO_MANIFEST_DIR").try_into()?;
+        let output = "
+error[E0308]: mismatched types
+ --> src/main.rs:3:5
+src/main.rs:10:2: warning: unused variable
+src/missing.rs:1:1: error: skipped because the file does not exist
+Cargo.toml:7
+";
+        let items = parse_error_locations(output, DEFAULT_ERROR_PATTERN, &working_directory)?;
+        assert_eq!(
+            items
+                .iter()
+                .map(|item| (
+                    item.location().path.clone(),
+                    item.location().range.start,
+                    item.info().as_ref().map(|info| info.content().clone())
+                ))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    working_directory.join("src/main.rs")?,
+                    Position { line: 2, column: 4 },
+                    None
+                ),
+                (
+                    working_directory.join("src/main.rs")?,
+                    Position { line: 9, column: 1 },
+                    Some("warning: unused variable".to_string())
+                ),
+                (
+                    working_directory.join("Cargo.toml")?,
+                    Position { line: 6, column: 0 },
+                    None
+                ),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -23,7 +23,10 @@ pub(crate) use SelectionMode::*;
 use shared::canonicalized_path::CanonicalizedPath;
 
 use crate::{
-    app::{App, Dimension, Dispatch, LocalSearchConfigUpdate, RequestParams, Scope},
+    app::{
+        App, Dimension, Dispatch, FilePickerKind, GlobalSearchConfigUpdate, GlobalSearchFilterGlob,
+        LocalSearchConfigUpdate, RequestParams, Scope,
+    },
     char_index_range::CharIndexRange,
     clipboard::CopiedTexts,
     components::{
@@ -47,6 +50,7 @@ use crate::{
     position::Position,
     quickfix_list::{DiagnosticSeverityRange, Location, QuickfixListItem},
     selection::SelectionMode,
+    selection_mode::AttentionComposition,
     style::Style,
     themes::Theme,
     ui_tree::ComponentKind,
@@ -84,6 +88,7 @@ pub(crate) enum ExpectKind {
     FileContent(CanonicalizedPath, String),
     FileContentEqual(CanonicalizedPath, CanonicalizedPath),
     CurrentSelectedTexts(&'static [&'static str]),
+    CurrentPrimarySelection(&'static str),
     CurrentCursorDirection(Direction),
     CurrentViewAlignment(Option<ViewAlignment>),
     ComponentsLength(usize),
@@ -105,6 +110,7 @@ pub(crate) enum ExpectKind {
     ComponentCount(usize),
     CurrentComponentPath(Option<CanonicalizedPath>),
     OpenedFilesCount(usize),
+    ScratchEditorsCount(usize),
     QuickfixListInfo(&'static str),
     ComponentsOrder(Vec<ComponentKind>),
     CurrentComponentTitle(&'static str),
@@ -144,6 +150,17 @@ impl ExpectKind {
             CurrentSelectedTexts(selected_texts) => {
                 contextualize(app.get_current_selected_texts(), to_vec(selected_texts))
             }
+            CurrentPrimarySelection(expected) => {
+                let component = component.borrow();
+                let editor = component.editor();
+                contextualize(
+                    editor
+                        .buffer()
+                        .slice(&editor.selection_set.primary_selection().extended_range())?
+                        .to_string(),
+                    expected.to_string(),
+                )
+            }
             ComponentsLength(length) => contextualize(app.components().len(), *length),
             Quickfixes(expected_quickfixes) => contextualize(
                 app.get_quickfix_list()
@@ -329,6 +346,7 @@ impl ExpectKind {
                 contextualize(expected, &app.current_component().borrow().path())
             }
             OpenedFilesCount(expected) => contextualize(expected, &app.opened_files_count()),
+            ScratchEditorsCount(expected) => contextualize(expected, &app.scratch_editors_count()),
             QuickfixListInfo(expected) => {
                 contextualize(*expected, &app.quickfix_list_info().unwrap())
             }
@@ -804,6 +822,53 @@ pub(crate) fn repo_git_hunks() -> Result<(), anyhow::Error> {
     })
 }
 
+#[test]
+fn attention_selection_mode() -> Result<(), anyhow::Error> {
+    let run_test = |composition: AttentionComposition, expected: &'static [&'static str]| {
+        execute_test(|s| {
+            let diagnostic = |line: u32, start: u32, end: u32| lsp_types::Diagnostic {
+                range: lsp_types::Range::new(
+                    lsp_types::Position {
+                        line,
+                        character: start,
+                    },
+                    lsp_types::Position {
+                        line,
+                        character: end,
+                    },
+                ),
+                message: "Oops".to_string(),
+                ..Default::default()
+            };
+            Box::new([
+                // Change the first line of foo.rs
+                App(OpenFile(s.foo_rs())),
+                Editor(Insert("// Hello".to_string())),
+                App(SaveAll),
+                App(HandleLspNotification(LspNotification::PublishDiagnostics(
+                    lsp_types::PublishDiagnosticsParams {
+                        uri: s.foo_rs().to_url().unwrap(),
+                        diagnostics: [diagnostic(0, 0, 2), diagnostic(1, 4, 5)].to_vec(),
+                        version: None,
+                    },
+                ))),
+                Editor(SetSelectionMode(SelectionMode::Attention {
+                    diff_mode: crate::git::DiffMode::UnstagedAgainstCurrentBranch,
+                    severity_range: DiagnosticSeverityRange::All,
+                    composition,
+                })),
+                Editor(CursorAddToAllSelections),
+                Expect(CurrentSelectedTexts(expected)),
+            ])
+        })
+    };
+    run_test(AttentionComposition::Intersection, &["//"])?;
+    run_test(
+        AttentionComposition::Union,
+        &["//", "// Hellopub(crate) struct Foo {\n", "a"],
+    )
+}
+
 #[test]
 pub(crate) fn non_git_ignored_files() -> Result<(), anyhow::Error> {
     execute_test(|s| {
@@ -971,6 +1036,7 @@ fn local_lsp_references() -> anyhow::Result<()> {
                 crate::lsp::process::ResponseContext {
                     scope: Some(Scope::Local),
                     description: None,
+                    path: None,
                 },
                 [
                     Location {
@@ -1114,13 +1180,14 @@ fn global_search_replace_regex() -> Result<(), anyhow::Error> {
             escaped: true,
             case_sensitive: false,
             match_whole_word: false,
+            multiline: false,
         }),
-        main_content: "main foo",
-        foo_content: "foo foo",
+        main_content: "main foo\n",
+        foo_content: "foo foo\n",
         search: "foo",
         replacement: "haha",
-        main_replaced: "main haha",
-        foo_replaced: "haha haha",
+        main_replaced: "main haha\n",
+        foo_replaced: "haha haha\n",
     })
 }
 
@@ -1143,12 +1210,12 @@ fn global_search_replace_ast_grep() -> Result<(), anyhow::Error> {
 fn global_search_replace_case_agnostic() -> Result<(), anyhow::Error> {
     test_global_search_replace(TestGlobalSearchReplaceArgs {
         mode: LocalSearchConfigMode::CaseAgnostic,
-        main_content: "HelloWorld, this is good",
-        foo_content: "im-lisp (hello-world and say 'HELLO_WORLD')",
+        main_content: "HelloWorld, this is good\n",
+        foo_content: "im-lisp (hello-world and say 'HELLO_WORLD')\n",
         search: "hello world",
         replacement: "bye sky",
-        main_replaced: "ByeSky, this is good",
-        foo_replaced: "im-lisp (bye-sky and say 'BYE_SKY')",
+        main_replaced: "ByeSky, this is good\n",
+        foo_replaced: "im-lisp (bye-sky and say 'BYE_SKY')\n",
     })
 }
 
@@ -1232,6 +1299,70 @@ foo a // Line 10
     })
 }
 
+#[test]
+fn global_search_open_buffers_only() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            // Not saved, so only the buffer contains it
+            Editor(SetContent("foo d\nfoo c".to_string())),
+            App(UpdateGlobalSearchConfig {
+                update: GlobalSearchConfigUpdate::SetOpenBuffersOnly(true),
+            }),
+            App(UpdateLocalSearchConfig {
+                update: LocalSearchConfigUpdate::Search("foo".to_string()),
+                scope: Scope::Global,
+                show_config_after_enter: false,
+            }),
+            // foo.rs is not opened, so it is not searched
+            Expect(QuickfixListContent(
+                format!(
+                    "
+■┬ {}
+ ├─ 1:1  foo d
+ └─ 2:1  foo c",
+                    s.main_rs().display_absolute()
+                )
+                .trim()
+                .to_string(),
+            )),
+        ])
+    })
+}
+
+#[test]
+fn global_replace_open_buffers_only() -> Result<(), anyhow::Error> {
+    execute_test(|s| {
+        let foo_rs = s.foo_rs();
+        let foo_rs_content = foo_rs.read().unwrap();
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo d\nfoo c".to_string())),
+            App(UpdateGlobalSearchConfig {
+                update: GlobalSearchConfigUpdate::SetOpenBuffersOnly(true),
+            }),
+            App(UpdateLocalSearchConfig {
+                update: LocalSearchConfigUpdate::Search("foo".to_string()),
+                scope: Scope::Global,
+                show_config_after_enter: false,
+            }),
+            App(UpdateLocalSearchConfig {
+                update: LocalSearchConfigUpdate::Replacement("bar".to_string()),
+                scope: Scope::Global,
+                show_config_after_enter: false,
+            }),
+            App(Dispatch::Replace {
+                scope: Scope::Global,
+            }),
+            Expect(FileContent(s.main_rs(), "bar d\nbar c".to_string())),
+            // foo.rs is not opened, so it is not replaced
+            ExpectCustom(Box::new(move || {
+                assert_eq!(foo_rs.read().unwrap(), foo_rs_content)
+            })),
+        ])
+    })
+}
+
 #[test]
 fn quickfix_list_show_info_if_possible() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -1267,6 +1398,178 @@ fn main() {
     })
 }
 
+#[test]
+fn selection_to_quickfix() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let item = |line: usize, column: usize, text: &str| {
+            QuickfixListItem::new(
+                Location {
+                    path: s.main_rs(),
+                    range: Position { line, column }..Position {
+                        line,
+                        column: column + text.len(),
+                    },
+                },
+                Some(Info::new("Selection".to_string(), text.to_string())),
+            )
+        };
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo bar\nspam".to_string())),
+            Editor(SetSelectionMode(WordShort)),
+            Editor(CursorAddToAllSelections),
+            Editor(SelectionToQuickfix),
+            // The quickfix list remains after the cursors are collapsed
+            Editor(CursorKeepPrimaryOnly),
+            Expect(Quickfixes(Box::new([
+                item(0, 0, "foo"),
+                item(0, 4, "bar"),
+                item(1, 0, "spam"),
+            ]))),
+        ])
+    })
+}
+
+#[test]
+fn quickfix_do() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let main_rs = s.main_rs();
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "fn main() {\n    let x = [1 + 2, 3 * 4];\n}\n".to_string(),
+            )),
+            App(SetQuickfixList(
+                crate::quickfix_list::QuickfixListType::Items(
+                    [
+                        Position {
+                            line: 1,
+                            column: 13,
+                        }..Position {
+                            line: 1,
+                            column: 18,
+                        },
+                        Position {
+                            line: 1,
+                            column: 20,
+                        }..Position {
+                            line: 1,
+                            column: 25,
+                        },
+                    ]
+                    .into_iter()
+                    .map(|range| {
+                        QuickfixListItem::new(
+                            Location {
+                                path: s.main_rs(),
+                                range,
+                            },
+                            None,
+                        )
+                    })
+                    .collect(),
+                ),
+            )),
+            App(QuickfixDo(EvaluateSelection)),
+            // The later item is evaluated first, so the earlier edit does not shift its location
+            Expect(CurrentComponentContent(
+                "fn main() {\n    let x = [3, 12];\n}\n",
+            )),
+            // Expect the file to be saved
+            ExpectCustom(Box::new(move || {
+                assert_eq!(
+                    main_rs.read().unwrap(),
+                    "fn main() {\n    let x = [3, 12];\n}\n"
+                )
+            })),
+        ])
+    })
+}
+
+#[test]
+fn run_task_command() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(RunTaskCommand(crate::task::Task {
+                name: "check".to_string(),
+                command: "printf 'compiling\\nsrc/main.rs:1:4: oops\\n'".to_string(),
+                error_pattern: None,
+            })),
+            App(HandleNextAppMessage),
+            Expect(Quickfixes(Box::new([QuickfixListItem::new(
+                Location {
+                    path: s.main_rs(),
+                    range: Position { line: 0, column: 3 }..Position { line: 0, column: 3 },
+                },
+                Some(Info::new("Error".to_string(), "oops".to_string())),
+            )]))),
+            // Expect the first error is focused
+            Expect(CurrentComponentPath(Some(s.main_rs()))),
+        ])
+    })
+}
+
+#[test]
+fn run_task_command_reuses_output_buffer() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let task = |output: &str| crate::task::Task {
+            name: "greet".to_string(),
+            command: format!("printf '{output}'"),
+            error_pattern: None,
+        };
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(RunTaskCommand(task("hello"))),
+            App(HandleNextAppMessage),
+            Expect(CurrentComponentTitle("[Task: greet]")),
+            Expect(CurrentComponentContent("hello")),
+            App(OpenFile(s.main_rs())),
+            App(RunTaskCommand(task("bye"))),
+            App(HandleNextAppMessage),
+            Expect(CurrentComponentTitle("[Task: greet]")),
+            Expect(CurrentComponentContent("bye")),
+            Expect(ScratchEditorsCount(1)),
+        ])
+    })
+}
+
+#[test]
+fn parse_errors_to_quickfix() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenScratch {
+                extension: "txt".to_string(),
+            }),
+            Editor(SetContent(
+                "error: oops\n --> src/foo.rs:2:3\nsrc/unknown.rs:1:1".to_string(),
+            )),
+            App(ParseErrorsToQuickfix { pattern: None }),
+            Expect(Quickfixes(Box::new([QuickfixListItem::new(
+                Location {
+                    path: s.foo_rs(),
+                    range: Position { line: 1, column: 2 }..Position { line: 1, column: 2 },
+                },
+                None,
+            )]))),
+            // A custom pattern
+            App(OpenScratch {
+                extension: "txt".to_string(),
+            }),
+            Editor(SetContent("at src/main.rs line 3".to_string())),
+            App(ParseErrorsToQuickfix {
+                pattern: Some(r"at (?P<path>\S+) line (?P<line>\d+)".to_string()),
+            }),
+            Expect(Quickfixes(Box::new([QuickfixListItem::new(
+                Location {
+                    path: s.main_rs(),
+                    range: Position { line: 2, column: 0 }..Position { line: 2, column: 0 },
+                },
+                None,
+            )]))),
+        ])
+    })
+}
+
 #[test]
 fn diagnostic_info() -> Result<(), anyhow::Error> {
     execute_test(|s| {
@@ -1431,6 +1734,7 @@ fn code_action() -> anyhow::Result<()> {
                 resource_operations: Vec::new(),
             }),
             command: None,
+            unresolved: None,
         };
         Box::new([
             App(OpenFile(s.main_rs())),
@@ -1444,6 +1748,95 @@ fn code_action() -> anyhow::Result<()> {
     })
 }
 
+#[test]
+fn code_action_resolve() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let unresolved: CodeAction = lsp_types::CodeAction {
+            title: "Use to_string".to_string(),
+            kind: Some(lsp_types::CodeActionKind::QUICKFIX),
+            ..Default::default()
+        }
+        .try_into()
+        .unwrap();
+        let resolved = CodeAction {
+            edit: Some(WorkspaceEdit {
+                edits: [TextDocumentEdit {
+                    path: s.main_rs(),
+                    edits: [PositionalEdit {
+                        range: Position::new(0, 2)..Position::new(0, 6),
+                        new_text: "to_string".to_string(),
+                    }]
+                    .to_vec(),
+                }]
+                .to_vec(),
+                resource_operations: Vec::new(),
+            }),
+            unresolved: None,
+            ..unresolved.clone()
+        };
+        assert!(unresolved.unresolved.is_some());
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("a.to_s".to_string())),
+            App(ReceiveCodeActions([unresolved].to_vec())),
+            App(HandleKeyEvents(keys!("s t r i n g enter").to_vec())),
+            // The edit is only applied when the resolved code action is received
+            Expect(CurrentComponentContent("a.to_s")),
+            App(HandleLspNotification(LspNotification::CodeActionResolve(
+                resolved,
+            ))),
+            Expect(CurrentComponentContent("a.to_string")),
+        ])
+    })
+}
+
+#[test]
+fn organize_imports() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let code_action = |kind: &str| CodeAction {
+            title: "Organize imports".to_string(),
+            kind: Some(kind.to_string()),
+            edit: Some(WorkspaceEdit {
+                edits: [TextDocumentEdit {
+                    path: s.main_rs(),
+                    edits: [PositionalEdit {
+                        range: Position::new(0, 0)..Position::new(1, 0),
+                        new_text: "".to_string(),
+                    }]
+                    .to_vec(),
+                }]
+                .to_vec(),
+                resource_operations: Vec::new(),
+            }),
+            command: None,
+            unresolved: None,
+        };
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("use std::io;\nuse std::fmt;\n".to_string())),
+            // Fallback to sorting imports when there is no language server
+            App(OrganizeImports),
+            Expect(CurrentComponentContent("use std::fmt;\nuse std::io;\n")),
+            // Fallback to sorting imports when no organize imports action is offered
+            Editor(SetContent("use std::io;\nuse std::fmt;\n".to_string())),
+            App(HandleLspNotification(LspNotification::OrganizeImports(
+                [code_action("quickfix")].to_vec(),
+            ))),
+            Expect(CurrentComponentContent("use std::fmt;\nuse std::io;\n")),
+            // Apply the organize imports action without opening the code actions prompt
+            App(HandleLspNotification(LspNotification::OrganizeImports(
+                [
+                    code_action("quickfix"),
+                    code_action("source.organizeImports"),
+                ]
+                .to_vec(),
+            ))),
+            Expect(CurrentComponentContent("use std::io;\n")),
+            Expect(ComponentCount(1)),
+        ])
+    })
+}
+
 #[test]
 fn opening_new_file_should_replace_current_window() -> anyhow::Result<()> {
     execute_test(|s| {
@@ -1947,3 +2340,280 @@ c1 c2 a1\nb1\nc1
         }
     })
 }
+
+#[test]
+fn convert_encoding_round_trip() -> anyhow::Result<()> {
+    use crate::encoding::Encoding;
+    run_test(|mut app, temp_dir| {
+        // The gitignore file is used to avoid formatting on save
+        let path = temp_dir.join(".gitignore")?;
+        let current_encoding = |app: &App<MockFrontend>| {
+            app.current_component()
+                .borrow()
+                .editor()
+                .buffer()
+                .encoding()
+        };
+        app.handle_dispatch(OpenFile(path.clone()))?;
+        app.handle_dispatch_editor(SetContent("héllo\n".to_string()))?;
+
+        app.handle_dispatch_editor(ConvertEncoding(Encoding::Utf16Le))?;
+        assert_eq!(std::fs::read(&path)?, Encoding::Utf16Le.encode("héllo\n")?);
+        assert_eq!(current_encoding(&app), Encoding::Utf16Le);
+
+        app.handle_dispatch_editor(ConvertEncoding(Encoding::Utf8))?;
+        assert_eq!(std::fs::read(&path)?, "héllo\n".as_bytes());
+        assert_eq!(current_encoding(&app), Encoding::Utf8);
+
+        // The previous encoding is restored if the content cannot be saved in the new encoding
+        app.handle_dispatch_editor(SetContent("🦀\n".to_string()))?;
+        assert!(app
+            .handle_dispatch_editor(ConvertEncoding(Encoding::Latin1))
+            .is_err());
+        assert_eq!(current_encoding(&app), Encoding::Utf8);
+        assert_eq!(std::fs::read(&path)?, "héllo\n".as_bytes());
+        Ok(())
+    })
+}
+
+#[test]
+fn headless_run_keys() -> anyhow::Result<()> {
+    run_test(|mut app, temp_dir| {
+        let path = temp_dir.join("src/main.rs")?;
+        let original_content = path.read()?;
+
+        let content = crate::headless::run_keys(&mut app, &path, "i h e l l o esc")?;
+        assert_eq!(content, format!("hello{}", original_content));
+
+        // The file is not written unless the keys save it
+        assert_eq!(path.read()?, original_content);
+
+        assert!(crate::headless::run_keys(&mut app, &path, "i unknown+x").is_err());
+        Ok(())
+    })
+}
+
+#[test]
+fn search_preset() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let update = |update: LocalSearchConfigUpdate| -> Dispatch {
+            UpdateLocalSearchConfig {
+                update,
+                scope: Scope::Local,
+                show_config_after_enter: false,
+            }
+        };
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo bar".to_string())),
+            App(update(LocalSearchConfigUpdate::Mode(
+                LocalSearchConfigMode::Regex(RegexConfig {
+                    escaped: true,
+                    case_sensitive: false,
+                    match_whole_word: false,
+                    multiline: false,
+                }),
+            ))),
+            App(update(LocalSearchConfigUpdate::Search("foo".to_string()))),
+            App(update(LocalSearchConfigUpdate::Replacement(
+                "baz".to_string(),
+            ))),
+            App(SaveSearchPreset {
+                scope: Scope::Local,
+                name: "foo2baz".to_string(),
+            }),
+            App(update(LocalSearchConfigUpdate::Search("bar".to_string()))),
+            App(update(LocalSearchConfigUpdate::Replacement(
+                "x".to_string(),
+            ))),
+            Expect(CurrentSelectedTexts(&["bar"])),
+            App(OpenSearchPresetPrompt {
+                scope: Scope::Local,
+            }),
+            App(HandleKeyEvents(keys!("f o o 2 enter").to_vec())),
+            Expect(CurrentSelectedTexts(&["foo"])),
+            Editor(ReplaceWithPattern),
+            Expect(CurrentComponentContent("baz bar")),
+        ])
+    })
+}
+
+#[test]
+fn search_preset_clears_globs_of_previous_preset() -> anyhow::Result<()> {
+    execute_test(|_| {
+        Box::new([
+            App(UpdateLocalSearchConfig {
+                update: LocalSearchConfigUpdate::Search("foo".to_string()),
+                scope: Scope::Global,
+                show_config_after_enter: false,
+            }),
+            App(SaveSearchPreset {
+                scope: Scope::Global,
+                name: "foo".to_string(),
+            }),
+            App(UpdateGlobalSearchConfig {
+                update: GlobalSearchConfigUpdate::SetGlob(
+                    GlobalSearchFilterGlob::Include,
+                    "**/*.spam".to_string(),
+                ),
+            }),
+            App(OpenSearchPresetPrompt {
+                scope: Scope::Global,
+            }),
+            App(HandleKeyEvents(keys!("f o o enter").to_vec())),
+            // The preset has no include glob, so the previous one is cleared
+            Expect(Not(Box::new(AppGridContains("**/*.spam")))),
+        ])
+    })
+}
+
+#[test]
+fn local_replace_keeping_replacements_selected() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let update = |update: LocalSearchConfigUpdate| -> Dispatch {
+            UpdateLocalSearchConfig {
+                update,
+                scope: Scope::Local,
+                show_config_after_enter: false,
+            }
+        };
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent("foo x fooo\nbar foo".to_string())),
+            App(update(LocalSearchConfigUpdate::Mode(
+                LocalSearchConfigMode::Regex(RegexConfig {
+                    escaped: false,
+                    case_sensitive: false,
+                    match_whole_word: false,
+                    multiline: false,
+                }),
+            ))),
+            App(update(LocalSearchConfigUpdate::Search("f(o+)".to_string()))),
+            App(update(LocalSearchConfigUpdate::Replacement(
+                "b${1}m".to_string(),
+            ))),
+            App(update(LocalSearchConfigUpdate::SelectReplacements(true))),
+            App(Dispatch::Replace {
+                scope: Scope::Local,
+            }),
+            Expect(CurrentComponentContent("boom x booom\nbar boom")),
+            Expect(CurrentSelectedTexts(&["boom", "booom", "boom"])),
+        ])
+    })
+}
+
+#[test]
+fn open_recent_file() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.foo_rs())),
+            App(OpenFile(s.main_rs())),
+            App(OpenFile(s.foo_rs())),
+            App(OpenFile(s.main_rs())),
+            App(OpenFilePicker(FilePickerKind::Recent)),
+            // The files are deduplicated and ordered by recency,
+            // so the second item is `foo.rs`
+            App(HandleKeyEvents(keys!("ctrl+n enter").to_vec())),
+            Expect(CurrentComponentPath(Some(s.foo_rs()))),
+        ])
+    })
+}
+
+#[test]
+fn scratch_buffers() -> anyhow::Result<()> {
+    execute_test(|s| {
+        let path = s.new_path("src/scratch.rs");
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(OpenScratch {
+                extension: "rs".to_string(),
+            }),
+            Expect(CurrentComponentTitle("[Scratch 1]")),
+            Expect(CurrentComponentPath(None)),
+            Editor(SetContent("fn scratch() {}".to_string())),
+            App(OpenScratch {
+                extension: "txt".to_string(),
+            }),
+            Expect(CurrentComponentTitle("[Scratch 2]")),
+            Expect(CurrentComponentContent("")),
+            // Scratch buffers are listed among the opened files
+            App(OpenFilePicker(FilePickerKind::Opened)),
+            App(HandleKeyEvents(keys!("1 enter").to_vec())),
+            Expect(CurrentComponentContent("fn scratch() {}")),
+            // Saving a scratch buffer prompts for its path
+            Editor(Save),
+            Editor(Insert(path.display().to_string())),
+            App(HandleKeyEvents(keys!("enter").to_vec())),
+            ExpectLater(Box::new(move || {
+                CurrentComponentPath(Some(path.clone().try_into().unwrap()))
+            })),
+            Expect(CurrentComponentContent("fn scratch() {}\n")),
+            // The numbers of the scratch buffers are never reused
+            App(OpenScratch {
+                extension: "txt".to_string(),
+            }),
+            Expect(CurrentComponentTitle("[Scratch 3]")),
+        ])
+    })
+}
+
+#[test]
+fn set_large_file_threshold() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(OpenLargeFileThresholdPrompt),
+            Editor(Insert("80".to_string())),
+            App(HandleKeyEvents(keys!("enter").to_vec())),
+            // main.rs has 71 bytes
+            App(OpenFile(s.main_rs())),
+            Expect(Not(Box::new(AppGridContains("[LARGE FILE]")))),
+            // foo.rs has 105 bytes
+            App(OpenFile(s.foo_rs())),
+            Expect(AppGridContains("[LARGE FILE]")),
+        ])
+    })
+}
+
+#[test]
+fn set_incremental_reparse_threshold() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            App(OpenIncrementalReparseThresholdPrompt),
+            Editor(Insert("10".to_string())),
+            App(HandleKeyEvents(keys!("enter").to_vec())),
+            App(OpenIncrementalReparseThresholdPrompt),
+            Expect(CurrentComponentTitle(
+                "Incremental reparse threshold in bytes (currently 10)",
+            )),
+        ])
+    })
+}
+
+#[test]
+fn list_todos() -> anyhow::Result<()> {
+    execute_test(|s| {
+        Box::new([
+            App(OpenFile(s.main_rs())),
+            Editor(SetContent(
+                "fn main() {} // TODO: do it\n// FIXME broken\n// TODOS are not tags".to_string(),
+            )),
+            App(SaveAll),
+            App(ListTodos),
+            Expect(QuickfixListContent(
+                format!(
+                    "
+■┬ {}
+ ├─ 1:17  fn main() {{}} // TODO: do it
+ └─ 2:4  // FIXME broken",
+                    s.main_rs().display_absolute()
+                )
+                .trim()
+                .to_string(),
+            )),
+            Expect(QuickfixListInfo("TODO: do it")),
+            Expect(CurrentSelectedTexts(&["TODO"])),
+        ])
+    })
+}
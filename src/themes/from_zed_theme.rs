@@ -147,6 +147,12 @@ pub fn from_zed_theme(url: &str) -> anyhow::Result<Vec<Theme>> {
                         .background_color(background),
                     bookmark: Style::new()
                         .set_some_background_color(from_some_hex(theme.style.conflict_background)),
+                    yank_flash: Style::new().set_some_background_color(from_some_hex(
+                        theme.style.search_match_background.clone(),
+                    )),
+                    document_highlight: Style::new().set_some_background_color(from_some_hex(
+                        theme.style.editor_document_highlight_read_background,
+                    )),
                     possible_selection_background: from_some_hex(
                         theme.style.search_match_background,
                     )
@@ -159,6 +165,10 @@ pub fn from_zed_theme(url: &str) -> anyhow::Result<Vec<Theme>> {
                     fuzzy_matched_char: Style::new()
                         .foreground_color(text_accent)
                         .underline(text_accent),
+                    indent_guide: Style::new()
+                        .foreground_color(text_color.apply_custom_alpha(background, 0.15)),
+                    indent_guide_active: Style::new()
+                        .foreground_color(text_color.apply_custom_alpha(background, 0.4)),
                 },
                 diagnostic: {
                     let default = DiagnosticStyles::default();
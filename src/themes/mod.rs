@@ -87,6 +87,10 @@ impl Theme {
             StyleKey::KeymapKey => self.ui.keymap_key,
             StyleKey::UiFuzzyMatchedChar => self.ui.fuzzy_matched_char,
             StyleKey::ParentLine => Style::new().background_color(self.ui.parent_lines_background),
+            StyleKey::IndentGuide => self.ui.indent_guide,
+            StyleKey::IndentGuideActive => self.ui.indent_guide_active,
+            StyleKey::YankFlash => self.ui.yank_flash,
+            StyleKey::DocumentHighlight => self.ui.document_highlight,
         }
     }
 }
@@ -143,6 +147,10 @@ pub(crate) struct UiStyles {
     pub(crate) keymap_key: Style,
     pub(crate) keymap_arrow: Style,
     pub(crate) keymap_hint: Style,
+    pub(crate) indent_guide: Style,
+    pub(crate) indent_guide_active: Style,
+    pub(crate) yank_flash: Style,
+    pub(crate) document_highlight: Style,
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Default)]
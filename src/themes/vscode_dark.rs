@@ -65,6 +65,10 @@ pub fn vscode_dark() -> Theme {
             keymap_key: Style::new().bold().foreground_color(hex!("#af00db")),
             keymap_arrow: Style::new().foreground_color(hex!("#808080")),
             fuzzy_matched_char: Style::new().foreground_color(hex!("#55A8F8")),
+            indent_guide: Style::new().foreground_color(hex!("#404040")),
+            indent_guide_active: Style::new().foreground_color(hex!("#707070")),
+            yank_flash: Style::new().background_color(hex!("#7A5A12")),
+            document_highlight: Style::new().background_color(hex!("#3A3D41")),
         },
         diagnostic: DiagnosticStyles::default(),
         hunk: super::HunkStyles::dark(),
@@ -65,6 +65,10 @@ pub fn vscode_light() -> Theme {
             keymap_key: Style::new().bold().foreground_color(hex!("#af00db")),
             keymap_arrow: Style::new().foreground_color(hex!("#808080")),
             fuzzy_matched_char: Style::new().foreground_color(hex!("#ff0000")),
+            indent_guide: Style::new().foreground_color(hex!("#d3d3d3")),
+            indent_guide_active: Style::new().foreground_color(hex!("#939393")),
+            yank_flash: Style::new().background_color(hex!("#FFE08A")),
+            document_highlight: Style::new().background_color(hex!("#E6E6E6")),
         },
         diagnostic: DiagnosticStyles::default(),
         hunk: super::HunkStyles::light(),
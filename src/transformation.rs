@@ -1,15 +1,29 @@
 use convert_case::Casing;
 
-use crate::soft_wrap::soft_wrap;
+use crate::{char_info::CharacterReplacement, grid::get_string_width, soft_wrap::soft_wrap};
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub(crate) enum Transformation {
     Case(convert_case::Case),
     Join,
     Wrap,
+    /// Add the given levels of indentation to every non-empty line.
+    Indent(usize),
+    /// Remove at most the given levels of indentation from every line.
+    Dedent(usize),
+    /// Rewrap the prose of a comment at the given width, keeping its comment markers intact.
+    ReflowComment(usize),
+    /// Collapse consecutive blank lines into one blank line,
+    /// and if `inline` is true, also collapse consecutive spaces and tabs after the indentation into one space.
+    CollapseWhitespace {
+        inline: bool,
+    },
+    /// Replace every occurrence of each character with its replacement.
+    ReplaceCharacters(Vec<CharacterReplacement>),
 }
 impl Transformation {
-    pub(crate) fn apply(&self, string: String) -> String {
+    /// `indent_width` is the number of spaces of one indentation level.
+    pub(crate) fn apply(&self, string: String, indent_width: usize) -> String {
         match self {
             Transformation::Case(case) => string.to_case(*case),
             Transformation::Join => regex::Regex::new(r"\s*\n+\s*")
@@ -17,10 +31,270 @@ impl Transformation {
                 .replace_all(&string, " ")
                 .to_string(),
             Transformation::Wrap => soft_wrap(&string, 80).to_string(),
+            Transformation::Indent(levels) => {
+                let indentation = " ".repeat(indent_width * levels);
+                string
+                    .split_inclusive('\n')
+                    .map(|line| {
+                        if line.trim().is_empty() {
+                            line.to_string()
+                        } else {
+                            format!("{}{}", indentation, line)
+                        }
+                    })
+                    .collect()
+            }
+            Transformation::Dedent(levels) => string
+                .split_inclusive('\n')
+                .map(|line| {
+                    let max_spaces = indent_width * levels;
+                    let removed_tabs = line.chars().take_while(|c| *c == '\t').take(*levels);
+                    let removed = match removed_tabs.count() {
+                        0 => line
+                            .chars()
+                            .take_while(|c| *c == ' ')
+                            .take(max_spaces)
+                            .count(),
+                        count => count,
+                    };
+                    line[removed..].to_string()
+                })
+                .collect(),
+            Transformation::ReflowComment(width) => reflow_comment(&string, *width),
+            Transformation::CollapseWhitespace { inline } => collapse_whitespace(&string, *inline),
+            Transformation::ReplaceCharacters(replacements) => string
+                .chars()
+                .map(|char| {
+                    replacements
+                        .iter()
+                        .find(|replacement| replacement.char == char)
+                        .map(|replacement| replacement.replacement.clone())
+                        .unwrap_or_else(|| char.to_string())
+                })
+                .collect(),
         }
     }
 }
 
+/// A run of comment lines that are rewrapped together.
+struct Paragraph<'a> {
+    /// The comment marker, including its indentation, for example `    ///`.
+    prefix: &'a str,
+    /// The indentation and the marker of a markdown list item, for example `  - `.
+    head: String,
+    /// The indentation of the lines after the first line of a markdown list item.
+    hanging: usize,
+    words: Vec<&'a str>,
+}
+
+impl Paragraph<'_> {
+    fn render(&self, width: usize) -> Vec<String> {
+        let continuation = format!("{} {}", self.prefix, " ".repeat(self.hanging));
+        let mut lines = Vec::new();
+        let mut current = format!("{} {}", self.prefix, self.head);
+        let mut is_empty = true;
+        for word in &self.words {
+            if !is_empty && get_string_width(&current) + 1 + get_string_width(word) > width {
+                lines.push(std::mem::replace(&mut current, continuation.clone()));
+                is_empty = true;
+            }
+            if !is_empty {
+                current.push(' ')
+            }
+            current.push_str(word);
+            is_empty = false;
+        }
+        lines.push(current);
+        lines
+    }
+}
+
+fn collapse_whitespace(string: &str, inline: bool) -> String {
+    let whitespaces_regex = regex::Regex::new(r"[ \t]{2,}").unwrap();
+    let mut previous_line_is_blank = false;
+    string
+        .split_inclusive('\n')
+        .filter_map(|line| {
+            let is_blank = line.trim().is_empty();
+            if is_blank && previous_line_is_blank {
+                return None;
+            }
+            previous_line_is_blank = is_blank;
+            if !inline || is_blank {
+                return Some(line.to_string());
+            }
+            let (indentation, rest) =
+                line.split_at(line.len() - line.trim_start_matches([' ', '\t']).len());
+            Some(format!(
+                "{}{}",
+                indentation,
+                whitespaces_regex.replace_all(rest, " ")
+            ))
+        })
+        .collect()
+}
+
+/// Reindent `string` to `indentation`, so that text copied from a different indentation level
+/// fits where it is pasted.
+///
+/// If the paste starts at column 0, every line is shifted by the same amount,
+/// such that the first non-blank line is indented with `indentation`,
+/// so that continuation lines, such as the `a)` of `foo(\n    a)`, keep their indentation relative to it.
+///
+/// Otherwise, the first line is untouched, because it continues the line it is pasted into,
+/// and the common indentation of the remaining lines is replaced with `indentation`.
+pub(crate) fn reindent(string: &str, indentation: &str, starts_at_column_0: bool) -> String {
+    let indentation_width = |line: &str| line.len() - line.trim_start_matches([' ', '\t']).len();
+    let reindent_line = |line: &str, old_indentation_width: usize| {
+        if line.trim().is_empty() {
+            line[indentation_width(line)..].to_string()
+        } else {
+            let old_indentation_width = old_indentation_width.min(indentation_width(line));
+            format!("{}{}", indentation, &line[old_indentation_width..])
+        }
+    };
+    if starts_at_column_0 {
+        let first_line_indentation_width = string
+            .split_inclusive('\n')
+            .find(|line| !line.trim().is_empty())
+            .map(indentation_width)
+            .unwrap_or(0);
+        return string
+            .split_inclusive('\n')
+            .map(|line| reindent_line(line, first_line_indentation_width))
+            .collect();
+    }
+    let mut lines = string.split_inclusive('\n');
+    let Some(first_line) = lines.next() else {
+        return String::new();
+    };
+    let lines = lines.collect::<Vec<_>>();
+    let common_indentation_width = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| indentation_width(line))
+        .min()
+        .unwrap_or(0);
+    Some(first_line.to_string())
+        .into_iter()
+        .chain(
+            lines
+                .into_iter()
+                .map(|line| reindent_line(line, common_indentation_width)),
+        )
+        .collect()
+}
+
+/// Rewrap the prose of line comments (`//`, `///`, `#`, `--`, etc.),
+/// and of the ` * ` lines of block comments, at `width`.
+///
+/// The indentation of markdown list items is preserved,
+/// while blank comment lines, fenced code blocks and non-comment lines are left untouched.
+///
+/// To not mistake code such as `*x = 5;` or `#[derive(Debug)]` for comments,
+/// `#` and `*` must be followed by a whitespace or the end of the line,
+/// and `*` lines must follow a block comment opener (`/*`).
+fn reflow_comment(string: &str, width: usize) -> String {
+    let comment_regex = regex::Regex::new(r"^(\s*(?://[/!]?|#+|--|;+|\*)) ?(.*)$").unwrap();
+    let list_item_regex = regex::Regex::new(r"^(?:[-*+]|\d+[.)])\s+").unwrap();
+    let mut lines: Vec<String> = Vec::new();
+    let mut paragraph: Option<Paragraph> = None;
+    let mut in_code_block = false;
+    let mut in_block_comment = false;
+    for line in string.lines() {
+        let trimmed = line.trim_start();
+        let is_block_comment_end = trimmed.starts_with("*/");
+        let is_within_block_comment = in_block_comment && !is_block_comment_end;
+        if let Some(rest) = trimmed.strip_prefix("/*") {
+            in_block_comment = !rest.contains("*/")
+        } else if line.contains("*/") {
+            in_block_comment = false
+        }
+        let Some(captures) = comment_regex
+            .captures(line)
+            .filter(|_| !is_block_comment_end)
+            .filter(|captures| {
+                let Some(prefix) = captures.get(1) else {
+                    return false;
+                };
+                let is_separated = line[prefix.end()..]
+                    .chars()
+                    .next()
+                    .map_or(true, char::is_whitespace);
+                match prefix.as_str().chars().last() {
+                    Some('*') => is_within_block_comment && is_separated,
+                    Some('#') => is_separated,
+                    _ => true,
+                }
+            })
+        else {
+            lines.extend(
+                paragraph
+                    .take()
+                    .map(|paragraph| paragraph.render(width))
+                    .into_iter()
+                    .flatten(),
+            );
+            lines.push(line.to_string());
+            continue;
+        };
+        let (Some(prefix), Some(text)) = (captures.get(1), captures.get(2)) else {
+            continue;
+        };
+        let (prefix, text) = (prefix.as_str(), text.as_str());
+        if text.trim_start().starts_with("```") {
+            in_code_block = !in_code_block;
+        }
+        if in_code_block || text.trim_start().starts_with("```") || text.trim().is_empty() {
+            lines.extend(
+                paragraph
+                    .take()
+                    .map(|paragraph| paragraph.render(width))
+                    .into_iter()
+                    .flatten(),
+            );
+            lines.push(line.to_string());
+            continue;
+        }
+        let body = text.trim_start();
+        let indentation = text.len() - body.len();
+        match (list_item_regex.find(body), &mut paragraph) {
+            (None, Some(paragraph)) if paragraph.prefix == prefix => {
+                paragraph.words.extend(body.split_whitespace())
+            }
+            (marker, _) => {
+                lines.extend(
+                    paragraph
+                        .take()
+                        .map(|paragraph| paragraph.render(width))
+                        .into_iter()
+                        .flatten(),
+                );
+                let marker = marker.map(|marker| marker.as_str()).unwrap_or_default();
+                paragraph = Some(Paragraph {
+                    prefix,
+                    head: format!("{}{}", " ".repeat(indentation), marker),
+                    hanging: indentation + marker.chars().count(),
+                    words: body[marker.len()..].split_whitespace().collect(),
+                })
+            }
+        }
+    }
+    lines.extend(
+        paragraph
+            .take()
+            .map(|paragraph| paragraph.render(width))
+            .into_iter()
+            .flatten(),
+    );
+    let result = lines.join("\n");
+    if string.ends_with('\n') {
+        result + "\n"
+    } else {
+        result
+    }
+}
+
 #[cfg(test)]
 mod test_transformation {
     use super::Transformation;
@@ -38,6 +312,7 @@ pineapple?
 "
             .trim()
             .to_string(),
+            4,
         );
         assert_eq!(result, "who lives in a pineapple?")
     }
@@ -48,7 +323,112 @@ pineapple?
             .apply("
 who lives in a pineapple under the sea? Spongebob Squarepants! absorbent and yellow and porous is he? Spongebob Squarepants
 "
-            .trim().to_string());
+            .trim().to_string(), 4);
         assert_eq!(result, "who lives in a pineapple under the sea? Spongebob Squarepants! absorbent and \nyellow and porous is he? Spongebob Squarepants")
     }
+
+    #[test]
+    fn indent() {
+        let result = Transformation::Indent(1).apply("a\n\n  b".to_string(), 2);
+        assert_eq!(result, "  a\n\n    b")
+    }
+
+    #[test]
+    fn reindent() {
+        let result = super::reindent("if a {\n        b();\n\n    }", "  ", false);
+        assert_eq!(result, "if a {\n      b();\n\n  }")
+    }
+
+    #[test]
+    fn reindent_from_column_0() {
+        // The first line carries its own old indentation
+        let result = super::reindent("    if a {\n        b();\n    }\n", "  ", true);
+        assert_eq!(result, "  if a {\n      b();\n  }\n");
+
+        // Continuation lines keep their indentation relative to the first line
+        let result = super::reindent("foo(\n    a)", "  ", true);
+        assert_eq!(result, "  foo(\n      a)");
+    }
+
+    #[test]
+    fn reflow_comment() {
+        let result = Transformation::ReflowComment(30).apply(
+            "
+    /// Who lives in a pineapple under the sea?
+    /// Spongebob Squarepants!
+    ///
+    /// - absorbent and yellow and porous is he
+    ///   Spongebob
+    /// ```
+    /// let long_line_of_code_is_untouched = true;
+    /// ```
+    fn f() {}
+"
+            .trim_start_matches('\n')
+            .to_string(),
+            4,
+        );
+        assert_eq!(
+            result,
+            "
+    /// Who lives in a
+    /// pineapple under the
+    /// sea? Spongebob
+    /// Squarepants!
+    ///
+    /// - absorbent and yellow
+    ///   and porous is he
+    ///   Spongebob
+    /// ```
+    /// let long_line_of_code_is_untouched = true;
+    /// ```
+    fn f() {}
+"
+            .trim_start_matches('\n')
+        )
+    }
+
+    #[test]
+    fn reflow_block_comment() {
+        let result = Transformation::ReflowComment(20)
+            .apply("/**\n * foo\n * bar baz qux spam\n */".to_string(), 4);
+        assert_eq!(result, "/**\n * foo bar baz qux\n * spam\n */")
+    }
+
+    #[test]
+    fn reflow_comment_should_leave_code_untouched() {
+        let code = "#[derive(Debug)]\n*x = 5;\n* y = 6;\n#!/bin/sh";
+        let result = Transformation::ReflowComment(20).apply(code.to_string(), 4);
+        assert_eq!(result, code)
+    }
+
+    #[test]
+    fn collapse_whitespace() {
+        let string = "a  b\n\n  \n\n    c\t\td\n\n".to_string();
+        assert_eq!(
+            Transformation::CollapseWhitespace { inline: false }.apply(string.clone(), 4),
+            "a  b\n\n    c\t\td\n\n"
+        );
+        assert_eq!(
+            Transformation::CollapseWhitespace { inline: true }.apply(string, 4),
+            "a b\n\n    c d\n\n"
+        );
+    }
+
+    #[test]
+    fn replace_characters() {
+        let result =
+            Transformation::ReplaceCharacters(crate::char_info::CharacterReplacement::defaults())
+                .apply(
+                    "\u{201C}a\u{00A0}b\u{200B}\u{2014}it\u{2019}s\u{2026}\u{201D}".to_string(),
+                    4,
+                );
+        assert_eq!(result, "\"a b-it's...\"")
+    }
+
+    #[test]
+    fn dedent() {
+        let result = Transformation::Dedent(1).apply("    a\n  b\n\t\tc".to_string(), 4);
+        assert_eq!(result, "a\nb\n\tc")
+    }
 }
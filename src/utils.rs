@@ -30,3 +30,37 @@ pub(crate) fn consolidate_errors<T, E: std::fmt::Debug>(
         Err(anyhow::anyhow!("{}: {:?}", message, errors))
     }
 }
+
+/// Run `command` with `sh -c`, writing `input` to its stdin.
+///
+/// Returns `Ok(stdout)` if the command succeeded, otherwise `Err(stderr)`.
+pub(crate) fn run_shell_command(
+    command: &str,
+    input: &str,
+) -> anyhow::Result<Result<String, String>> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    {
+        // Write in another thread, otherwise a command that prints before reading
+        // its whole stdin could block forever on a full stdout pipe
+        let mut stdin = child
+            .stdin
+            .take()
+            .ok_or_else(|| anyhow::anyhow!("Failed to open the stdin of `{}`", command))?;
+        let input = input.to_string();
+        std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    }
+    let output = child.wait_with_output()?;
+    if output.status.success() {
+        Ok(Ok(String::from_utf8_lossy(&output.stdout).to_string()))
+    } else {
+        Ok(Err(String::from_utf8_lossy(&output.stderr).to_string()))
+    }
+}